@@ -0,0 +1,70 @@
+// The block cache (see blocks.rs), measured by running the same loop from ROM, where
+// fetches come out of the cache, and from WRAM, where every opcode is read and
+// looked up as it's fetched. The loop branches every few instructions, so the
+// cache has to find a block at a branch target about as often as it ever would.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use gameboy_core::{Emulator, Game};
+
+// Frames run before measuring, so the cache has everything decoded
+const WARM_UP_FRAMES: usize = 10;
+
+fn get_loop(base: u16) -> Vec<u8> {
+    // An inner loop of a few instructions, and a call out of the outer one, to
+    // run from base
+    let subroutine = base + 0x0C;
+    vec![
+        0x06, 0x0A,                                      // LD B, 10
+        0x7E,                                            // LD A, (HL)
+        0x81,                                            // ADD A, C
+        0x05,                                            // DEC B
+        0x20, 0xFB,                                      // JR NZ, -5
+        0xCD, subroutine as u8, (subroutine >> 8) as u8, // CALL subroutine
+        0x18, 0xF4,                                      // JR -12
+        0x0C,                                            // INC C
+        0xC9,                                            // RET
+    ]
+}
+
+fn get_rom(in_wram: bool) -> Vec<u8> {
+    // The loop goes at 0x200. Run from WRAM, it's put together for 0xC000 and
+    // copied there first
+    let mut rom = vec![0; 0x8000];
+    rom[0x100..0x103].copy_from_slice(&[0xC3, 0x50, 0x01]); // JP 0x150
+
+    let code = get_loop(if in_wram { 0xC000 } else { 0x200 });
+    rom[0x200..0x200 + code.len()].copy_from_slice(&code);
+
+    if in_wram {
+        rom[0x150..0x161].copy_from_slice(&[
+            0x21, 0x00, 0xC0, // LD HL, 0xC000
+            0x11, 0x00, 0x02, // LD DE, 0x200
+            0x1A,             // LD A, (DE)
+            0x22,             // LD (HL+), A
+            0x13,             // INC DE
+            0x7D,             // LD A, L
+            0xFE, 0x20,       // CP 0x20
+            0x20, 0xF8,       // JR NZ, -8
+            0xC3, 0x00, 0xC0, // JP 0xC000
+        ]);
+    } else {
+        rom[0x150..0x153].copy_from_slice(&[0xC3, 0x00, 0x02]); // JP 0x200
+    }
+
+    rom
+}
+
+fn run_frames(c: &mut Criterion) {
+    for (name, in_wram) in [("rom", false), ("wram", true)].iter() {
+        let mut emulator = Emulator::new(Game::from_bytes(&get_rom(*in_wram)));
+        emulator.set_idle_skipping(false);
+        for _ in 0..WARM_UP_FRAMES {
+            emulator.update();
+        }
+
+        c.bench_function(&format!("frame from {}", name), |b| b.iter(|| emulator.update()));
+    }
+}
+
+criterion_group!(benches, run_frames);
+criterion_main!(benches);
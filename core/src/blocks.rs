@@ -0,0 +1,105 @@
+// Block cache
+//
+// ROM can't be written to, so the code in it only changes when a different bank is
+// mapped in. Rather than fetching and looking up every opcode each time a loop
+// comes back around, runs of ROM code are decoded once into blocks - the opcode and
+// handler of each instruction, up to the next one that could send the PC somewhere
+// else. The Cpu then follows along through the current block, and only has to look
+// one up when the PC jumps, which is just an index into a table with a slot for each
+// ROM address. Blocks are tagged with the ROM mapping they were decoded from, so a
+// bank switch leaves the old ones behind without clearing 32K slots.
+
+use super::mmu;
+use super::ops;
+
+use std::rc::Rc;
+
+// Slots in the table, one for each address in ROM
+const ROM_SIZE: usize = 0x8000;
+
+// Most instructions decoded into a single block
+const MAX_BLOCK_LENGTH: usize = 64;
+
+struct DecodedOp {
+    pc: u16,
+    opcode: u8,
+    handler: &'static ops::OpHandler,
+}
+
+pub struct BlockCache {
+    // The block starting at each address, and the Mmu's ROM mapping version it
+    // was decoded with
+    blocks: Vec<Option<(u32, Rc<Vec<DecodedOp>>)>>,
+
+    // The ROM mapping version the current block belongs to
+    version: u32,
+
+    // The block being run and how far into it the last fetch was
+    current: Option<(Rc<Vec<DecodedOp>>, usize)>,
+}
+
+impl BlockCache {
+    pub fn new() -> BlockCache {
+        BlockCache {
+            blocks: vec![None; ROM_SIZE],
+            version: 0,
+            current: None,
+        }
+    }
+
+    pub fn fetch(&mut self, mmu: &mmu::Mmu, pc: u16) -> Option<(u8, &'static ops::OpHandler)> {
+        // The opcode at pc and its handler, or None if pc isn't in ROM and the
+        // opcode has to be fetched normally
+        if pc as usize >= ROM_SIZE {
+            self.current = None;
+            return None;
+        }
+
+        let version = mmu.get_rom_mapping_version();
+        if version != self.version {
+            self.version = version;
+            self.current = None;
+        }
+
+        // Most of the time the PC has just moved onto the next op in the block
+        if let Some((block, index)) = self.current.as_mut() {
+            if let Some(op) = block.get(*index + 1) {
+                if op.pc == pc {
+                    *index += 1;
+                    return Some((op.opcode, op.handler));
+                }
+            }
+        }
+
+        let block = match &self.blocks[pc as usize] {
+            Some((decoded_with, block)) if *decoded_with == version => block.clone(),
+            _ => {
+                let block = Rc::new(decode_block(mmu, pc));
+                self.blocks[pc as usize] = Some((version, block.clone()));
+                block
+            }
+        };
+
+        let op = &block[0];
+        let decoded = (op.opcode, op.handler);
+        self.current = Some((block, 0));
+
+        Some(decoded)
+    }
+}
+
+fn decode_block(mmu: &mmu::Mmu, start: u16) -> Vec<DecodedOp> {
+    let mut ops = Vec::new();
+    let mut pc = start;
+
+    loop {
+        let opcode = mmu.peek_memory(&(pc as usize));
+        let handler = ops::get_handler(opcode);
+        ops.push(DecodedOp { pc, opcode, handler });
+
+        pc += handler.length;
+        if handler.ends_block || pc as usize >= ROM_SIZE || ops.len() == MAX_BLOCK_LENGTH {
+            return ops;
+        }
+    }
+}
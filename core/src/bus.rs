@@ -0,0 +1,76 @@
+use super::mmu;
+
+// Bus
+//
+// Everything the CPU does to the outside world comes down to reading a byte,
+// writing a byte, or letting time pass. Normally that's the Mmu and the hardware
+// behind it, but a Cpu can be given any other Bus to run against instead (see
+// Cpu::set_bus), so instructions can be run without a cartridge or any IO
+// registers getting in the way.
+
+pub trait Bus {
+    fn read8(&mut self, address: &usize) -> u8;
+    fn write8(&mut self, address: &usize, data: u8);
+
+    // Moves whatever is behind the bus on by some T-cycles
+    fn tick(&mut self, cycles: usize);
+}
+
+impl Bus for mmu::Mmu {
+    fn read8(&mut self, address: &usize) -> u8 {
+        // While the PPU is using VRAM or OAM the CPU reads 0xFF from it, and its
+        // writes are lost
+        if self.is_locked_by_ppu(address) {
+            return 0xFF;
+        }
+
+        self.read_memory(address)
+    }
+
+    fn write8(&mut self, address: &usize, data: u8) {
+        if self.is_locked_by_ppu(address) {
+            return;
+        }
+
+        self.write_memory(address, data);
+    }
+
+    fn tick(&mut self, cycles: usize) {
+        // The hardware that lives in the Mmu - the timers, cartridge clocks (RTC)
+        // and OAM DMA. The PPU is still run by the Cpu
+        self.tick_dma(&cycles);
+        self.tick_timer(&cycles);
+        self.tick_cartridge(&cycles);
+    }
+}
+
+// The whole 64KB address space as plain RAM, with nothing else behind it. Used to
+// run the SM83 test vectors (see sm83.rs)
+#[cfg(feature = "sm83-tests")]
+pub struct FlatMemory {
+    memory: Vec<u8>,
+}
+
+#[cfg(feature = "sm83-tests")]
+impl FlatMemory {
+    pub fn new() -> FlatMemory {
+        FlatMemory {
+            memory: vec![0; 0x10000],
+        }
+    }
+}
+
+#[cfg(feature = "sm83-tests")]
+impl Bus for FlatMemory {
+    fn read8(&mut self, address: &usize) -> u8 {
+        self.memory[*address & 0xFFFF]
+    }
+
+    fn write8(&mut self, address: &usize, data: u8) {
+        self.memory[*address & 0xFFFF] = data;
+    }
+
+    fn tick(&mut self, _cycles: usize) {
+        // Nothing here runs by itself
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+// CGB colors are 15-bit (5 bits per channel) and were designed for the CGB's
+// dim, washed out LCD. Simply scaling them up to 8 bits per channel gives colors
+// that are far more saturated than games were meant to look, so optionally run
+// them through a transform approximating the real screen:
+// None: Straight 5 to 8 bit scaling
+// Cgb: Approximation of the CGB LCD, muted and with some channel bleed
+// Agb: Approximation of the (darker, more accurate) GBA LCD running CGB games
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorCorrection {
+    #[default]
+    None = 0,
+    Cgb = 1,
+    Agb = 2,
+}
+
+// Converts a CGB color (xBBBBBGGGGGRRRRR) to 8-bit RGB
+pub fn convert_cgb_color(color: u16, correction: ColorCorrection) -> [u8; 3] {
+    let red = (color & 0x1F) as u32;
+    let green = ((color >> 5) & 0x1F) as u32;
+    let blue = ((color >> 10) & 0x1F) as u32;
+
+    match correction {
+        ColorCorrection::None => [
+            scale_channel(red),
+            scale_channel(green),
+            scale_channel(blue),
+        ],
+
+        // Each output channel mixes in a bit of the others, results are at most 248
+        ColorCorrection::Cgb => [
+            ((red * 13 + green * 2 + blue) >> 1) as u8,
+            ((green * 3 + blue) << 1) as u8,
+            ((red * 3 + green * 2 + blue * 11) >> 1) as u8,
+        ],
+
+        ColorCorrection::Agb => correct_agb(red, green, blue),
+    }
+}
+
+fn scale_channel(value: u32) -> u8 {
+    // 5 bits to 8 bits, copying the top bits down so 31 maps to 255
+    ((value << 3) | (value >> 2)) as u8
+}
+
+fn correct_agb(red: u32, green: u32, blue: u32) -> [u8; 3] {
+    // Work in linear light, mix the channels and convert back. Each row of the
+    // mix adds up to 1 so white stays white
+    let linear = |value: u32| (value as f32 / 31.0).powf(2.2);
+    let (r, g, b) = (linear(red), linear(green), linear(blue));
+
+    let mixed = [
+        0.80 * r + 0.275 * g - 0.075 * b,
+        0.135 * r + 0.64 * g + 0.225 * b,
+        0.195 * r + 0.155 * g + 0.65 * b,
+    ];
+
+    let mut result = [0; 3];
+    for (i, value) in mixed.iter().enumerate() {
+        result[i] = (value.clamp(0.0, 1.0).powf(1.0 / 2.2) * 255.0).round() as u8;
+    }
+
+    result
+}
+
+// Built in sets of DMG shades, lightest to darkest, for frontends to offer
+// without having to come up with the RGB values themselves:
+// Gray: Plain white to black, the default
+// Green: The yellow-green of the original DMG screen
+// Pocket: The more neutral, slightly warm grays of the Gameboy Pocket
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PalettePreset {
+    Gray = 0,
+    Green = 1,
+    Pocket = 2,
+}
+
+impl PalettePreset {
+    pub fn get_shades(&self) -> [[u8; 3]; 4] {
+        match self {
+            PalettePreset::Gray   => [[0xFF, 0xFF, 0xFF], [0xAA, 0xAA, 0xAA], [0x55, 0x55, 0x55], [0x00, 0x00, 0x00]],
+            PalettePreset::Green  => [[0x9B, 0xBC, 0x0F], [0x8B, 0xAC, 0x0F], [0x30, 0x62, 0x30], [0x0F, 0x38, 0x0F]],
+            PalettePreset::Pocket => [[0xE0, 0xDB, 0xCD], [0xA8, 0x9F, 0x94], [0x70, 0x6B, 0x66], [0x2B, 0x2B, 0x26]],
+        }
+    }
+}
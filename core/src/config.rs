@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+use super::color;
+use super::display;
+use super::ppu;
+
+// All the user facing settings in one place, so a frontend can persist them with
+// a single export/import instead of calling every setter and keeping track itself.
+// Every field has a default so older saved configs missing newer fields still load
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EmulatorConfig {
+    // The four DMG shades as RGB, lightest (shade 0) to darkest (shade 3), for the
+    // background, sprite palette 0 and sprite palette 1
+    pub palettes: [[[u8; 3]; 4]; 3],
+
+    // Keyboard key (as reported by KeyboardEvent.key) to Gameboy button name
+    pub key_map: BTreeMap<String, String>,
+
+    // Emulation speed multiplier, 1.0 is normal speed
+    pub speed: f64,
+
+    // Skip idle loops to save CPU, see idle.rs
+    pub idle_skipping: bool,
+
+    // Draw with the pixel FIFO instead of a scanline at a time, see fifo.rs
+    pub pixel_fifo: bool,
+
+    // Frames to skip drawing after each drawn one, see Emulator::set_frame_skip
+    pub frame_skip: u8,
+
+    // Emulate the DMG's OAM corruption bug, see oam_bug.rs
+    pub oam_bug: bool,
+
+    pub filters: FilterConfig,
+    pub display: display::DisplayAdjustments,
+    pub audio: AudioConfig,
+
+    pub cheats_enabled: bool,
+}
+
+// Post-processing applied to the frame before it is handed to the frontend
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct FilterConfig {
+    pub frame_blending: bool,
+    pub color_correction: color::ColorCorrection,
+
+    // Darkening of the pixel grid when upscaling, 0.0 is off and 1.0 is black lines
+    pub lcd_grid: f32,
+
+    // Integer scale (1 - 4) the frame is kept upscaled to after each update, see
+    // Emulator::set_scale. 1 is off
+    pub scale: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AudioConfig {
+    pub enabled: bool,
+    pub volume: f32,
+    pub sample_rate: u32,
+
+    // Samples to keep buffered when pacing emulation from audio (see pacing.rs)
+    pub target_buffer: u32,
+}
+
+impl Default for EmulatorConfig {
+    fn default() -> EmulatorConfig {
+        let mut key_map = BTreeMap::new();
+        key_map.insert(String::from("ArrowRight"), String::from("right"));
+        key_map.insert(String::from("ArrowLeft"), String::from("left"));
+        key_map.insert(String::from("ArrowUp"), String::from("up"));
+        key_map.insert(String::from("ArrowDown"), String::from("down"));
+        key_map.insert(String::from("z"), String::from("a"));
+        key_map.insert(String::from("x"), String::from("b"));
+        key_map.insert(String::from("Shift"), String::from("select"));
+        key_map.insert(String::from("Enter"), String::from("start"));
+
+        EmulatorConfig {
+            palettes: [ppu::DEFAULT_SHADES; 3],
+            key_map,
+            speed: 1.0,
+            idle_skipping: false,
+            pixel_fifo: false,
+            frame_skip: 0,
+            oam_bug: false,
+            filters: FilterConfig::default(),
+            display: display::DisplayAdjustments::default(),
+            audio: AudioConfig::default(),
+            cheats_enabled: false,
+        }
+    }
+}
+
+impl Default for FilterConfig {
+    fn default() -> FilterConfig {
+        FilterConfig {
+            frame_blending: false,
+            color_correction: color::ColorCorrection::None,
+            lcd_grid: 0.0,
+            scale: 1,
+        }
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> AudioConfig {
+        AudioConfig {
+            enabled: true,
+            volume: 1.0,
+            sample_rate: 44100,
+            target_buffer: 4096,
+        }
+    }
+}
+
+impl EmulatorConfig {
+    pub fn to_json(&self) -> String {
+        // Serializing plain data can't fail
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_json(json: &str) -> Result<EmulatorConfig, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid emulator config - {}", e))
+    }
+}
@@ -0,0 +1,780 @@
+use super::utils;
+
+use serde::Serialize;
+
+use super::blocks;
+use super::bus::{self, Bus};
+use super::mmu;
+use super::oam_bug::OamBugAccess;
+use super::game;
+use super::ops;
+use super::debugger;
+use super::savestate;
+use super::scheduler;
+use super::ppu;
+
+// Most T-cycles a single step of HALT skips. With the LCD and timer both off there
+// may be nothing scheduled at all, and a frame shouldn't overrun by much
+const MAX_HALT_SKIP: usize = 456;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PairName {
+    AF,
+    BC,
+    DE,
+    HL,
+}
+
+// A 16 bit register that can also be used as two 8 bit halves, e.g. BC is B (hi)
+// and C (lo)
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Register {
+    value: u16,
+}
+
+impl Register {
+    pub fn new(value: u16) -> Register {
+        Register { value }
+    }
+
+    pub fn value(&self) -> u16 {
+        self.value
+    }
+
+    pub fn set_value(&mut self, value: u16) {
+        self.value = value;
+    }
+
+    pub fn hi(&self) -> u8 {
+        (self.value >> 8) as u8
+    }
+
+    pub fn lo(&self) -> u8 {
+        (self.value & 0xFF) as u8
+    }
+
+    pub fn set_hi(&mut self, hi: u8) {
+        self.value = ((hi as u16) << 8) | (self.value & 0xFF);
+    }
+
+    pub fn set_lo(&mut self, lo: u8) {
+        self.value = (self.value & 0xFF00) | lo as u16;
+    }
+}
+
+// Snapshot of the timer registers for debugging
+// div: Divider register (FF04)
+// internal_counter: The full 16 bit counter DIV is the upper 8 bits of, the low
+//                   byte being how far along DIV is to its next increment
+// tima/tma/tac: Timer counter, modulo and control (FF05 - FF07)
+// timer_enabled: Bit 2 of TAC
+// cycles_until_increment: Cycles left before TIMA next increments, 0 when the
+//                         timer is stopped
+#[derive(Copy, Clone, Debug)]
+pub struct TimerState {
+    pub div: u8,
+    pub internal_counter: u16,
+    pub tima: u8,
+    pub tma: u8,
+    pub tac: u8,
+    pub timer_enabled: bool,
+    pub cycles_until_increment: u32,
+}
+
+// Snapshot of the CPU registers for debugging
+// ime: Interrupt master enable, not counting an EI that hasn't taken effect yet
+#[derive(Serialize, Copy, Clone, Debug, PartialEq)]
+pub struct Registers {
+    pub af: u16,
+    pub bc: u16,
+    pub de: u16,
+    pub hl: u16,
+    pub sp: u16,
+    pub pc: u16,
+    pub ime: bool,
+    pub halted: bool,
+}
+
+// Where the CPU locked up after fetching an illegal opcode
+#[derive(Copy, Clone, Debug)]
+pub struct LockInfo {
+    pub pc: u16,
+    pub opcode: u8,
+}
+
+// The hardware to act like when starting without a boot ROM. Games tell them apart
+// by what the boot ROM leaves in A (0x01 on the DMG, 0xFF on the Pocket)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Model {
+    Dmg = 0,
+    Mgb = 1,
+}
+
+pub struct Cpu {
+    pub mmu: mmu::Mmu,
+
+    // When set, instructions read, write and tick through this instead of the Mmu
+    // (see bus.rs). The rest of the hardware then doesn't run at all
+    bus: Option<Box<dyn bus::Bus>>,
+
+    // Graphics, see ppu.rs
+    pub ppu: ppu::Ppu,
+
+    // The register pairs, get_pair picks one out by name
+    pub af: Register,
+    pub bc: Register,
+    pub de: Register,
+    pub hl: Register,
+    pub program_counter: u16,
+    pub stack_pointer: Register,
+    interrupt_master: bool,
+
+    // EI only turns on interrupt_master after the instruction that follows it
+    interrupt_master_pending: bool,
+
+    halted: bool,
+
+    // Set by HALT when it hits the HALT bug, the next opcode byte is read twice
+    halt_bug: bool,
+
+    // Set by STOP. The CPU, PPU and divider are all frozen until a button is pressed
+    stopped: bool,
+
+    // Set by an illegal opcode. The CPU never runs anything again (not even
+    // interrupts), while the rest of the hardware carries on
+    locked: bool,
+
+    // Cycles the rest of the hardware has been moved on by during the current
+    // instruction (see tick)
+    cycles_ticked: usize,
+
+    // Holds back the cycles above until the timers or PPU need to run
+    scheduler: scheduler::Scheduler,
+
+    // Decoded ROM code, see blocks.rs
+    block_cache: blocks::BlockCache,
+
+    // Writes made by instructions, for telling if a loop is actually doing
+    // anything (see idle.rs). The PPU and timers writing their own registers
+    // don't count
+    write_count: u32,
+
+    // Whether OAM gets corrupted by accesses during the OAM scan (see oam_bug.rs)
+    oam_bug: bool,
+}
+
+impl Cpu {
+    pub fn new(game: game::Game) -> Cpu {
+        // Initial values are defined in GB architecture
+
+        let mut cpu = Cpu {
+            mmu: mmu::Mmu::new(game),
+            bus: None,
+            ppu: ppu::Ppu::new(),
+            af: Register::new(0),
+            bc: Register::new(0),
+            de: Register::new(0),
+            hl: Register::new(0),
+            program_counter: 0x100,
+            stack_pointer: Register::new(0xFFFE),
+            interrupt_master: false,
+            interrupt_master_pending: false,
+            halted: false,
+            halt_bug: false,
+            stopped: false,
+            locked: false,
+            cycles_ticked: 0,
+            scheduler: scheduler::Scheduler::new(),
+            block_cache: blocks::BlockCache::new(),
+            write_count: 0,
+            oam_bug: false,
+        };
+
+        cpu.skip_boot_rom(Model::Dmg);
+        cpu
+    }
+
+    // Only the SM83 test vector runner swaps the bus out (see sm83.rs)
+    #[cfg(feature = "sm83-tests")]
+    pub fn set_bus(&mut self, bus: Box<dyn bus::Bus>) {
+        self.bus = Some(bus);
+    }
+
+    pub fn set_oam_bug(&mut self, enabled: bool) {
+        self.oam_bug = enabled;
+    }
+
+    #[cfg(feature = "sm83-tests")]
+    pub fn take_bus(&mut self) -> Option<Box<dyn bus::Bus>> {
+        // Goes back to running against the Mmu
+        self.bus.take()
+    }
+
+    pub fn execute_op(&mut self) -> usize {
+        // Runs one instruction, moving the timers and PPU along with it. Returns
+        // the cycles it took
+        self.cycles_ticked = 0;
+
+        if self.locked {
+            self.tick(4);
+        } else if !self.halted {
+            self.mmu.set_current_pc(self.program_counter);
+            let (next_op, handler) = self.fetch_op();
+
+            // The HALT bug stops the PC moving past the opcode, so the opcode is
+            // read again as the first operand (or as the next instruction if it has
+            // none). Starting the instruction one byte back has the same effect
+            if self.halt_bug {
+                self.halt_bug = false;
+                self.program_counter = self.program_counter.wrapping_sub(1);
+            }
+
+            // An EI before this instruction takes effect once it is done, so nothing
+            // can interrupt between EI and the instruction after it
+            let enable_interrupts = self.interrupt_master_pending;
+
+            let result = handler.run(self, next_op);
+            self.program_counter = self.program_counter.wrapping_add(result.pc_delta);
+
+            // Anything that wasn't a memory access (internal delays) happens at the end
+            let cycles_left = result.cycles.saturating_sub(self.cycles_ticked);
+            self.tick(cycles_left);
+
+            if enable_interrupts && self.interrupt_master_pending {
+                self.interrupt_master_pending = false;
+                self.interrupt_master = true;
+            }
+        } else {
+            // A pending interrupt ends HALT even with IME off. It isn't serviced,
+            // execution just carries on after the HALT
+            if self.mmu.get_interrupts().get_pending() > 0 {
+                self.halted = false;
+                self.tick(4);
+            } else {
+                // Only the hardware can request an interrupt, and it doesn't do
+                // anything until its next event, so skip straight there rather than
+                // a machine cycle at a time
+                let cycles = self.scheduler.get_cycles_until_event().min(MAX_HALT_SKIP);
+                self.tick(((cycles + 3) & !3).max(4));
+            }
+        }
+
+        self.cycles_ticked
+    }
+
+    fn fetch_op(&mut self) -> (u8, &'static ops::OpHandler) {
+        // Reads the opcode at the PC. Code in ROM comes out of the block cache
+        // already decoded, but takes the same machine cycle as any other fetch
+        let pc = self.program_counter;
+
+        // During OAM DMA the fetch might not be able to reach the code at all. The
+        // cache only knows about the Mmu's ROM, not any other bus
+        let cached = if self.mmu.get_dma().is_active() || self.bus.is_some() {
+            None
+        } else {
+            self.block_cache.fetch(&self.mmu, pc)
+        };
+        match cached {
+            Some(decoded) => {
+                self.tick(4);
+                decoded
+            }
+            None => {
+                let opcode = self.read_cycle(&(pc as usize));
+                (opcode, ops::get_handler(opcode))
+            }
+        }
+    }
+
+    pub fn tick(&mut self, cycles: usize) {
+        // Moves the rest of the hardware on by some T-cycles. They only actually
+        // run once something is due to happen (see scheduler.rs)
+        self.cycles_ticked += cycles;
+        if let Some(bus) = self.bus.as_mut() {
+            bus.tick(cycles);
+        } else if self.scheduler.add_cycles(cycles) {
+            self.sync();
+        }
+    }
+
+    pub fn sync(&mut self) {
+        // Catches the timers and PPU up on the cycles the scheduler has been
+        // holding back, then works out when they next need to run. Running on
+        // another bus there's no hardware to catch up
+        if self.bus.is_some() {
+            return;
+        }
+
+        let cycles = self.scheduler.take_pending();
+        if cycles > 0 {
+            // Timers, cartridge clocks and DMA (see bus.rs)
+            self.mmu.tick(cycles);
+        }
+
+        for interrupt in self.ppu.tick(&mut self.mmu, cycles) {
+            self.mmu.request_interrupt(interrupt);
+        }
+
+        self.reschedule();
+    }
+
+    pub fn read_cycle(&mut self, address: &usize) -> u8 {
+        // A memory read made by an instruction. Every access takes a machine cycle
+        // (4 T-cycles) and the hardware moves on before the access happens, so
+        // reads see the timers and PPU as they are partway through the instruction
+        self.do_read_cycle(address, OamBugAccess::Read)
+    }
+
+    pub fn read_cycle_increment(&mut self, address: &usize) -> u8 {
+        // A read that steps the register holding the address in the same cycle (LD
+        // A, (HL+) / LD A, (HL-)), which only makes a difference to the OAM bug
+        self.do_read_cycle(address, OamBugAccess::ReadIncrease)
+    }
+
+    pub fn address_cycle(&mut self, address: &usize) {
+        // An internal cycle that still puts an address on the bus (INC rr / DEC
+        // rr). Nothing is read or written, but the OAM bug sees it as a write
+        self.tick(4);
+        self.trigger_oam_bug(address, OamBugAccess::Write);
+    }
+
+    fn do_read_cycle(&mut self, address: &usize, access: OamBugAccess) -> u8 {
+        self.tick(4);
+        if let Some(bus) = self.bus.as_mut() {
+            return bus.read8(address);
+        }
+
+        if scheduler::is_hardware_address(address) || scheduler::is_video_address(address) || self.mmu.get_dma().is_active() {
+            self.sync();
+        }
+        self.trigger_oam_bug(address, access);
+
+        // Everything outside the CPU is on the DMA's bus while it runs
+        if self.mmu.get_dma().is_blocking(address) {
+            return 0xFF;
+        }
+
+        self.mmu.read8(address)
+    }
+
+    pub fn write_cycle(&mut self, address: &usize, data: u8) {
+        // A memory write made by an instruction, timed the same as read_cycle.
+        // Writing a register can move the next event (TAC, DIV, LCDC...)
+        self.tick(4);
+        self.write_count = self.write_count.wrapping_add(1);
+        if let Some(bus) = self.bus.as_mut() {
+            bus.write8(address, data);
+            return;
+        }
+
+        if scheduler::is_hardware_address(address) || scheduler::is_video_address(address) || self.mmu.get_dma().is_active() {
+            self.sync();
+        }
+        self.trigger_oam_bug(address, OamBugAccess::Write);
+
+        if self.mmu.get_dma().is_blocking(address) {
+            return;
+        }
+
+        if scheduler::is_hardware_address(address) {
+            self.mmu.write8(address, data);
+            self.reschedule();
+        } else {
+            self.mmu.write8(address, data);
+        }
+    }
+
+    fn trigger_oam_bug(&mut self, address: &usize, access: OamBugAccess) {
+        // Any address in 0xFE00 - 0xFEFF on the bus while the PPU is scanning OAM
+        // corrupts the row it's on. OAM DMA has the bus to itself, so the CPU's
+        // accesses never get that far while it runs. PUSH, POP, CALL and RET are
+        // left as their plain reads and writes, the extra corruption from their SP
+        // steps isn't emulated
+        if !self.oam_bug || self.bus.is_some() || !(0xFE00..=0xFEFF).contains(address) || self.mmu.get_dma().is_active() {
+            return;
+        }
+
+        self.sync();
+        if let Some(row) = self.ppu.get_oam_scan_row(&self.mmu) {
+            self.mmu.corrupt_oam(row, access);
+        }
+    }
+
+    pub fn get_write_count(&self) -> u32 {
+        self.write_count
+    }
+
+    pub fn save_state(&self) -> savestate::SaveState {
+        savestate::SaveState {
+            cpu: savestate::CpuState {
+                af: self.af.value(),
+                bc: self.bc.value(),
+                de: self.de.value(),
+                hl: self.hl.value(),
+                sp: self.stack_pointer.value(),
+                pc: self.program_counter,
+                interrupt_master: self.interrupt_master,
+                interrupt_master_pending: self.interrupt_master_pending,
+                halted: self.halted,
+                halt_bug: self.halt_bug,
+                stopped: self.stopped,
+                locked: self.locked,
+                scanline_counter: self.ppu.get_scanline_counter(),
+                line: Some(self.ppu.get_line()),
+                mode_3_end: Some(self.ppu.get_mode_3_end()),
+                window_line: self.ppu.get_window_line(),
+                window_y_triggered: self.ppu.is_window_y_triggered(),
+                stat_line: self.ppu.get_stat_line(),
+            },
+            mmu: self.mmu.save_state(),
+        }
+    }
+
+    pub fn load_state(&mut self, state: &savestate::SaveState) {
+        let cpu = &state.cpu;
+
+        self.af = Register::new(cpu.af);
+        self.bc = Register::new(cpu.bc);
+        self.de = Register::new(cpu.de);
+        self.hl = Register::new(cpu.hl);
+        self.stack_pointer = Register::new(cpu.sp);
+        self.program_counter = cpu.pc;
+        self.interrupt_master = cpu.interrupt_master;
+        self.interrupt_master_pending = cpu.interrupt_master_pending;
+        self.halted = cpu.halted;
+        self.halt_bug = cpu.halt_bug;
+        self.stopped = cpu.stopped;
+        self.locked = cpu.locked;
+        self.ppu.set_scanline_counter(cpu.scanline_counter);
+        self.ppu.set_mode_3_end(cpu.mode_3_end.unwrap_or(ppu::MODE_3_BOUNDS));
+        self.ppu.set_window_state(cpu.window_line, cpu.window_y_triggered);
+        self.ppu.set_stat_line(cpu.stat_line);
+
+        self.mmu.load_state(&state.mmu);
+        self.ppu.set_lcd_enabled(self.ppu.is_lcd_enabled(&self.mmu));
+        self.ppu.set_line(cpu.line.unwrap_or(self.mmu.read_memory(&utils::CURRENT_SCANLINE_ADDR)));
+
+        // Anything held back belonged to the state being replaced
+        self.scheduler = scheduler::Scheduler::new();
+    }
+
+    pub fn skip_boot_rom(&mut self, model: Model) {
+        // Leaves everything how the boot ROM would have on the given model, as if it
+        // had just handed over to the game at 0x0100
+        let a = match model {
+            Model::Dmg => 0x01,
+            Model::Mgb => 0xFF,
+        };
+
+        // H and C depend on the header checksum the boot ROM works out, which is
+        // only 0 when the header checksum byte (0x014D) is 0 as well
+        let flags = if self.mmu.read_memory(&0x014D) == 0 { 0x80 } else { 0xB0 };
+
+        self.af = Register::new((a << 8) | flags);
+        self.bc = Register::new(0x0013);
+        self.de = Register::new(0x00D8);
+        self.hl = Register::new(0x014D);
+        self.stack_pointer = Register::new(0xFFFE);
+        self.program_counter = 0x0100;
+        self.interrupt_master = false;
+        self.interrupt_master_pending = false;
+
+        self.mmu.skip_boot_rom();
+    }
+
+    pub fn start_boot_rom(&mut self) {
+        // Power on state, where the boot ROM starts from. Everything is 0 and it's
+        // up to the boot ROM to set things up
+        self.af = Register::new(0);
+        self.bc = Register::new(0);
+        self.de = Register::new(0);
+        self.hl = Register::new(0);
+
+        self.stack_pointer = Register::new(0);
+        self.program_counter = 0x0000;
+        self.interrupt_master = false;
+        self.interrupt_master_pending = false;
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn halt(&mut self) {
+        // HALT stops the CPU until an interrupt is requested. If IME is off and an
+        // interrupt is already pending the CPU doesn't halt at all and trips the
+        // HALT bug instead
+        if !self.interrupt_master && self.mmu.get_interrupts().get_pending() > 0 {
+            self.halt_bug = true;
+        } else {
+            self.halted = true;
+        }
+    }
+
+    pub fn enable_interrupts(&mut self) {
+        // EI - takes effect after the next instruction (see execute_op)
+        self.interrupt_master_pending = true;
+    }
+
+    pub fn restore_interrupts(&mut self) {
+        // RETI - takes effect straight away
+        self.interrupt_master = true;
+        self.interrupt_master_pending = false;
+    }
+
+    pub fn disable_interrupts(&mut self) {
+        // DI - takes effect straight away and cancels an EI that hasn't yet
+        self.interrupt_master = false;
+        self.interrupt_master_pending = false;
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn stop(&mut self) {
+        // STOP puts the whole system into low power mode. The divider is reset when
+        // entering it
+        self.stopped = true;
+        self.sync();
+        self.mmu.get_timer_mut().reset_divider();
+        self.reschedule();
+    }
+
+    pub fn wake_from_stop(&mut self) {
+        self.stopped = false;
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    pub fn lock(&mut self) {
+        // Only a reset gets the CPU going again
+        self.locked = true;
+    }
+
+    pub fn get_lock_info(&self) -> Option<LockInfo> {
+        // The PC is left on the illegal opcode
+        if !self.locked {
+            return None;
+        }
+
+        Some(LockInfo {
+            pc: self.program_counter,
+            opcode: self.mmu.peek_memory(&(self.program_counter as usize)),
+        })
+    }
+
+    pub fn get_pair(&self, pair: &PairName) -> &Register {
+        match pair {
+            PairName::AF => &self.af,
+            PairName::BC => &self.bc,
+            PairName::DE => &self.de,
+            PairName::HL => &self.hl,
+        }
+    }
+
+    pub fn get_pair_mut(&mut self, pair: &PairName) -> &mut Register {
+        match pair {
+            PairName::AF => &mut self.af,
+            PairName::BC => &mut self.bc,
+            PairName::DE => &mut self.de,
+            PairName::HL => &mut self.hl,
+        }
+    }
+
+    pub fn get_registers(&self) -> Registers {
+        Registers {
+            af: self.af.value(),
+            bc: self.bc.value(),
+            de: self.de.value(),
+            hl: self.hl.value(),
+            sp: self.stack_pointer.value(),
+            pc: self.program_counter,
+            ime: self.interrupt_master,
+            halted: self.halted,
+        }
+    }
+
+    pub fn set_register(&mut self, name: &str, value: u16) -> Result<(), String> {
+        // For patching state from a debugger. name is a pair (af, bc, de, hl), a
+        // single register (a - l), sp, pc or ime. Single registers only take the low
+        // byte of value, and the low 4 bits of F always stay 0
+        let name = name.to_lowercase();
+        let byte = (value & 0xFF) as u8;
+
+        match name.as_str() {
+            "af"  => self.af.set_value(value & 0xFFF0),
+            "bc"  => self.bc.set_value(value),
+            "de"  => self.de.set_value(value),
+            "hl"  => self.hl.set_value(value),
+            "sp"  => self.stack_pointer.set_value(value),
+            "pc"  => self.program_counter = value,
+            "ime" => if value > 0 { self.restore_interrupts() } else { self.disable_interrupts() },
+            _     => {
+                // Single registers, as the pair they're in and whether they're the high byte
+                let (pair, high) = match name.as_str() {
+                    "a" => (PairName::AF, true),
+                    "f" => (PairName::AF, false),
+                    "b" => (PairName::BC, true),
+                    "c" => (PairName::BC, false),
+                    "d" => (PairName::DE, true),
+                    "e" => (PairName::DE, false),
+                    "h" => (PairName::HL, true),
+                    "l" => (PairName::HL, false),
+                    _   => return Err(format!("Unknown register - {}", name))
+                };
+
+                let byte = if name == "f" { byte & 0xF0 } else { byte };
+                let register = self.get_pair_mut(&pair);
+                if high { register.set_hi(byte) } else { register.set_lo(byte) }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_timer_state(&self) -> TimerState {
+        let timer = self.mmu.get_timer();
+
+        TimerState {
+            div: timer.read_div(),
+            internal_counter: timer.get_counter(),
+            tima: timer.read_tima(),
+            tma: timer.read_tma(),
+            tac: timer.read_tac(),
+            timer_enabled: timer.is_enabled(),
+            cycles_until_increment: if timer.is_enabled() { timer.get_cycles_until_increment() as u32 } else { 0 },
+        }
+    }
+
+    pub fn cycles_until_next_event(&self) -> usize {
+        // How long until something observable happens in hardware - the divider ticking,
+        // the timer incrementing, or the LCD changing mode/scanline. Nothing the CPU can
+        // see changes before this, so idle time can safely be skipped up to here
+        let timer = self.mmu.get_timer();
+        let mut cycles = timer.get_cycles_until_div_increment();
+
+        if timer.is_enabled() {
+            cycles = cycles.min(timer.get_cycles_until_increment());
+        }
+
+        cycles = cycles.min(self.ppu.get_cycles_until_event(&self.mmu));
+
+        if self.mmu.get_dma().is_active() {
+            cycles = cycles.min(self.mmu.get_dma().get_cycles_left());
+        }
+
+        // Always move forward by at least one machine cycle
+        cycles.max(4)
+    }
+
+    fn reschedule(&mut self) {
+        // The next time the hardware does something by itself that the CPU could
+        // notice without reading a register - the LCD changing mode/scanline (and
+        // maybe requesting an interrupt), TIMA overflowing or OAM DMA finishing
+        let timer_cycles = self.mmu.get_timer().get_cycles_until_overflow();
+        let mut cycles = timer_cycles.min(self.ppu.get_cycles_until_event(&self.mmu));
+        if self.mmu.get_dma().is_active() {
+            cycles = cycles.min(self.mmu.get_dma().get_cycles_left());
+        }
+        self.scheduler.schedule(cycles);
+    }
+
+    pub fn do_interrupts(&mut self) -> usize {
+        // Services the highest priority pending interrupt, returns the cycles that
+        // took (0 if there wasn't one). IME is turned off while it's serviced, so
+        // the rest wait until the handler turns it back on
+        self.cycles_ticked = 0;
+
+        if self.interrupt_master && !self.locked && self.mmu.get_interrupts().get_highest_priority().is_some() {
+            self.service_interrupt();
+        }
+
+        self.cycles_ticked
+    }
+
+    pub fn push_word_to_stack(&mut self, word: &u16) {
+        let hi: u8 = word.checked_shr(8).unwrap_or(0) as u8;
+        let lo = (word & 0xFF) as u8;
+
+        // SP wraps around the address space like it does on hardware
+        let mut sp = self.stack_pointer.value();
+        sp = sp.wrapping_sub(1);
+        self.write_cycle(&(sp as usize), hi);
+        sp = sp.wrapping_sub(1);
+        self.write_cycle(&(sp as usize), lo);
+        self.stack_pointer.set_value(sp);
+    }
+
+    pub fn pop_word_from_stack(&mut self) -> u16 {
+        let sp = self.stack_pointer.value();
+        let lo = self.read_cycle(&(sp as usize)) as u16;
+        let hi = self.read_cycle(&(sp.wrapping_add(1) as usize)) as u16;
+        self.stack_pointer.set_value(sp.wrapping_add(2));
+        (hi << 8) | lo
+    }
+
+    pub fn is_flag_set(&self, bit: u8) -> bool {
+        // bit is one of the utils::*_BIT flag positions in F
+        self.af.lo() & (1 << bit) > 0
+    }
+
+    pub fn set_flag(&mut self, bit: u8) {
+        self.af.set_lo(self.af.lo() | (1 << bit));
+    }
+
+    pub fn clear_flag(&mut self, bit: u8) {
+        self.af.set_lo(self.af.lo() & !(1 << bit));
+    }
+
+    pub fn set_flags(&mut self, zero: bool, subtract: bool, half_carry: bool, carry: bool) {
+        // Replaces all four flags at once. Bits 3 - 0 of F are always 0
+        let mut flags = 0;
+        if zero { flags |= 1 << utils::ZERO_BIT; }
+        if subtract { flags |= 1 << utils::SUBTRACT_BIT; }
+        if half_carry { flags |= 1 << utils::HALF_CARRY_BIT; }
+        if carry { flags |= 1 << utils::CARRY_BIT; }
+
+        self.af.set_lo(flags);
+    }
+
+    fn service_interrupt(&mut self) {
+        // The highest priority requested interrupt is performed by calling its
+        // vector (see interrupts.rs)
+
+        // We need to flip the master interrupt switch off
+        self.interrupt_master = false;
+        self.halted = false;
+
+        // Dispatch takes 5 machine cycles. Two are spent waiting (the CPU
+        // effectively runs two NOPs), two pushing the current execution address
+        // onto the stack and the last one setting the PC
+        self.tick(8);
+        let current_pc = self.program_counter;
+        let mut sp = self.stack_pointer.value().wrapping_sub(1);
+        self.write_cycle(&(sp as usize), (current_pc >> 8) as u8);
+
+        // Which interrupt runs is only decided now, between the two pushes. If the
+        // high byte landed on IE (SP was 0x0000) and turned the interrupt off, it is
+        // cancelled and execution carries on at 0x0000 instead
+        let interrupt = self.mmu.get_interrupts().get_highest_priority();
+
+        sp = sp.wrapping_sub(1);
+        self.write_cycle(&(sp as usize), (current_pc & 0xFF) as u8);
+        self.stack_pointer.set_value(sp);
+        self.tick(4);
+
+        match interrupt {
+            Some(interrupt) => {
+                // Turn off the bit in the interrupt request register for the
+                // interrupt we are running
+                self.mmu.get_interrupts_mut().acknowledge(interrupt);
+                self.mmu.record_event(debugger::HardwareEvent::InterruptServiced(interrupt.bit()));
+                self.program_counter = interrupt.vector();
+            }
+            None            => self.program_counter = 0x0000
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::VecDeque;
+
+// The kinds of things the debugger can break on. Besides plain PC breakpoints
+// we can stop when hardware does something interesting, which is handy when
+// tracking down raster effects or mapper bugs
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BreakKind {
+    Breakpoint = 0,
+    Interrupt = 1,
+    ModeChange = 2,
+    RomBankSwitch = 3,
+    RamBankSwitch = 4,
+}
+
+// Events raised by the Cpu and Mmu while running. These are only recorded
+// while the debugger is interested in them
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HardwareEvent {
+    InterruptServiced(u8), // Interrupt bit that was serviced
+    ModeChanged(u8),       // New LCD mode (0 - 3)
+    RomBankSwitched(u8),   // New ROM bank
+    RamBankSwitched(u8),   // New RAM bank
+}
+
+impl HardwareEvent {
+    pub fn kind(&self) -> BreakKind {
+        match self {
+            HardwareEvent::InterruptServiced(_) => BreakKind::Interrupt,
+            HardwareEvent::ModeChanged(_)       => BreakKind::ModeChange,
+            HardwareEvent::RomBankSwitched(_)   => BreakKind::RomBankSwitch,
+            HardwareEvent::RamBankSwitched(_)   => BreakKind::RamBankSwitch,
+        }
+    }
+
+    pub fn value(&self) -> u8 {
+        match self {
+            HardwareEvent::InterruptServiced(v) => *v,
+            HardwareEvent::ModeChanged(v)       => *v,
+            HardwareEvent::RomBankSwitched(v)   => *v,
+            HardwareEvent::RamBankSwitched(v)   => *v,
+        }
+    }
+}
+
+// Context of the last break, handed to JS so a debugger UI can show why we stopped
+// kind: What caused the break
+// value: Interrupt bit, LCD mode or bank number depending on kind (PC for breakpoints)
+// pc: Program counter at the time of the break
+// scanline: Current scanline (LY) at the time of the break
+// instance: Id of the emulator that stopped, for pages running several
+#[derive(Copy, Clone, Debug)]
+pub struct BreakInfo {
+    pub instance: u32,
+    pub kind: BreakKind,
+    pub value: u16,
+    pub pc: u16,
+    pub scanline: u8,
+}
+
+// What Emulator::step_instruction ran, for a debugger UI stepping through code
+// bytes: The opcode and its operands (the CB prefix counts as part of the opcode)
+// mnemonic: The instruction disassembled, i.e. "LD A, (HL)"
+// cycles: T-cycles that passed, including an interrupt dispatched straight after
+// pc: Program counter afterwards
+// bytes and mnemonic are empty if the CPU was halted, stopped or locked up, as no
+// instruction ran
+#[derive(Clone, Debug)]
+pub struct StepInfo {
+    pub bytes: Vec<u8>,
+    pub mnemonic: String,
+    pub cycles: u32,
+    pub pc: u16,
+}
+
+// An event breakpoint matches a kind of event, optionally only for a specific value
+// (i.e. only the timer interrupt, or only mode 3)
+struct EventBreakpoint {
+    kind: BreakKind,
+    value: Option<u8>,
+}
+
+pub struct Debugger {
+    instance: u32,
+    breakpoints: Vec<u16>,
+    event_breakpoints: Vec<EventBreakpoint>,
+    break_info: Option<BreakInfo>,
+}
+
+impl Debugger {
+    pub fn new(instance: u32) -> Debugger {
+        Debugger {
+            instance,
+            breakpoints: Vec::new(),
+            event_breakpoints: Vec::new(),
+            break_info: None,
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, pc: u16) {
+        if !self.breakpoints.contains(&pc) {
+            self.breakpoints.push(pc);
+        }
+    }
+
+    pub fn remove_breakpoint(&mut self, pc: u16) {
+        self.breakpoints.retain(|b| *b != pc);
+    }
+
+    pub fn add_event_breakpoint(&mut self, kind: BreakKind, value: Option<u8>) {
+        self.event_breakpoints.push(EventBreakpoint { kind, value });
+    }
+
+    pub fn clear_event_breakpoints(&mut self) {
+        self.event_breakpoints.clear();
+    }
+
+    // If nothing is set, the emulator doesn't need to check anything per instruction
+    pub fn is_active(&self) -> bool {
+        !self.breakpoints.is_empty() || !self.event_breakpoints.is_empty()
+    }
+
+    // Hardware events only need to be recorded if there is an event breakpoint
+    pub fn wants_events(&self) -> bool {
+        !self.event_breakpoints.is_empty()
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.break_info.is_some()
+    }
+
+    pub fn get_break_info(&self) -> Option<BreakInfo> {
+        self.break_info
+    }
+
+    pub fn resume(&mut self) {
+        self.break_info = None;
+    }
+
+    pub fn check_breakpoint(&mut self, pc: u16, scanline: u8) -> bool {
+        if self.breakpoints.contains(&pc) {
+            self.break_info = Some(BreakInfo {
+                instance: self.instance,
+                kind: BreakKind::Breakpoint,
+                value: pc,
+                pc,
+                scanline,
+            });
+        }
+
+        self.is_broken()
+    }
+
+    pub fn check_events(&mut self, events: &[HardwareEvent], pc: u16, scanline: u8) -> bool {
+        for event in events {
+            let kind = event.kind();
+            let value = event.value();
+
+            let matched = self.event_breakpoints
+                .iter()
+                .any(|b| b.kind == kind && b.value.is_none_or(|v| v == value));
+
+            if matched {
+                self.break_info = Some(BreakInfo {
+                    instance: self.instance,
+                    kind,
+                    value: value as u16,
+                    pc,
+                    scanline,
+                });
+                break;
+            }
+        }
+
+        self.is_broken()
+    }
+}
+
+// A single write to the memory bank controller
+// address: Address that was written to (0000-7FFF)
+// value: Value that was written
+// rom_bank: ROM bank selected after the write
+// ram_bank: RAM bank selected after the write
+// pc: Program counter of the instruction doing the write
+#[derive(Copy, Clone, Debug)]
+pub struct BankSwitchEntry {
+    pub address: u16,
+    pub value: u8,
+    pub rom_bank: u8,
+    pub ram_bank: u8,
+    pub pc: u16,
+}
+
+// Ring buffer of banking writes. Once full, the oldest entries are dropped so
+// games that switch banks constantly don't grow this forever. The capacity comes
+// from JS, so nothing is allocated up front - it only grows as entries come in
+pub struct BankSwitchLog {
+    entries: VecDeque<BankSwitchEntry>,
+    capacity: usize,
+}
+
+impl BankSwitchLog {
+    pub fn new(capacity: usize) -> BankSwitchLog {
+        BankSwitchLog {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, entry: BankSwitchEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+
+    pub fn entries(&self) -> Vec<BankSwitchEntry> {
+        self.entries.iter().cloned().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+// Display adjustments applied when the finished frame is handed to the frontend.
+// Doing this in Rust means every frontend (and screenshots) get the same output.
+// brightness: Added to every channel, -1.0 to 1.0 (0.0 is unchanged)
+// contrast: Scales channels around the midpoint (1.0 is unchanged), 0.0 or more
+// gamma: Output gamma (1.0 is unchanged, higher is brighter midtones), above 0.0
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DisplayAdjustments {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub gamma: f32,
+}
+
+impl Default for DisplayAdjustments {
+    fn default() -> DisplayAdjustments {
+        DisplayAdjustments {
+            brightness: 0.0,
+            contrast: 1.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+impl DisplayAdjustments {
+    pub fn is_identity(&self) -> bool {
+        *self == DisplayAdjustments::default()
+    }
+
+    pub fn clamped(&self) -> DisplayAdjustments {
+        // The adjustments pulled into the ranges above. Anything that isn't a
+        // number at all goes back to its default
+        let default = DisplayAdjustments::default();
+        let or_default = |value: f32, default: f32| if value.is_finite() { value } else { default };
+
+        DisplayAdjustments {
+            brightness: or_default(self.brightness, default.brightness).clamp(-1.0, 1.0),
+            contrast: or_default(self.contrast, default.contrast).max(0.0),
+            gamma: if self.gamma.is_finite() && self.gamma > 0.0 { self.gamma } else { default.gamma },
+        }
+    }
+
+    // Every channel value maps to exactly one output value, so the adjustments are
+    // baked into a lookup table once instead of doing float math per pixel
+    pub fn build_lookup_table(&self) -> [u8; 256] {
+        let mut table = [0; 256];
+        let gamma = if self.gamma > 0.0 { self.gamma } else { 1.0 };
+
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mut value = i as f32 / 255.0;
+            value = (value - 0.5) * self.contrast + 0.5 + self.brightness;
+            value = value.clamp(0.0, 1.0).powf(1.0 / gamma);
+            *entry = (value * 255.0).round() as u8;
+        }
+
+        table
+    }
+}
+
+// Applies the adjustments to an RGBA frame (leaving alpha alone). With no
+// adjustments set this is a plain copy
+pub struct FrameConverter {
+    adjustments: DisplayAdjustments,
+    lookup_table: [u8; 256],
+}
+
+impl FrameConverter {
+    pub fn new() -> FrameConverter {
+        let adjustments = DisplayAdjustments::default();
+
+        FrameConverter {
+            adjustments,
+            lookup_table: adjustments.build_lookup_table(),
+        }
+    }
+
+    pub fn set_adjustments(&mut self, adjustments: DisplayAdjustments) {
+        self.adjustments = adjustments;
+        self.lookup_table = adjustments.build_lookup_table();
+    }
+
+    pub fn convert(&self, frame: &[u8]) -> Vec<u8> {
+        if self.adjustments.is_identity() {
+            return frame.to_vec();
+        }
+
+        frame
+            .iter()
+            .enumerate()
+            .map(|(i, c)| if i % 4 == 3 { *c } else { self.lookup_table[*c as usize] })
+            .collect()
+    }
+}
+
+// Mixes the previous frame evenly into the current one, both RGBA, like the slow
+// response of the DMG LCD does. Games that flicker sprites every other frame for
+// transparency rely on this. Alpha is taken from the current frame
+pub fn blend_frames(current: &[u8], previous: &[u8]) -> Vec<u8> {
+    current
+        .iter()
+        .zip(previous.iter())
+        .enumerate()
+        .map(|(i, (c, p))| if i % 4 == 3 { *c } else { ((*c as u16 + *p as u16) / 2) as u8 })
+        .collect()
+}
+
+// Nearest neighbour integer upscaling of an RGBA frame. When grid_strength is above
+// 0 the last row and column of every scaled up pixel are darkened by that amount,
+// giving the dot-matrix look of the DMG LCD. There is no room for a grid at 1x
+pub fn scale_frame(frame: &[u8], width: usize, height: usize, scale: usize, grid_strength: f32) -> Vec<u8> {
+    let scale = scale.max(1);
+    let mut scaled = vec![0; width * scale * height * scale * 4];
+    scale_frame_into(&mut scaled, frame, width, height, scale, grid_strength);
+    scaled
+}
+
+// Same as scale_frame, into a buffer that's already (width * scale) x (height *
+// scale), so it can be reused frame after frame
+pub fn scale_frame_into(scaled: &mut [u8], frame: &[u8], width: usize, height: usize, scale: usize, grid_strength: f32) {
+    let scale = scale.max(1);
+    let scaled_width = width * scale;
+
+    let grid = scale > 1 && grid_strength > 0.0;
+    let grid_factor = 1.0 - grid_strength.min(1.0);
+
+    for y in 0..height * scale {
+        let source_y = y / scale;
+        let grid_row = grid && y % scale == scale - 1;
+
+        for x in 0..scaled_width {
+            let source_x = x / scale;
+            let grid_column = grid && x % scale == scale - 1;
+
+            let source = (source_y * width + source_x) * 4;
+            let destination = (y * scaled_width + x) * 4;
+
+            for c in 0..4 {
+                let mut value = frame[source + c];
+                if (grid_row || grid_column) && c < 3 {
+                    value = (value as f32 * grid_factor) as u8;
+                }
+
+                scaled[destination + c] = value;
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+// OAM DMA
+//
+// Writing a value to FF46 copies 160 bytes (40 sprites, 4 bytes each) from value *
+// 0x100 into OAM (FE00-FE9F). The copy isn't instant, one byte goes across each
+// machine cycle so it takes 160 of them (640 T-cycles). While it runs the DMA has
+// the bus, so the CPU can only reach what's inside it - HRAM, the IO registers and
+// IE. Reads of anything else come back as 0xFF and writes are lost, which is why
+// games copy a small routine into HRAM and wait there for the DMA to finish.
+// Starting another DMA while one is running restarts it from the new source.
+
+// T-cycles a whole transfer takes
+const DMA_CYCLES: usize = 0xA0 * 4;
+
+pub struct Dma {
+    source: usize,
+
+    // T-cycles until the transfer is done, 0 when there isn't one
+    cycles_left: usize,
+}
+
+impl Dma {
+    pub fn new() -> Dma {
+        Dma {
+            source: 0,
+            cycles_left: 0,
+        }
+    }
+
+    pub fn start(&mut self, data: u8) {
+        // data is the upper byte of the source address
+        self.source = (data as usize) << 8;
+        self.cycles_left = DMA_CYCLES;
+    }
+
+    pub fn tick(&mut self, cycles: usize) -> std::ops::Range<usize> {
+        // Moves the transfer on, returns which of the 160 bytes are due to be
+        // copied now (empty if none are)
+        let copied = self.get_bytes_copied();
+        self.cycles_left = self.cycles_left.saturating_sub(cycles);
+        copied..self.get_bytes_copied()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.cycles_left > 0
+    }
+
+    pub fn is_blocking(&self, address: &usize) -> bool {
+        // Whether the CPU is locked out of an address by the transfer
+        self.is_active() && *address < 0xFF00
+    }
+
+    pub fn get_source(&self) -> usize {
+        self.source
+    }
+
+    pub fn get_cycles_left(&self) -> usize {
+        self.cycles_left
+    }
+
+    pub fn set_state(&mut self, source: usize, cycles_left: usize) {
+        // Only for loading states
+        self.source = source;
+        self.cycles_left = cycles_left.min(DMA_CYCLES);
+    }
+
+    fn get_bytes_copied(&self) -> usize {
+        (DMA_CYCLES - self.cycles_left) / 4
+    }
+}
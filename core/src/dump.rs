@@ -0,0 +1,43 @@
+// Full memory dumps
+//
+// A dump is a single blob made of labeled sections so it can be picked apart in a
+// hex editor or by a script without knowing the emulator's internals:
+//
+// "GBDUMP" magic, followed by a version byte
+// Then for each section:
+//   1 byte: Length of the label
+//   N bytes: Label (ASCII)
+//   4 bytes: Length of the data (little endian)
+//   N bytes: Data
+
+const MAGIC: &[u8] = b"GBDUMP";
+const VERSION: u8 = 1;
+
+pub struct MemoryDump {
+    data: Vec<u8>,
+}
+
+impl MemoryDump {
+    pub fn new() -> MemoryDump {
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC);
+        data.push(VERSION);
+
+        MemoryDump {
+            data,
+        }
+    }
+
+    pub fn add_section(&mut self, label: &str, bytes: &[u8]) {
+        let label = &label.as_bytes()[..label.len().min(255)];
+
+        self.data.push(label.len() as u8);
+        self.data.extend_from_slice(label);
+        self.data.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
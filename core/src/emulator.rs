@@ -0,0 +1,871 @@
+use super::color;
+use super::config;
+use super::cpu;
+use super::debugger;
+use super::display;
+use super::dump;
+use super::game;
+use super::graphics;
+use super::idle;
+use super::mmu;
+use super::ops;
+use super::osd;
+use super::pacing;
+use super::ppu;
+use super::savestate;
+use super::trace;
+use super::utils;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// Gets the instance id and the frame number (frames finished since power on) as
+// each frame finishes
+pub type VBlankCallback = Box<dyn FnMut(u32, u32)>;
+
+// Ids handed out to each new instance, only ever counts up
+static NEXT_INSTANCE_ID: AtomicU32 = AtomicU32::new(1);
+
+pub struct Emulator {
+    // Instances are fully independent, the id and log prefix are only so a page
+    // running several of them can tell them apart
+    id: u32,
+    log_prefix: String,
+
+    pub(crate) cpu: cpu::Cpu,
+    debugger: debugger::Debugger,
+    idle_detector: idle::IdleDetector,
+    config: config::EmulatorConfig,
+    frame_converter: display::FrameConverter,
+
+    // The frame upscaled to the configured scale after every update, empty
+    // while the scale is 1 (see set_scale)
+    scaled_frame: Vec<u8>,
+
+    osd: osd::Osd,
+    audio_pacer: pacing::AudioPacer,
+    tracer: trace::Tracer,
+
+    // Called as V-Blank starts, with the frame number it was last called with
+    vblank_callback: Option<VBlankCallback>,
+    last_frame_number: u32,
+}
+
+impl Emulator {
+    pub fn new(game: game::Game) -> Emulator {
+        let id = NEXT_INSTANCE_ID.fetch_add(1, Ordering::Relaxed);
+        let log_prefix = format!("gb{}", id);
+        let _log_scope = utils::LogScope::enter(&log_prefix);
+
+        Emulator {
+            id,
+            log_prefix,
+            cpu: cpu::Cpu::new(game),
+            debugger: debugger::Debugger::new(id),
+            idle_detector: idle::IdleDetector::new(),
+            config: config::EmulatorConfig::default(),
+            frame_converter: display::FrameConverter::new(),
+            scaled_frame: Vec::new(),
+            osd: osd::Osd::new(),
+            audio_pacer: pacing::AudioPacer::new(),
+            tracer: trace::Tracer::new(),
+            vblank_callback: None,
+            last_frame_number: 0,
+        }
+    }
+
+    pub fn load_boot_rom(&mut self, boot_rom: &[u8]) -> Result<(), String> {
+        // Runs a real 256 byte DMG boot ROM (logo scroll and all) before the game,
+        // instead of starting at 0x0100 with everything already set up. Needs to be
+        // called before anything has run, the CPU starts over from 0x0000
+        if boot_rom.len() != 0x100 {
+            return Err(format!("Boot ROM should be 256 bytes, got {}", boot_rom.len()));
+        }
+
+        self.cpu.mmu.set_boot_rom(boot_rom);
+        self.cpu.start_boot_rom();
+        Ok(())
+    }
+
+    pub fn set_model(&mut self, model: cpu::Model) {
+        // Which hardware to act like when there's no boot ROM, DMG by default. Like
+        // load_boot_rom this needs to be called before anything has run
+        self.cpu.skip_boot_rom(model);
+    }
+
+    pub fn update(&mut self) {
+        // Gameboy can execute 4194304 cycles per second and
+        // we will be emulating at 60 fps. In other words, this
+        // function should be called 60 times per second as it represents
+        // a single frame update
+
+        // 4194304/60 = 66905
+        // Scaled by the configured speed for fast forward / slow motion
+        let max_cycles_per_frame = (pacing::CYCLES_PER_FRAME as f64 * self.config.speed) as usize;
+
+        // Stay paused until the debugger is told to resume
+        if self.debugger.is_broken() {
+            return;
+        }
+
+        // Nothing runs while stopped, a button press wakes the CPU (see set_joypad)
+        if !self.cpu.is_stopped() {
+            self.run_cycles(max_cycles_per_frame);
+        }
+
+        // Frame Update
+        if !self.debugger.is_broken() {
+            self.osd.tick();
+        }
+
+        self.do_scale_frame();
+    }
+
+    pub fn update_audio_paced(&mut self, samples_consumed: u32, buffer_fill: u32) {
+        // Alternative to update() for frontends that pace emulation off audio.
+        // Call it whenever the audio consumer reports in, with the samples it has
+        // played since the last call and how many are still queued. Runs however
+        // many cycles keep the buffer at the configured target, ignoring speed
+        if self.debugger.is_broken() {
+            return;
+        }
+
+        // Don't build up a backlog of cycles to run while stopped
+        if self.cpu.is_stopped() {
+            self.audio_pacer.reset();
+            return;
+        }
+
+        let audio = &self.config.audio;
+        let cycles = self.audio_pacer.get_cycles(samples_consumed, buffer_fill, audio.target_buffer, audio.sample_rate);
+        let cycles_run = self.run_cycles(cycles);
+
+        for _ in 0..self.audio_pacer.add_cycles(cycles_run) {
+            self.osd.tick();
+        }
+
+        self.do_scale_frame();
+    }
+
+    pub fn set_audio_pacing(&mut self, sample_rate: u32, target_buffer: u32) {
+        // Sample rate of the audio consumer and how many samples it should be
+        // kept topped up with
+        self.config.audio.sample_rate = sample_rate;
+        self.config.audio.target_buffer = target_buffer;
+        self.audio_pacer.reset();
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn set_log_prefix(&mut self, prefix: &str) {
+        // Log lines from this instance are shown as "[prefix] message", "gb<id>"
+        // by default. An empty prefix turns tagging off
+        self.log_prefix = String::from(prefix);
+    }
+
+    pub fn set_idle_skipping(&mut self, enabled: bool) {
+        // Opt-in speedhack, see idle.rs
+        self.config.idle_skipping = enabled;
+        self.idle_detector.set_enabled(enabled);
+    }
+
+    pub fn set_pixel_fifo(&mut self, enabled: bool) {
+        // Slower, but gets raster effects right (see fifo.rs)
+        self.config.pixel_fifo = enabled;
+        self.cpu.ppu.set_pixel_fifo(enabled);
+    }
+
+    pub fn set_frame_skip(&mut self, frames: u8) {
+        // Only draws one frame in every frames + 1, for devices too slow to draw
+        // them all. Everything else (timing, interrupts, STAT) carries on as
+        // normal, the screen just keeps the last frame drawn. 0 draws them all
+        self.config.frame_skip = frames;
+        self.cpu.ppu.set_frame_skip(frames);
+    }
+
+    pub fn set_oam_bug(&mut self, enabled: bool) {
+        // Accuracy option, off by default. On a DMG, INC rr / DEC rr and accesses
+        // pointing into 0xFE00 - 0xFEFF while the PPU is scanning OAM corrupt
+        // sprite memory (see oam_bug.rs). A few test ROMs check for it, games only
+        // ever run into it by mistake
+        self.config.oam_bug = enabled;
+        self.cpu.set_oam_bug(enabled);
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        self.config.speed = speed.max(0.0);
+    }
+
+    pub fn set_palette(&mut self, palette: graphics::GraphicsPalette, colors: &[u8]) {
+        // Overrides the RGB shown for each shade of a palette, colors is 4 RGB
+        // triples from lightest to darkest. The palette registers are untouched
+        if colors.len() < 12 {
+            let _log_scope = self.log_scope();
+            log!("Palette needs 12 values, got {}", colors.len());
+            return;
+        }
+
+        let mut shades = [[0; 3]; 4];
+        for (i, shade) in shades.iter_mut().enumerate() {
+            shade.copy_from_slice(&colors[i * 3..i * 3 + 3]);
+        }
+
+        self.config.palettes[palette as usize] = shades;
+        self.cpu.ppu.set_palette(palette as usize, shades);
+    }
+
+    pub fn set_palette_color(&mut self, palette: graphics::GraphicsPalette, shade: u8, red: u8, green: u8, blue: u8) {
+        // Overrides a single shade of a palette
+        let mut colors = Vec::with_capacity(12);
+        for rgb in self.config.palettes[palette as usize].iter() {
+            colors.extend_from_slice(rgb);
+        }
+
+        let index = (shade as usize % 4) * 3;
+        colors[index] = red;
+        colors[index + 1] = green;
+        colors[index + 2] = blue;
+
+        self.set_palette(palette, &colors);
+    }
+
+    pub fn set_palette_preset(&mut self, preset: color::PalettePreset) {
+        // Puts the same built in shades on the background and both sprite
+        // palettes, set_palette can change any of them afterwards
+        let colors: Vec<u8> = preset.get_shades().iter().flatten().copied().collect();
+        for palette in [graphics::GraphicsPalette::Bgp, graphics::GraphicsPalette::Obp0, graphics::GraphicsPalette::Obp1] {
+            self.set_palette(palette, &colors);
+        }
+    }
+
+    pub fn set_color_correction(&mut self, correction: color::ColorCorrection) {
+        self.config.filters.color_correction = correction;
+    }
+
+    pub fn convert_cgb_color(&self, color: u16) -> Vec<u8> {
+        // 15-bit CGB color to RGB, using the configured color correction
+        color::convert_cgb_color(color, self.config.filters.color_correction).to_vec()
+    }
+
+    pub fn set_display_adjustments(&mut self, brightness: f32, contrast: f32, gamma: f32) {
+        let adjustments = display::DisplayAdjustments {
+            brightness,
+            contrast,
+            gamma,
+        }.clamped();
+
+        self.config.display = adjustments;
+        self.frame_converter.set_adjustments(adjustments);
+    }
+
+    pub fn get_frame(&self) -> Vec<u8> {
+        // The finished frame as the frontend should show it, blended with the
+        // last one if frame blending is on and with display adjustments applied
+        let screen = self.cpu.ppu.get_screen_data();
+        let mut frame = match self.cpu.ppu.get_previous_screen_data() {
+            Some(previous) => self.frame_converter.convert(&display::blend_frames(screen, previous)),
+            None           => self.frame_converter.convert(screen),
+        };
+        self.osd.draw(&mut frame, 160, 144);
+        frame
+    }
+
+    pub fn screenshot_png(&self) -> Result<Vec<u8>, String> {
+        // The frame as get_frame gives it, encoded as a 160x144 PNG ready to be
+        // saved as is
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 160, 144);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer.write_image_data(&self.get_frame()).map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| e.to_string())?;
+
+        Ok(png)
+    }
+
+    pub fn screen_ptr(&self) -> *const u8 {
+        // The PPU's own 160x144 RGBA buffer, for frontends that read it straight
+        // out of memory rather than copying it with get_frame. It's the raw frame,
+        // without display adjustments or on screen messages. It only ever holds
+        // finished frames, lines are drawn somewhere else first
+        self.cpu.ppu.get_screen_data().as_ptr()
+    }
+
+    pub fn screen_len(&self) -> usize {
+        self.cpu.ppu.get_screen_data().len()
+    }
+
+    pub fn osd_message(&mut self, text: &str, frames: u32) {
+        // Shows a message over the frame for the given number of frames
+        self.osd.show(text, frames);
+    }
+
+    pub fn clear_osd(&mut self) {
+        self.osd.clear();
+    }
+
+    pub fn set_frame_blending(&mut self, enabled: bool) {
+        // LCD ghosting, see display::blend_frames
+        self.config.filters.frame_blending = enabled;
+        self.cpu.ppu.set_frame_blending(enabled);
+    }
+
+    pub fn set_layer_enabled(&mut self, layer: ppu::Layer, enabled: bool) {
+        // Hides the background, window or sprites from the frame for debugging.
+        // LCDC is left alone, so the game carries on as if nothing changed
+        self.cpu.ppu.set_layer_enabled(layer, enabled);
+    }
+
+    pub fn set_lcd_grid(&mut self, strength: f32) {
+        self.config.filters.lcd_grid = if strength.is_nan() { 0.0 } else { strength.clamp(0.0, 1.0) };
+    }
+
+    pub fn get_scaled_frame(&self, scale: usize) -> Vec<u8> {
+        // The frame upscaled by an integer factor, with the LCD grid effect if
+        // it is turned on
+        let frame = self.get_frame();
+        display::scale_frame(&frame, 160, 144, scale, self.config.filters.lcd_grid)
+    }
+
+    pub fn set_scale(&mut self, scale: usize) {
+        // Keeps an upscaled copy of the frame (2x, 3x or 4x, with the LCD grid if
+        // it's on) up to date after every update, for frontends to read through
+        // scaled_frame_ptr rather than scaling it themselves. 1 turns it off
+        self.config.filters.scale = scale.clamp(1, 4);
+        self.do_scale_frame();
+    }
+
+    pub fn get_scale(&self) -> usize {
+        self.config.filters.scale
+    }
+
+    pub fn scaled_frame_ptr(&self) -> *const u8 {
+        // (160 * scale) x (144 * scale) RGBA, as get_scaled_frame would give it.
+        // The buffer moves when the scale changes
+        self.scaled_frame.as_ptr()
+    }
+
+    pub fn scaled_frame_len(&self) -> usize {
+        self.scaled_frame.len()
+    }
+
+    pub fn export_config(&self) -> String {
+        self.config.to_json()
+    }
+
+    pub fn import_config(&mut self, json: &str) -> Result<(), String> {
+        let _log_scope = self.log_scope();
+        let config = config::EmulatorConfig::from_json(json)?;
+        self.apply_config(config);
+        Ok(())
+    }
+
+    pub fn set_joypad(&mut self, player: u8, state: u8) {
+        // Button state for a player, bits are the same as the Mmu joypad byte
+        // (0 = pressed). Players 2 - 4 are only visible with SGB multiplayer on
+        let _log_scope = self.log_scope();
+        let previous = self.cpu.mmu.get_joypad(player);
+        self.cpu.mmu.set_joypad(player, state);
+
+        // A button going from released to pressed wakes the CPU from STOP
+        if previous & !state > 0 {
+            self.cpu.wake_from_stop();
+        }
+    }
+
+    pub fn set_sgb_multiplayer(&mut self, enabled: bool) {
+        self.cpu.mmu.set_sgb_enabled(enabled);
+    }
+
+    pub fn add_breakpoint(&mut self, pc: u16) {
+        self.debugger.add_breakpoint(pc);
+    }
+
+    pub fn remove_breakpoint(&mut self, pc: u16) {
+        self.debugger.remove_breakpoint(pc);
+    }
+
+    pub fn break_on_interrupt(&mut self, bit: u8) {
+        self.add_event_breakpoint(debugger::BreakKind::Interrupt, Some(bit));
+    }
+
+    pub fn break_on_mode(&mut self, mode: u8) {
+        self.add_event_breakpoint(debugger::BreakKind::ModeChange, Some(mode));
+    }
+
+    pub fn break_on_bank_switch(&mut self) {
+        self.add_event_breakpoint(debugger::BreakKind::RomBankSwitch, None);
+        self.add_event_breakpoint(debugger::BreakKind::RamBankSwitch, None);
+    }
+
+    pub fn clear_event_breakpoints(&mut self) {
+        self.debugger.clear_event_breakpoints();
+        self.cpu.mmu.set_event_recording(false);
+    }
+
+    pub fn break_info(&self) -> Option<debugger::BreakInfo> {
+        self.debugger.get_break_info()
+    }
+
+    pub fn resume(&mut self) {
+        // Run the instruction we stopped on so we don't immediately break on the
+        // same PC breakpoint again
+        if self.debugger.is_broken() {
+            let _log_scope = self.log_scope();
+            self.debugger.resume();
+
+            self.step();
+            self.cpu.sync();
+            self.cpu.mmu.take_events();
+        }
+    }
+
+    pub fn step_instruction(&mut self) -> debugger::StepInfo {
+        // Runs exactly one instruction and says what it was. Works whether or not
+        // the debugger is broken, and leaves it how it was
+        let _log_scope = self.log_scope();
+        let pc = self.cpu.program_counter;
+        let running = !self.cpu.is_halted() && !self.cpu.is_stopped() && !self.cpu.is_locked();
+
+        let (bytes, mnemonic) = if running {
+            self.describe_instruction(pc)
+        } else {
+            (Vec::new(), "")
+        };
+
+        let cycles = if self.cpu.is_stopped() { 0 } else { self.step() };
+        self.cpu.sync();
+
+        // Nothing is checking events, so don't leave them for the next run to break on
+        self.cpu.mmu.take_events();
+
+        debugger::StepInfo {
+            bytes,
+            mnemonic: String::from(mnemonic),
+            cycles: cycles as u32,
+            pc: self.cpu.program_counter,
+        }
+    }
+
+    pub fn mbc_state(&self) -> mmu::MbcState {
+        self.cpu.mmu.get_mbc_state()
+    }
+
+    pub fn enable_bank_log(&mut self, capacity: usize) {
+        self.cpu.mmu.enable_bank_log(capacity);
+    }
+
+    pub fn disable_bank_log(&mut self) {
+        self.cpu.mmu.disable_bank_log();
+    }
+
+    pub fn clear_bank_log(&mut self) {
+        self.cpu.mmu.clear_bank_log();
+    }
+
+    pub fn bank_log(&self) -> Vec<debugger::BankSwitchEntry> {
+        self.cpu.mmu.get_bank_log()
+    }
+
+    pub fn get_registers(&self) -> cpu::Registers {
+        self.cpu.get_registers()
+    }
+
+    pub fn set_register(&mut self, name: &str, value: u16) -> Result<(), String> {
+        // See Cpu::set_register for the names
+        self.cpu.set_register(name, value)
+    }
+
+    pub fn timer_state(&self) -> cpu::TimerState {
+        self.cpu.get_timer_state()
+    }
+
+    pub fn lock_info(&self) -> Option<cpu::LockInfo> {
+        // Set once the game has run an illegal opcode, which hangs a real Gameboy.
+        // The screen and timers keep going but no more code runs
+        self.cpu.get_lock_info()
+    }
+
+    pub fn set_ppu_timeline(&mut self, enabled: bool) {
+        self.cpu.ppu.timeline.set_enabled(enabled);
+    }
+
+    pub fn ppu_timeline(&self) -> Result<String, String> {
+        // Per scanline PPU activity for the last full frame as JSON - dots spent
+        // in each mode, sprites selected, and whether the window was drawn or a
+        // STAT interrupt was requested. Empty until a frame has been recorded
+        let timeline = self.cpu.ppu.timeline.get_last_frame();
+        serde_json::to_string(timeline).map_err(|e| e.to_string())
+    }
+
+    pub fn ppu_stats(&self) -> Result<String, String> {
+        // Counters for the last full frame as JSON - sprites selected and dropped
+        // (past the 10 per line limit) on each line, lines the window was drawn
+        // on, dots spent in each mode and STAT interrupts requested. Sprites
+        // dropped on a line are the usual reason a game flickers
+        let stats = self.cpu.ppu.get_frame_stats();
+        serde_json::to_string(stats).map_err(|e| e.to_string())
+    }
+
+    pub fn set_trace(&mut self, enabled: bool) {
+        // Gameboy Doctor style CPU trace, see trace.rs
+        self.tracer.set_enabled(enabled);
+    }
+
+    pub fn set_trace_callback(&mut self, callback: Option<trace::TraceCallback>) {
+        // Streams trace lines as they're made instead of keeping them for take_trace
+        self.tracer.set_callback(callback);
+    }
+
+    pub fn on_vblank(&mut self, callback: Option<VBlankCallback>) {
+        // The callback runs once for each frame the PPU finishes, from inside
+        // update as V-Blank starts, with this instance's id and the number of the
+        // frame. None stops it
+        self.last_frame_number = self.cpu.ppu.get_frame_number();
+        self.vblank_callback = callback;
+    }
+
+    pub fn take_trace(&mut self) -> String {
+        self.tracer.take()
+    }
+
+    pub fn rtc_registers(&self) -> Vec<u8> {
+        self.cpu.mmu.get_rtc_registers()
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        self.cpu.save_state().to_bytes()
+    }
+
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), String> {
+        let state = savestate::SaveState::from_bytes(state)?;
+        self.cpu.load_state(&state);
+        Ok(())
+    }
+
+    pub fn diff_states(&self, before: &[u8], after: &[u8]) -> Result<String, String> {
+        // Structured diff of two savestates as JSON - registers, IO registers
+        // and ranges of memory that changed
+        let before = savestate::SaveState::from_bytes(before)?;
+        let after = savestate::SaveState::from_bytes(after)?;
+        let diff = savestate::diff(&before, &after);
+
+        serde_json::to_string(&diff).map_err(|e| e.to_string())
+    }
+
+    pub fn diff_with_current(&self, state: &[u8]) -> Result<String, String> {
+        // Same as diff_states, comparing a savestate against the running emulator
+        let current = self.save_state();
+        self.diff_states(state, &current)
+    }
+
+    pub fn export_tile(&self, tile: usize, palette: graphics::GraphicsPalette) -> Vec<u8> {
+        // A single 8x8 tile (0 - 383) as RGBA
+        let mut image = vec![0; 8 * 8 * 4];
+        self.do_draw_tile(&mut image, 8, 0, 0, tile, palette);
+        image
+    }
+
+    pub fn export_sprite_sheet(&self, palette: graphics::GraphicsPalette) -> Vec<u8> {
+        // All 256 tiles sprites can use (0x8000 - 0x8FFF) as a 128x128 RGBA image,
+        // 16 tiles per row. 8x16 sprites end up as horizontally adjacent pairs
+        let mut image = vec![0; 128 * 128 * 4];
+        for tile in 0..256 {
+            self.do_draw_tile(&mut image, 128, (tile % 16) * 8, (tile / 16) * 8, tile, palette);
+        }
+
+        image
+    }
+
+    pub fn debug_tiles(&self) -> Vec<u8> {
+        // All 384 VRAM tiles as one 128x192 RGBA image, 16 tiles per row, shaded
+        // by their raw color ids for inspecting graphics
+        graphics::draw_tile_atlas(&self.cpu.mmu, &self.config.palettes[ppu::BG_PALETTE])
+    }
+
+    pub fn debug_palette(&self, palette: graphics::GraphicsPalette) -> Vec<u8> {
+        // The RGB each color id (0 - 3) currently comes out as, 4 RGB triples in
+        // color id order. Goes through the palette register as the game has it
+        // set right now and the shades configured for that palette
+        let palette_value = self.cpu.mmu.peek_memory(&palette.get_address());
+        let shades = &self.config.palettes[palette as usize];
+
+        (0..4)
+            .flat_map(|color_num| shades[graphics::get_shade(palette_value, color_num) as usize])
+            .collect()
+    }
+
+    pub fn debug_oam(&self) -> Result<String, String> {
+        // All 40 OAM entries as a JSON array, with whether each one is on screen
+        // and whether it was drawn last frame (see graphics::OamEntry)
+        let entries: Vec<graphics::OamEntry> = (0..graphics::OAM_SPRITE_COUNT)
+            .map(|sprite| {
+                let mut entry = graphics::get_oam_entry(&self.cpu.mmu, sprite, self.get_sprite_height());
+                entry.rendered = self.cpu.ppu.is_sprite_rendered(sprite);
+                entry
+            })
+            .collect();
+
+        serde_json::to_string(&entries).map_err(|e| e.to_string())
+    }
+
+    pub fn debug_oam_thumbnails(&self) -> Vec<u8> {
+        // Every sprite as a 64x80 RGBA image, 8 to a row in OAM order with each
+        // one in an 8x16 cell, drawn with its flips and palette
+        let shades = [self.config.palettes[ppu::OBJ_0_PALETTE], self.config.palettes[ppu::OBJ_1_PALETTE]];
+        graphics::draw_oam_thumbnails(&self.cpu.mmu, self.get_sprite_height(), &shades)
+    }
+
+    pub fn export_tilemap(&self, layer: graphics::TilemapLayer) -> Vec<u8> {
+        // The whole 256x256 background or window map as RGBA, regardless of
+        // scroll position, using the tile map and tile data LCDC currently selects
+        let lcd_control = self.cpu.mmu.peek_memory(&utils::LCD_CONTROL_ADDR);
+
+        // Bit 3 selects the background map, bit 6 the window map
+        // 8 = 0b00001000, 64 = 0b01000000
+        let map_select = match layer {
+            graphics::TilemapLayer::Background => lcd_control & 8 > 0,
+            graphics::TilemapLayer::Window     => lcd_control & 64 > 0,
+        };
+        let map_address = if map_select { 0x9C00 } else { 0x9800 };
+
+        // Bit 4 selects the tile data region, 16 = 0b00010000
+        let unsigned = lcd_control & 16 > 0;
+
+        graphics::draw_tilemap(&self.cpu.mmu, map_address, unsigned, &self.config.palettes[ppu::BG_PALETTE])
+    }
+
+    pub fn debug_tilemap(&self, map: graphics::TileMap, data: graphics::TileData, show_viewport: bool) -> Vec<u8> {
+        // Either 256x256 tile map as RGBA with either tile data region, whatever
+        // LCDC has picked. The part of the background that's on screen can be
+        // outlined in red
+        let unsigned = data == graphics::TileData::Data8000;
+        let mut image = graphics::draw_tilemap(&self.cpu.mmu, map.get_address(), unsigned, &self.config.palettes[ppu::BG_PALETTE]);
+
+        if show_viewport {
+            let scroll_x = self.cpu.mmu.peek_memory(&utils::SCROLL_X_ADDR);
+            let scroll_y = self.cpu.mmu.peek_memory(&utils::SCROLL_Y_ADDR);
+            graphics::draw_viewport(&mut image, scroll_x, scroll_y, [0xFF, 0x00, 0x00]);
+        }
+
+        image
+    }
+
+    pub fn dump_memory(&self) -> Vec<u8> {
+        // Everything in memory as one labeled blob (see dump.rs) for hex editors
+        // and bug reports. The address space is as the CPU currently sees it,
+        // with whatever banks are mapped in
+        let mut dump = dump::MemoryDump::new();
+
+        dump.add_section("address_space", &self.peek_range(0, 0x10000));
+        dump.add_section("external_ram", &self.cpu.mmu.get_external_ram());
+
+        let rtc = self.cpu.mmu.get_rtc_registers();
+        if !rtc.is_empty() {
+            dump.add_section("rtc", &rtc);
+        }
+
+        dump.into_bytes()
+    }
+
+    pub fn peek(&self, address: u16) -> u8 {
+        // Side-effect free read for the debugger, disassembler, watch lists
+        // and memory viewer
+        self.cpu.mmu.peek_memory(&(address as usize))
+    }
+
+    pub fn peek_range(&self, address: u16, length: usize) -> Vec<u8> {
+        // Same as peek, but for a block of memory. Wraps around at the end of
+        // the address space
+        (0..length)
+            .map(|i| self.cpu.mmu.peek_memory(&((address as usize + i) & 0xFFFF)))
+            .collect()
+    }
+
+    pub(crate) fn log_scope(&self) -> utils::LogScope {
+        utils::LogScope::enter(&self.log_prefix)
+    }
+
+    fn run_cycles(&mut self, cycles: usize) -> usize {
+        // Runs instructions until at least the given number of cycles have passed,
+        // or the debugger breaks. Returns the cycles that actually ran
+        let _log_scope = self.log_scope();
+        let debugging = self.debugger.is_active();
+        let mut cycles_run = 0;
+
+        while cycles_run < cycles && !self.cpu.is_stopped() {
+            cycles_run += self.step();
+            self.check_vblank();
+
+            // The debugger looks at the hardware after every instruction
+            if debugging {
+                self.cpu.sync();
+                if self.check_debugger() {
+                    break;
+                }
+            }
+        }
+
+        // Leave everything caught up for whatever looks at the state between frames
+        self.cpu.sync();
+        self.check_vblank();
+
+        cycles_run
+    }
+
+    fn check_vblank(&mut self) {
+        // The PPU is synced at least as often as it changes line, so a new frame
+        // number turns up here as V-Blank starts
+        let callback = match self.vblank_callback.as_mut() {
+            Some(callback) => callback,
+            None           => return,
+        };
+
+        let frame_number = self.cpu.ppu.get_frame_number();
+        if frame_number != self.last_frame_number {
+            self.last_frame_number = frame_number;
+            callback(self.id, frame_number);
+        }
+    }
+
+    pub(crate) fn step(&mut self) -> usize {
+        // Runs a single instruction, returns the number of cycles that passed. The
+        // rest of the hardware may be left behind until it's next needed (see sync)
+        if self.tracer.is_enabled() && !self.cpu.is_halted() && !self.cpu.is_locked() {
+            self.tracer.trace(&self.cpu, self.id);
+        }
+
+        let previous_pc = self.cpu.program_counter;
+        let mut cycles = self.cpu.execute_op();
+
+        // If the CPU is just waiting on hardware, jump straight to the next event
+        // instead of running the wait loop an instruction at a time
+        if self.idle_detector.is_enabled() && self.is_idle(previous_pc) {
+            // Whole machine cycles, like everything else the CPU does
+            self.cpu.sync();
+            let skipped = (self.cpu.cycles_until_next_event() + 3) & !3;
+            self.cpu.tick(skipped);
+            cycles += skipped;
+        }
+
+        // Dispatching an interrupt takes 20 cycles of its own on top
+        cycles += self.cpu.do_interrupts();
+
+        cycles
+    }
+
+    fn describe_instruction(&self, pc: u16) -> (Vec<u8>, &'static str) {
+        // The bytes and mnemonic of the instruction at pc
+        let opcode = self.peek(pc);
+        let handler = if opcode == 0xCB {
+            ops::get_cb_handler(self.peek(pc.wrapping_add(1)))
+        } else {
+            ops::get_handler(opcode)
+        };
+
+        (self.peek_range(pc, handler.length as usize), handler.mnemonic)
+    }
+
+    fn add_event_breakpoint(&mut self, kind: debugger::BreakKind, value: Option<u8>) {
+        self.debugger.add_event_breakpoint(kind, value);
+        self.cpu.mmu.set_event_recording(true);
+    }
+
+    fn get_sprite_height(&self) -> u8 {
+        // LCDC bit 2 makes every sprite 8x16, 4 = 0b00000100
+        let lcd_control = self.cpu.mmu.peek_memory(&utils::LCD_CONTROL_ADDR);
+        if lcd_control & 4 > 0 { 16 } else { 8 }
+    }
+
+    fn do_scale_frame(&mut self) {
+        let scale = self.config.filters.scale;
+        if scale <= 1 {
+            self.scaled_frame = Vec::new();
+            return;
+        }
+
+        self.scaled_frame.resize(160 * scale * 144 * scale * 4, 0);
+        let frame = self.get_frame();
+        display::scale_frame_into(&mut self.scaled_frame, &frame, 160, 144, scale, self.config.filters.lcd_grid);
+    }
+
+    fn do_draw_tile(&self, image: &mut [u8], width: usize, x: usize, y: usize, tile: usize, palette: graphics::GraphicsPalette) {
+        let pixels = graphics::decode_tile(&self.cpu.mmu, tile);
+        let palette_value = self.cpu.mmu.peek_memory(&palette.get_address());
+
+        graphics::draw_tile(
+            image,
+            width,
+            x,
+            y,
+            &pixels,
+            palette_value,
+            &self.config.palettes[palette as usize],
+            palette.is_sprite_palette(),
+        );
+    }
+
+    fn apply_config(&mut self, config: config::EmulatorConfig) {
+        // Push every setting that the emulator acts on down to where it lives,
+        // the rest is kept for the frontend to read back
+        self.idle_detector.set_enabled(config.idle_skipping);
+        self.cpu.ppu.set_pixel_fifo(config.pixel_fifo);
+        self.cpu.ppu.set_frame_skip(config.frame_skip);
+        self.cpu.set_oam_bug(config.oam_bug);
+        self.cpu.ppu.set_frame_blending(config.filters.frame_blending);
+
+        for (i, shades) in config.palettes.iter().enumerate() {
+            self.cpu.ppu.set_palette(i, *shades);
+        }
+        self.config = config;
+
+        // Settings with limits go back through their setters, so an imported
+        // config can't hold anything the setters would have turned down
+        let display = self.config.display;
+        self.set_speed(self.config.speed);
+        self.set_lcd_grid(self.config.filters.lcd_grid);
+        self.set_display_adjustments(display.brightness, display.contrast, display.gamma);
+        self.set_scale(self.config.filters.scale);
+    }
+
+    fn is_idle(&mut self, previous_pc: u16) -> bool {
+        // A locked CPU never runs anything again, interrupts included
+        if self.cpu.is_locked() {
+            return true;
+        }
+
+        // A pending interrupt either gets serviced or ends HALT straight away, so
+        // the CPU isn't waiting on anything
+        if self.cpu.mmu.get_interrupts().get_pending() > 0 {
+            return false;
+        }
+
+        if self.cpu.is_halted() {
+            return true;
+        }
+
+        let pc = self.cpu.program_counter;
+        let writes = self.cpu.get_write_count();
+        let registers = self.cpu.get_registers();
+        self.idle_detector.observe(previous_pc, pc, writes, &registers)
+    }
+
+    fn check_debugger(&mut self) -> bool {
+        // Checks whatever happened during the last instruction against the debugger,
+        // returns true if we should stop running
+        let pc = self.cpu.program_counter;
+        let scanline = self.cpu.mmu.peek_memory(&utils::CURRENT_SCANLINE_ADDR);
+
+        if self.debugger.wants_events() {
+            let events = self.cpu.mmu.take_events();
+            if self.debugger.check_events(&events, pc, scanline) {
+                return true;
+            }
+        }
+
+        self.debugger.check_breakpoint(pc, scanline)
+    }
+}
@@ -0,0 +1,229 @@
+use super::utils;
+use super::mmu;
+
+use std::collections::VecDeque;
+
+// Pixel FIFO
+//
+// The other way of drawing a line (see ppu.rs). Rather than drawing the whole line
+// as it starts, the background and window are fetched a tile at a time and shifted
+// out to the LCD a pixel per dot through mode 3, like the hardware does it. SCX,
+// SCY, LCDC and BGP are read as each tile or pixel needs them, so changes made
+// partway through a line (raster effects) land where they would on hardware.
+// Sprites are still drawn over the line once it's done.
+//
+// The fetcher takes 2 dots for each of its steps - reading the tile number, the low
+// byte of the tile row and the high byte - then waits until the FIFO is empty to
+// push the 8 pixels. The first SCX % 8 pixels of the line are thrown away.
+
+pub struct PixelFifo {
+    // Set from the start of mode 3 until the whole line has been drawn
+    active: bool,
+    line: u8,
+
+    // The line of the window to draw if it comes in, and whether LY has matched
+    // WY yet this frame so it can (see Ppu's window_line)
+    window_line: u8,
+    window_y_triggered: bool,
+
+    // Colour numbers (0 - 3) waiting to be shifted out
+    pixels: VecDeque<u8>,
+
+    // Dots into the current fetch, and which tile along the line it's for
+    fetcher_dots: u8,
+    fetcher_x: u8,
+    tile_number: u8,
+    data_lo: u8,
+    data_hi: u8,
+
+    // The next pixel of the line to go out, and how many still need throwing away
+    x: u8,
+    discard: u8,
+
+    // Whether the fetcher has switched over to the window for the rest of the line
+    window: bool,
+
+    // Whether the background and window are shown, hidden ones come out as shade
+    // 0 (see Ppu's layers_enabled)
+    show_background: bool,
+    show_window: bool,
+}
+
+impl PixelFifo {
+    pub fn new() -> PixelFifo {
+        PixelFifo {
+            active: false,
+            line: 0,
+            window_line: 0,
+            window_y_triggered: false,
+            pixels: VecDeque::with_capacity(16),
+            fetcher_dots: 0,
+            fetcher_x: 0,
+            tile_number: 0,
+            data_lo: 0,
+            data_hi: 0,
+            x: 0,
+            discard: 0,
+            window: false,
+            show_background: true,
+            show_window: true,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn is_using_window(&self) -> bool {
+        self.window
+    }
+
+    pub fn set_layers_enabled(&mut self, background: bool, window: bool) {
+        self.show_background = background;
+        self.show_window = window;
+    }
+
+    pub fn start_line(&mut self, mmu: &mmu::Mmu, line: u8, window_line: u8, window_y_triggered: bool) {
+        self.active = true;
+        self.line = line;
+        self.window_line = window_line;
+        self.window_y_triggered = window_y_triggered;
+        self.pixels.clear();
+        self.fetcher_dots = 0;
+        self.fetcher_x = 0;
+        self.x = 0;
+        self.discard = mmu.read_memory(&utils::SCROLL_X_ADDR) & 7;
+        self.window = false;
+    }
+
+    pub fn tick(&mut self, mmu: &mmu::Mmu, row: &mut [u8], colors: &mut [u8; 160], palette: &[[u8; 3]; 4]) {
+        // One dot of mode 3. row is the line's 160 RGBA pixels, and colors gets the
+        // colour number of each one for the sprites drawn over them
+        if !self.active || self.x >= 160 {
+            return;
+        }
+
+        self.check_window(mmu);
+        self.tick_fetcher(mmu);
+
+        let color_num = match self.pixels.pop_front() {
+            Some(color_num) => color_num,
+            None            => return,
+        };
+
+        if self.discard > 0 {
+            self.discard -= 1;
+            return;
+        }
+
+        // With the background off (LCDC bit 0) the line is left blank, as shade 0
+        // whatever BGP says
+        let lcd_control = mmu.read_memory(&utils::LCD_CONTROL_ADDR);
+        let shown = if self.window { self.show_window } else { self.show_background };
+        let (color_num, shade) = if lcd_control & 1 == 0 {
+            (0, 0)
+        } else if shown {
+            let pallette = mmu.read_memory(&utils::COLOR_PALLETTE_ADDR);
+            (color_num, (pallette >> (color_num * 2)) & 3)
+        } else {
+            (color_num, 0)
+        };
+
+        colors[self.x as usize] = color_num;
+        let index = self.x as usize * 4;
+        row[index..index + 3].copy_from_slice(&palette[shade as usize]);
+        self.x += 1;
+    }
+
+    pub fn finish_line(&mut self, mmu: &mmu::Mmu, row: &mut [u8], colors: &mut [u8; 160], palette: &[[u8; 3]; 4]) {
+        // Runs whatever is left of the line once mode 3 is over. Each dot puts out
+        // at most a pixel, so 160 pixels plus a fetch or two is as long as it takes
+        while self.active && self.x < 160 {
+            self.tick(mmu, row, colors, palette);
+        }
+        self.active = false;
+    }
+
+    fn check_window(&mut self, mmu: &mmu::Mmu) {
+        // The window takes over once the line reaches WX - 7, if it's on (LCDC bit
+        // 5) and LY has matched WY this frame. The fetcher starts again from the
+        // window's first tile. WX over 166 keeps it off the line altogether
+        if self.window || !self.window_y_triggered {
+            return;
+        }
+
+        let lcd_control = mmu.read_memory(&utils::LCD_CONTROL_ADDR);
+        let window_x = mmu.read_memory(&utils::WINDOW_X_ADDR);
+
+        // Before the SCX pixels have been thrown away the line hasn't started, so
+        // only a window at the left edge (WX 7 or less) can come in
+        if self.discard > 0 && window_x > 7 {
+            return;
+        }
+
+        if lcd_control & 32 > 0 && self.x as u16 + 7 >= window_x as u16 {
+            self.window = true;
+            self.pixels.clear();
+            self.fetcher_dots = 0;
+            self.fetcher_x = 0;
+
+            // With WX below 7 the window's first 7 - WX pixels are off the left
+            // edge, rather than the SCX ones
+            self.discard = 7u8.saturating_sub(window_x);
+        }
+    }
+
+    fn tick_fetcher(&mut self, mmu: &mmu::Mmu) {
+        self.fetcher_dots += 1;
+
+        match self.fetcher_dots {
+            2 => self.tile_number = mmu.read_memory(&self.get_tile_map_address(mmu)),
+            4 => self.data_lo = mmu.read_memory(&self.get_tile_data_address(mmu)),
+            6 => self.data_hi = mmu.read_memory(&(self.get_tile_data_address(mmu) + 1)),
+            d if d >= 7 && self.pixels.is_empty() => {
+                // Bit 7 is the leftmost pixel
+                for bit in (0..8).rev() {
+                    let color_num = (((self.data_hi >> bit) & 1) << 1) | ((self.data_lo >> bit) & 1);
+                    self.pixels.push_back(color_num);
+                }
+                self.fetcher_x = self.fetcher_x.wrapping_add(1);
+                self.fetcher_dots = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn get_tile_map_address(&self, mmu: &mmu::Mmu) -> usize {
+        // Background uses LCDC bit 3 to pick its map, the window bit 6
+        let lcd_control = mmu.read_memory(&utils::LCD_CONTROL_ADDR);
+        let map_bit = if self.window { 64 } else { 8 };
+        let map = if lcd_control & map_bit > 0 { 0x9C00 } else { 0x9800 };
+
+        let (tile_x, y) = if self.window {
+            (self.fetcher_x, self.window_line)
+        } else {
+            let scroll_x = mmu.read_memory(&utils::SCROLL_X_ADDR);
+            ((scroll_x / 8).wrapping_add(self.fetcher_x) & 31, self.get_background_y(mmu))
+        };
+
+        map + (y as usize / 8) * 32 + (tile_x as usize & 31)
+    }
+
+    fn get_tile_data_address(&self, mmu: &mmu::Mmu) -> usize {
+        // LCDC bit 4 picks unsigned tile numbers from 0x8000, or signed ones
+        // around 0x9000
+        let lcd_control = mmu.read_memory(&utils::LCD_CONTROL_ADDR);
+        let tile = if lcd_control & 16 > 0 {
+            0x8000 + self.tile_number as usize * 16
+        } else {
+            (0x9000 + (self.tile_number as i8 as isize) * 16) as usize
+        };
+
+        let y = if self.window { self.window_line } else { self.get_background_y(mmu) };
+        tile + (y as usize % 8) * 2
+    }
+
+    fn get_background_y(&self, mmu: &mmu::Mmu) -> u8 {
+        mmu.read_memory(&utils::SCROLL_Y_ADDR).wrapping_add(self.line)
+    }
+}
@@ -0,0 +1,56 @@
+pub struct Game {
+    // The full cartridge ROM. Carts range from 32KB with no banking up to
+    // several MB for multicarts, so this is sized to whatever was loaded
+    rom: Vec<u8>,
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
+}
+
+impl Game {
+    pub fn new() -> Game {
+        Game {
+            rom: vec![0; 0x8000],
+        }
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Game {
+        // Anything smaller than the two fixed ROM banks gets padded out so
+        // reads of 0000-7FFF always land in the cartridge
+        let mut rom = data.to_vec();
+        if rom.len() < 0x8000 {
+            rom.resize(0x8000, 0);
+        }
+
+        Game {
+            rom,
+        }
+    }
+
+    pub fn load_game_memory() {
+        log!("Loading game");
+    }
+
+    pub fn read_catridge_data(&self, address: usize) -> u8 {
+        // Reading past the end of the ROM wraps around, like it would on a
+        // cartridge where the upper address lines aren't connected
+        self.rom[address % self.rom.len()]
+    }
+
+    pub fn get_slice(&self, address: usize, length: usize) -> Option<&[u8]> {
+        // A run of the ROM, None if it goes past the end (where reads would wrap)
+        self.rom.get(address..address + length)
+    }
+
+    pub fn size(&self) -> usize {
+        self.rom.len()
+    }
+
+    pub fn rom_bank_count(&self) -> usize {
+        // Number of 16KB ROM banks
+        self.rom.len() / 0x4000
+    }
+}
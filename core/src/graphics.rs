@@ -0,0 +1,281 @@
+use super::mmu;
+use super::utils;
+
+use serde::Serialize;
+
+// Helpers for pulling graphics out of VRAM for exporting and debugging. Memory is
+// read with peek_memory so none of this affects the running game.
+
+// Which palette register to color graphics with
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GraphicsPalette {
+    Bgp = 0,
+    Obp0 = 1,
+    Obp1 = 2,
+}
+
+impl GraphicsPalette {
+    pub fn get_address(&self) -> usize {
+        match self {
+            GraphicsPalette::Bgp  => utils::COLOR_PALLETTE_ADDR,
+            GraphicsPalette::Obp0 => utils::SPRITE_PALLETTE_0_ADDR,
+            GraphicsPalette::Obp1 => utils::SPRITE_PALLETTE_1_ADDR,
+        }
+    }
+
+    // Color 0 is transparent for sprites
+    pub fn is_sprite_palette(&self) -> bool {
+        *self != GraphicsPalette::Bgp
+    }
+}
+
+// There are 384 tiles in VRAM from 0x8000 - 0x97FF, 16 bytes each
+pub const TILE_COUNT: usize = 384;
+
+// All 384 tiles laid out 16 to a row, in pixels
+pub const TILE_ATLAS_WIDTH: usize = 16 * 8;
+pub const TILE_ATLAS_HEIGHT: usize = TILE_COUNT / 16 * 8;
+
+// A palette register value that maps each color id to the same shade (3, 2, 1, 0
+// in bits 7-0), for showing tiles as they are stored
+const IDENTITY_PALETTE: u8 = 0xE4;
+
+// Decodes a tile into its 64 color ids (0 - 3), row by row. Each row of 8 pixels
+// is two bytes, the first holding the low bit of each color id and the second the
+// high bit, with bit 7 being the left most pixel
+pub fn decode_tile(mmu: &mmu::Mmu, tile: usize) -> [u8; 64] {
+    let mut pixels = [0; 64];
+    let tile_address = 0x8000 + (tile % TILE_COUNT) * 16;
+
+    for row in 0..8 {
+        let data_1 = mmu.peek_memory(&(tile_address + row * 2));
+        let data_2 = mmu.peek_memory(&(tile_address + row * 2 + 1));
+
+        for column in 0..8 {
+            let color_bit = 7 - column;
+            let mut color_num = (data_2 >> color_bit) & 1;
+            color_num <<= 1;
+            color_num |= (data_1 >> color_bit) & 1;
+
+            pixels[row * 8 + column] = color_num;
+        }
+    }
+
+    pixels
+}
+
+// Maps a color id through a palette register to a shade (0 - 3). Bits 1-0 of the
+// palette are the shade for color 0, bits 3-2 for color 1 and so on
+pub fn get_shade(palette: u8, color_num: u8) -> u8 {
+    (palette >> (color_num * 2)) & 0x3
+}
+
+// Draws a decoded tile into an RGBA image at (x, y)
+#[allow(clippy::too_many_arguments)]
+pub fn draw_tile(
+    image: &mut [u8],
+    image_width: usize,
+    x: usize,
+    y: usize,
+    pixels: &[u8; 64],
+    palette: u8,
+    shades: &[[u8; 3]; 4],
+    transparent_zero: bool,
+) {
+    for row in 0..8 {
+        for column in 0..8 {
+            let color_num = pixels[row * 8 + column];
+            let shade = shades[get_shade(palette, color_num) as usize];
+            let index = ((y + row) * image_width + x + column) * 4;
+
+            image[index] = shade[0];
+            image[index + 1] = shade[1];
+            image[index + 2] = shade[2];
+            image[index + 3] = if transparent_zero && color_num == 0 { 0 } else { 255 };
+        }
+    }
+}
+
+// Renders every tile in VRAM as a TILE_ATLAS_WIDTH x TILE_ATLAS_HEIGHT RGBA image,
+// tile 0 at the top left. Color ids are shown as is rather than through a palette
+// register, so tiles look the same whatever the game has BGP or OBP set to
+pub fn draw_tile_atlas(mmu: &mmu::Mmu, shades: &[[u8; 3]; 4]) -> Vec<u8> {
+    let mut image = vec![0; TILE_ATLAS_WIDTH * TILE_ATLAS_HEIGHT * 4];
+
+    for tile in 0..TILE_COUNT {
+        let pixels = decode_tile(mmu, tile);
+        draw_tile(&mut image, TILE_ATLAS_WIDTH, (tile % 16) * 8, (tile / 16) * 8, &pixels, IDENTITY_PALETTE, shades, false);
+    }
+
+    image
+}
+
+// Which layer's tile map to look at
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TilemapLayer {
+    Background = 0,
+    Window = 1,
+}
+
+// Which of the two tile maps, picked directly rather than through LCDC
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TileMap {
+    Map9800 = 0,
+    Map9C00 = 1,
+}
+
+impl TileMap {
+    pub fn get_address(&self) -> usize {
+        match self {
+            TileMap::Map9800 => 0x9800,
+            TileMap::Map9C00 => 0x9C00,
+        }
+    }
+}
+
+// Which tile data region map entries index, 0x8000 with unsigned tile numbers or
+// 0x8800 with signed ones
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TileData {
+    Data8000 = 0,
+    Data8800 = 1,
+}
+
+// Works out which of the 384 VRAM tiles a tile map entry refers to. With the
+// 0x8000 data region tile numbers are unsigned, with the 0x8800 region they are
+// signed and relative to 0x9000
+pub fn get_map_tile(mmu: &mmu::Mmu, map_address: usize, tile_x: usize, tile_y: usize, unsigned: bool) -> usize {
+    let tile_num = mmu.peek_memory(&(map_address + tile_y * 32 + tile_x));
+
+    if unsigned {
+        tile_num as usize
+    } else {
+        (256 + (tile_num as i8) as i32) as usize
+    }
+}
+
+// Renders a full 32x32 tile map as a 256x256 RGBA image
+pub fn draw_tilemap(mmu: &mmu::Mmu, map_address: usize, unsigned: bool, shades: &[[u8; 3]; 4]) -> Vec<u8> {
+    let mut image = vec![0; 256 * 256 * 4];
+    let palette = mmu.peek_memory(&utils::COLOR_PALLETTE_ADDR);
+
+    for tile_y in 0..32 {
+        for tile_x in 0..32 {
+            let tile = get_map_tile(mmu, map_address, tile_x, tile_y, unsigned);
+            let pixels = decode_tile(mmu, tile);
+            draw_tile(&mut image, 256, tile_x * 8, tile_y * 8, &pixels, palette, shades, false);
+        }
+    }
+
+    image
+}
+
+// Outlines the 160x144 area of a 256x256 tile map image that's on screen with the
+// given scroll, wrapping around the edges the same way the background does
+pub fn draw_viewport(image: &mut [u8], scroll_x: u8, scroll_y: u8, color: [u8; 3]) {
+    for y in 0..256usize {
+        for x in 0..256usize {
+            let screen_x = (x as u8).wrapping_sub(scroll_x) as usize;
+            let screen_y = (y as u8).wrapping_sub(scroll_y) as usize;
+
+            let inside = screen_x < 160 && screen_y < 144;
+            let edge = screen_x == 0 || screen_x == 159 || screen_y == 0 || screen_y == 143;
+            if inside && edge {
+                let index = (y * 256 + x) * 4;
+                image[index..index + 3].copy_from_slice(&color);
+                image[index + 3] = 255;
+            }
+        }
+    }
+}
+
+// There are 40 sprites in OAM from 0xFE00 - 0xFE9F, 4 bytes each
+pub const OAM_SPRITE_COUNT: usize = 40;
+
+// Sprite thumbnails are laid out 8 to a row, each 8x16 so tall sprites fit
+pub const OAM_THUMBNAILS_WIDTH: usize = 8 * 8;
+pub const OAM_THUMBNAILS_HEIGHT: usize = OAM_SPRITE_COUNT / 8 * 16;
+
+// One sprite's OAM entry for the sprite viewer. x and y are as stored, 8 and 16
+// more than where the sprite's top left corner is on screen
+// on_screen: Whether any of the sprite is inside the 160x144 LCD
+// rendered: Whether the PPU drew the sprite on any line of the last full frame
+#[derive(Serialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OamEntry {
+    pub index: u8,
+    pub x: u8,
+    pub y: u8,
+    pub tile: u8,
+    pub attributes: u8,
+    pub on_screen: bool,
+    pub rendered: bool,
+}
+
+// Reads a sprite's entry out of OAM. rendered is left for the caller, only the
+// PPU knows that
+pub fn get_oam_entry(mmu: &mmu::Mmu, sprite: usize, sprite_height: u8) -> OamEntry {
+    let address = utils::SPRITE_ATTRIBUTE_ADDR + sprite * 4;
+    let y = mmu.peek_memory(&address);
+    let x = mmu.peek_memory(&(address + 1));
+
+    OamEntry {
+        index: sprite as u8,
+        x,
+        y,
+        tile: mmu.peek_memory(&(address + 2)),
+        attributes: mmu.peek_memory(&(address + 3)),
+        on_screen: x > 0 && x < 168 && y as u16 + sprite_height as u16 > 16 && y < 160,
+        rendered: false,
+    }
+}
+
+// Renders every sprite as it would look on screen, flipped and colored through
+// its OBP register, into an OAM_THUMBNAILS_WIDTH x OAM_THUMBNAILS_HEIGHT RGBA
+// image. Color 0 is transparent, as is the bottom half of 8x8 sprites
+pub fn draw_oam_thumbnails(mmu: &mmu::Mmu, sprite_height: u8, shades: &[[[u8; 3]; 4]; 2]) -> Vec<u8> {
+    let mut image = vec![0; OAM_THUMBNAILS_WIDTH * OAM_THUMBNAILS_HEIGHT * 4];
+
+    for sprite in 0..OAM_SPRITE_COUNT {
+        let entry = get_oam_entry(mmu, sprite, sprite_height);
+
+        // 8x16 sprites are an even tile and the odd one after it
+        let mut pixels = [0; 128];
+        if sprite_height == 16 {
+            let tile = (entry.tile & 0xFE) as usize;
+            pixels[..64].copy_from_slice(&decode_tile(mmu, tile));
+            pixels[64..].copy_from_slice(&decode_tile(mmu, tile + 1));
+        } else {
+            pixels[..64].copy_from_slice(&decode_tile(mmu, entry.tile as usize));
+        }
+
+        // Bit 4 picks OBP1 over OBP0, bit 5 is the x flip and bit 6 the y flip
+        let obp1 = entry.attributes & 16 > 0;
+        let x_flip = entry.attributes & 32 > 0;
+        let y_flip = entry.attributes & 64 > 0;
+
+        let palette_address = if obp1 { utils::SPRITE_PALLETTE_1_ADDR } else { utils::SPRITE_PALLETTE_0_ADDR };
+        let palette = mmu.peek_memory(&palette_address);
+        let shades = &shades[obp1 as usize];
+
+        let cell_x = (sprite % 8) * 8;
+        let cell_y = (sprite / 8) * 16;
+
+        for row in 0..sprite_height as usize {
+            for column in 0..8 {
+                let source_row = if y_flip { sprite_height as usize - 1 - row } else { row };
+                let source_column = if x_flip { 7 - column } else { column };
+
+                let color_num = pixels[source_row * 8 + source_column];
+                if color_num == 0 {
+                    continue;
+                }
+
+                let index = ((cell_y + row) * OAM_THUMBNAILS_WIDTH + cell_x + column) * 4;
+                image[index..index + 3].copy_from_slice(&shades[get_shade(palette, color_num) as usize]);
+                image[index + 3] = 255;
+            }
+        }
+    }
+
+    image
+}
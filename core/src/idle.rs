@@ -0,0 +1,87 @@
+use super::cpu;
+
+// Idle loop detection
+//
+// A lot of games spend most of a frame doing nothing useful - sitting in HALT
+// or spinning in a tight loop polling LY/STAT waiting for the next line or vblank.
+// When this is turned on, we watch for short backwards jumps that keep landing on
+// the same loop without the CPU writing to memory or ending up with different
+// registers. Once the same loop has run enough times in a row we consider the CPU
+// idle and the emulator can skip straight to the next hardware event instead of
+// running the loop instruction by instruction. A loop that counts a register down
+// changes it every time around, so it's never taken for idle.
+
+// The largest loop body (in bytes) we still consider a polling loop
+const MAX_LOOP_LENGTH: u16 = 16;
+
+// How many identical iterations before we call the loop idle
+const IDLE_ITERATIONS: u8 = 8;
+
+pub struct IdleDetector {
+    enabled: bool,
+    loop_start: u16,
+    loop_end: u16,
+    iterations: u8,
+    writes_at_loop_start: u32,
+
+    // The registers as the last time around the loop jumped back
+    registers_at_loop_start: Option<cpu::Registers>,
+}
+
+impl IdleDetector {
+    pub fn new() -> IdleDetector {
+        IdleDetector {
+            enabled: false,
+            loop_start: 0,
+            loop_end: 0,
+            iterations: 0,
+            writes_at_loop_start: 0,
+            registers_at_loop_start: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.loop_start = 0;
+        self.loop_end = 0;
+        self.iterations = 0;
+        self.registers_at_loop_start = None;
+    }
+
+    // Called after every instruction with the PC before and after it ran, the
+    // number of writes the CPU has made so far and its registers. Returns true
+    // when the CPU looks idle
+    pub fn observe(&mut self, previous_pc: u16, pc: u16, writes: u32, registers: &cpu::Registers) -> bool {
+        // Only backwards jumps are interesting, everything else just runs
+        if pc >= previous_pc || previous_pc - pc > MAX_LOOP_LENGTH {
+            return false;
+        }
+
+        // Same loop as last time, nothing got written in between and the
+        // registers are back where they were - this iteration couldn't have
+        // changed anything the loop is waiting on
+        let same_loop = pc == self.loop_start && previous_pc == self.loop_end;
+        let unchanged = writes == self.writes_at_loop_start && self.registers_at_loop_start.as_ref() == Some(registers);
+        if same_loop && unchanged {
+            if self.iterations < IDLE_ITERATIONS {
+                self.iterations += 1;
+            }
+        } else {
+            self.loop_start = pc;
+            self.loop_end = previous_pc;
+            self.iterations = 0;
+            self.writes_at_loop_start = writes;
+            self.registers_at_loop_start = Some(*registers);
+        }
+
+        self.iterations >= IDLE_ITERATIONS
+    }
+}
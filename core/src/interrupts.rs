@@ -0,0 +1,90 @@
+// Interrupts
+//
+// There are 5 interrupts. Each has a bit in IF (0xFF0F), which the hardware sets to
+// request it, and a bit in IE (0xFFFF), which the game sets to enable it. When IME
+// is on, the requested and enabled interrupt with the lowest bit (the highest
+// priority) is serviced by calling its vector. IME itself is kept in the Cpu as
+// only instructions and the dispatch sequence touch it.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    VBlank = 0,
+    Lcd = 1,
+    Timer = 2,
+    Serial = 3,
+    Joypad = 4,
+}
+
+// Highest priority first
+const PRIORITY: [Interrupt; 5] = [
+    Interrupt::VBlank,
+    Interrupt::Lcd,
+    Interrupt::Timer,
+    Interrupt::Serial,
+    Interrupt::Joypad,
+];
+
+impl Interrupt {
+    pub fn bit(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn vector(&self) -> u16 {
+        // V-Blank: 0x40, LCD: 0x48, Timer: 0x50, Serial: 0x58, Joypad: 0x60
+        0x40 + (self.bit() as u16) * 8
+    }
+
+    fn mask(&self) -> u8 {
+        1 << self.bit()
+    }
+}
+
+pub struct InterruptController {
+    request: u8, // IF
+    enabled: u8, // IE
+}
+
+impl InterruptController {
+    pub fn new() -> InterruptController {
+        InterruptController {
+            request: 0,
+            enabled: 0,
+        }
+    }
+
+    pub fn request(&mut self, interrupt: Interrupt) {
+        self.request |= interrupt.mask();
+    }
+
+    pub fn acknowledge(&mut self, interrupt: Interrupt) {
+        // Clears the request once the interrupt is being serviced
+        self.request &= !interrupt.mask();
+    }
+
+    pub fn get_pending(&self) -> u8 {
+        // Interrupts that are both requested and enabled
+        self.request & self.enabled & 0x1F
+    }
+
+    pub fn get_highest_priority(&self) -> Option<Interrupt> {
+        let pending = self.get_pending();
+        PRIORITY.iter().copied().find(|interrupt| pending & interrupt.mask() > 0)
+    }
+
+    pub fn read_request(&self) -> u8 {
+        // Only the low 5 bits of IF exist, the rest read back as 1
+        self.request | 0xE0
+    }
+
+    pub fn write_request(&mut self, value: u8) {
+        self.request = value & 0x1F;
+    }
+
+    pub fn read_enabled(&self) -> u8 {
+        self.enabled
+    }
+
+    pub fn write_enabled(&mut self, value: u8) {
+        self.enabled = value;
+    }
+}
@@ -0,0 +1,56 @@
+// Emulator core
+//
+// Everything that makes up the Gameboy itself, with no ties to the browser so it
+// can be used from native frontends, fuzzers and tests as well as the wasm
+// bindings in the parent crate.
+
+#[macro_use]
+pub mod utils;
+
+mod blocks;
+mod bus;
+mod color;
+mod config;
+mod cpu;
+mod debugger;
+mod display;
+mod dma;
+mod dump;
+mod emulator;
+mod fifo;
+mod game;
+mod graphics;
+mod idle;
+mod interrupts;
+mod mmm01;
+mod mmu;
+mod oam_bug;
+mod ops;
+mod osd;
+mod pacing;
+mod ppu;
+mod ppu_stats;
+mod savestate;
+mod scheduler;
+mod sgb;
+#[cfg(feature = "sm83-tests")]
+mod sm83;
+mod tama5;
+mod testrom;
+mod tiles;
+mod timeline;
+mod timer;
+mod trace;
+
+pub use color::{ColorCorrection, PalettePreset};
+pub use cpu::{LockInfo, Model, Registers, TimerState};
+pub use debugger::{BankSwitchEntry, BreakInfo, BreakKind, StepInfo};
+pub use emulator::{Emulator, VBlankCallback};
+pub use game::Game;
+pub use graphics::{GraphicsPalette, TileData, TileMap, TilemapLayer};
+pub use mmu::{MapperType, MbcState};
+pub use ppu::Layer;
+#[cfg(feature = "sm83-tests")]
+pub use sm83::run_sm83_tests;
+pub use testrom::{run_test_rom, run_to_breakpoint, TestRomResult, TestRomStatus};
+pub use trace::TraceCallback;
@@ -0,0 +1,141 @@
+// MMM01 multicart mapper
+//
+// Used by collection cartridges (Momotarou Collection, Taito Variety Pack, etc.).
+// The cart powers up "unmapped" with the last 32KB of the ROM (the menu) in
+// 0000-7FFF. The menu then configures which part of the ROM the selected game
+// lives in and locks the configuration, after which the mapper behaves like an
+// MBC1 confined to that part of the ROM.
+//
+// Registers (bits marked * can only be written while unmapped):
+// 0000-1FFF: Bits 0-3 RAM enable (0xA), *Bit 6 map and lock the configuration
+// 2000-3FFF: Bits 0-4 ROM bank low, *Bits 5-6 ROM bank mid
+// 4000-5FFF: Bits 0-1 RAM bank low, *Bits 2-3 RAM bank high, *Bits 4-5 ROM bank high
+// 6000-7FFF: Bit 0 MBC1 mode, *Bits 2-5 ROM bank mask
+//
+// The ROM bank mask fixes bits 1-4 of the low ROM bank so the game can't bank
+// outside of its own area once mapped.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Mmm01 {
+    mapped: bool,
+    ram_enabled: bool,
+    rom_bank_low: u8,
+    rom_bank_mid: u8,
+    rom_bank_high: u8,
+    ram_bank_low: u8,
+    ram_bank_high: u8,
+    rom_bank_mask: u8,
+    mbc1_mode: bool,
+}
+
+impl Mmm01 {
+    pub fn new() -> Mmm01 {
+        Mmm01 {
+            mapped: false,
+            ram_enabled: false,
+            rom_bank_low: 0,
+            rom_bank_mid: 0,
+            rom_bank_high: 0,
+            ram_bank_low: 0,
+            ram_bank_high: 0,
+            rom_bank_mask: 0,
+            mbc1_mode: false,
+        }
+    }
+
+    pub fn is_ram_enabled(&self) -> bool {
+        self.ram_enabled
+    }
+
+    pub fn is_mbc1_mode(&self) -> bool {
+        self.mbc1_mode
+    }
+
+    pub fn write(&mut self, address: usize, data: u8) {
+        match address {
+            m if m < 0x2000 => {
+                self.ram_enabled = data & 0xF == 0xA;
+
+                // 64 = 0b01000000 - Bit 6 locks the configuration in
+                if !self.mapped && data & 64 > 0 {
+                    self.mapped = true;
+                }
+            }
+
+            m if m < 0x4000 => {
+                // Once mapped, bits fixed by the mask can't be changed by the game
+                let fixed = self.get_fixed_bits();
+                self.rom_bank_low = (self.rom_bank_low & fixed) | (data & 0x1F & !fixed);
+
+                if !self.mapped {
+                    self.rom_bank_mid = (data >> 5) & 0x3;
+                }
+            }
+
+            m if m < 0x6000 => {
+                self.ram_bank_low = data & 0x3;
+
+                if !self.mapped {
+                    self.ram_bank_high = (data >> 2) & 0x3;
+                    self.rom_bank_high = (data >> 4) & 0x3;
+                }
+            }
+
+            _ => {
+                self.mbc1_mode = data & 1 > 0;
+
+                if !self.mapped {
+                    self.rom_bank_mask = (data >> 2) & 0xF;
+                }
+            }
+        }
+    }
+
+    pub fn get_rom_bank(&self) -> usize {
+        // Bank mapped into 4000-7FFF. Like MBC1, the game selecting bank 0 of its
+        // own area gets bank 1 instead
+        let mut low = self.rom_bank_low;
+        if low & !self.get_fixed_bits() & 0x1F == 0 {
+            low |= 1;
+        }
+
+        self.get_outer_bank() | (low as usize)
+    }
+
+    pub fn get_ram_bank(&self) -> u8 {
+        (self.ram_bank_high << 2) | self.ram_bank_low
+    }
+
+    pub fn get_rom_address(&self, address: usize, bank_count: usize) -> usize {
+        let bank_count = bank_count.max(2);
+
+        if !self.mapped {
+            // Menu lives in the last 32KB of the ROM
+            return ((bank_count - 2) * 0x4000) + address;
+        }
+
+        if address < 0x4000 {
+            // Bank 0 of the selected game, the part of the low bank fixed by the mask
+            let bank = self.get_outer_bank() | ((self.rom_bank_low & self.get_fixed_bits()) as usize);
+            return (bank % bank_count) * 0x4000 + address;
+        }
+
+        (self.get_rom_bank() % bank_count) * 0x4000 + (address - 0x4000)
+    }
+
+    fn get_outer_bank(&self) -> usize {
+        ((self.rom_bank_high as usize) << 7) | ((self.rom_bank_mid as usize) << 5)
+    }
+
+    fn get_fixed_bits(&self) -> u8 {
+        // Mask bits 2-5 of the 6000 register fix bits 1-4 of the ROM bank, and only
+        // once the configuration has been locked in
+        if self.mapped {
+            (self.rom_bank_mask << 1) & 0x1E
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,1065 @@
+use super::utils;
+use super::game;
+use super::debugger;
+use super::dma;
+use super::savestate;
+use super::mmm01;
+use super::oam_bug;
+use super::sgb;
+use super::tama5;
+use super::tiles;
+use super::interrupts;
+use super::interrupts::Interrupt;
+use super::timer;
+
+use std::borrow::Cow;
+
+// MEMORY INFO
+//
+// 0000-3FFF 16KB ROM Bank 00 (in cartridge, fixed at bank 00)
+// 4000-7FFF 16KB ROM Bank 01..NN (in cartridge, switchable bank number)
+// 8000-9FFF 8KB Video RAM (VRAM) (switchable bank 0-1 in CGB Mode)
+// A000-BFFF 8KB External RAM (in cartridge, switchable bank, if any)
+// C000-CFFF 4KB Work RAM Bank 0 (WRAM)
+// D000-DFFF 4KB Work RAM Bank 1 (WRAM) (switchable bank 1-7 in CGB Mode)
+// E000-FDFF Same as C000-DDFF (ECHO) (typically not used)
+// FE00-FE9F Sprite Attribute Table (OAM)
+// FEA0-FEFF Not Usable
+// FF00-FF7F I/O Ports
+// FF80-FFFE High RAM (HRAM)
+// FFFF Interrupt Enable Register
+
+// The memory bank controller the cartridge uses, as reported to the debugger
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MapperType {
+    None = 0,
+    Mbc1 = 1,
+    Mbc2 = 2,
+    Mmm01 = 3,
+    WisdomTree = 4,
+    Tama5 = 5,
+}
+
+// Snapshot of the memory bank controller for debugging
+// mapper: Which MBC the cartridge uses
+// rom_bank: ROM bank currently mapped into 4000-7FFF
+// ram_bank: RAM bank currently mapped into A000-BFFF
+// ram_enabled: Whether external RAM is enabled for writing
+// rom_banking_mode: MBC1 banking mode - true for ROM banking, false for RAM banking
+// rtc: The mapper's RTC registers, empty when it doesn't have one (see
+//      get_rtc_registers)
+#[derive(Clone, Debug)]
+pub struct MbcState {
+    pub mapper: MapperType,
+    pub rom_bank: u8,
+    pub ram_bank: u8,
+    pub ram_enabled: bool,
+    pub rom_banking_mode: bool,
+    pub rtc: Vec<u8>,
+}
+
+pub struct Mmu {
+    memory: [u8; 0x10000],
+
+    // Joypad byte - we will use 8 bits for denoting key pressed - not the same
+	// as internal memory joypad state. Just for convenience sake and for setting
+	// internal memory
+    joypad: u8,
+
+    // Joypads for players 2 - 4 when the SGB multiplayer adapter is in use, same
+    // layout as joypad above
+    extra_joypads: [u8; 3],
+    sgb: sgb::Sgb,
+
+    // The type of rom banking (MBC1, MBC2, etc.) the cartridge uses
+	// Some games don't use any and the rom bank mode is found at memory
+	// location 0x147 after the game is loaded into memory (0x000 - 0x7FFF)
+    mapper: MapperType,
+    mmm01: mmm01::Mmm01,
+    tama5: tama5::Tama5,
+    rom_banking: bool,
+
+    // Different rom banks could be loaded into second area of memory (4000 - 7FFF)
+	// But memory region 0000 - 7FFF is fixed at rom bank 0. That stays loaded
+	// So keep a variable that says what rom bank is loaded into the second region
+    current_rom_bank: u8,
+
+    // Goes up whenever a different part of the cartridge is mapped into 0000 - 7FFF,
+    // so anything holding on to decoded ROM knows to throw it away (see blocks.rs)
+    rom_mapping_version: u32,
+
+    // Memory location 0x148 tells how many RAM banks exist
+	// A RAM bank is 0x2000 bytes in size and the maximum RAM banks that a game can
+	// have is 4. Keep an Array variable to represent 4 RAM banks (0x8000 in size)
+	// and a variable to tell us which RAM bank is being used currently (between 0 and 3)
+	// RAM banking isn't used if ROM bank mode is MBC2 so currentRamBank will stay 0
+    ram_banks: [u8; 0x8000],
+    current_ram_bank: u8,
+    enable_ram: bool,
+
+    // DIV, TIMA, TMA and TAC
+    timer: timer::Timer,
+
+    // IF and IE
+    interrupts: interrupts::InterruptController,
+
+    // Copies sprite data into OAM when FF46 is written
+    dma: dma::Dma,
+
+    // The tile data in VRAM decoded to colour numbers, kept up to date as it's
+    // written (see tiles.rs)
+    tile_cache: tiles::TileCache,
+
+    // Hardware events (bank switches, interrupts, mode changes) for the debugger.
+    // These are only recorded when something is listening for them
+    record_events: bool,
+    events: Vec<debugger::HardwareEvent>,
+
+    // Optional log of writes to the bank controller and the PC of the instruction
+    // that is currently executing (so log entries can say who did the write)
+    bank_log: Option<debugger::BankSwitchLog>,
+    current_pc: u16,
+
+    // Every byte sent out over the serial port while capture_serial is set.
+    // Nothing is ever connected to the other end, but test ROMs print their
+    // results this way. Off otherwise, so a game using the port doesn't grow this
+    // for the whole session
+    serial_output: Vec<u8>,
+    capture_serial: bool,
+
+    // Mapped over 0000 - 00FF from power on until something is written to FF50.
+    // Empty unless the frontend supplied one (see set_boot_rom)
+    boot_rom: Vec<u8>,
+    boot_rom_mapped: bool,
+
+    cartridge: game::Game
+}
+
+impl Mmu {
+    pub fn new(game: game::Game) -> Mmu {
+        // Init Memory to all 0 and then some spots equal to the following (from Docs)
+        let mut memory = [0; 0x10000];
+
+        memory[0xFF05] = 0x00;
+        memory[0xFF06] = 0x00;
+        memory[0xFF07] = 0x00;
+        memory[0xFF10] = 0x80;
+        memory[0xFF11] = 0xBF;
+        memory[0xFF12] = 0xF3;
+        memory[0xFF14] = 0xBF;
+        memory[0xFF16] = 0x3F;
+        memory[0xFF17] = 0x00;
+        memory[0xFF19] = 0xBF;
+        memory[0xFF1A] = 0x7F;
+        memory[0xFF1B] = 0xFF;
+        memory[0xFF1C] = 0x9F;
+        memory[0xFF1E] = 0xBF;
+        memory[0xFF20] = 0xFF;
+        memory[0xFF21] = 0x00;
+        memory[0xFF22] = 0x00;
+        memory[0xFF23] = 0xBF;
+        memory[0xFF24] = 0x77;
+        memory[0xFF25] = 0xF3;
+        memory[0xFF26] = 0xF1;
+        memory[0xFF40] = 0x91;
+        memory[0xFF42] = 0x00;
+        memory[0xFF43] = 0x00;
+        memory[0xFF45] = 0x00;
+        memory[0xFF47] = 0xFC;
+        memory[0xFF48] = 0xFF;
+        memory[0xFF49] = 0xFF;
+        memory[0xFF4A] = 0x00;
+        memory[0xFF4B] = 0x00;
+        memory[0xFFFF] = 0x00;
+
+        // Set the first bank into memory 0x000 - 0x7FFF
+        for (i, byte) in memory.iter_mut().enumerate().take(0x8000) {
+            *byte = game.read_catridge_data(i);
+        }
+
+        let mut mmu = Mmu {
+            memory,
+            joypad: 7, // All bits set to 1
+            extra_joypads: [0xFF; 3],
+            sgb: sgb::Sgb::new(),
+            mapper: MapperType::None,
+            mmm01: mmm01::Mmm01::new(),
+            tama5: tama5::Tama5::new(),
+            rom_banking: true,
+            current_rom_bank: 1,
+            rom_mapping_version: 0,
+            ram_banks: [0; 0x8000],
+            current_ram_bank: 0,
+            enable_ram: false,
+            timer: timer::Timer::new(),
+            interrupts: interrupts::InterruptController::new(),
+            dma: dma::Dma::new(),
+            tile_cache: tiles::TileCache::new(),
+            record_events: false,
+            events: Vec::new(),
+            bank_log: None,
+            current_pc: 0,
+            serial_output: Vec::new(),
+            capture_serial: false,
+            boot_rom: Vec::new(),
+            boot_rom_mapped: false,
+            cartridge: game
+        };
+
+        mmu.determine_rom_banking_type();
+        mmu
+    }
+
+    pub fn determine_rom_banking_type(&mut self) {
+        // Read from the cartridge directly as some mappers (i.e. MMM01) don't have
+        // the header of the actual game in 0000-7FFF at power on
+        let cartridge_type = self.cartridge.read_catridge_data(0x147);
+
+        self.mapper = match cartridge_type {
+            0x01..=0x03 => MapperType::Mbc1,
+            0x05..=0x06 => MapperType::Mbc2,
+            0xFD        => MapperType::Tama5,
+            _           => MapperType::None
+        };
+
+        // MMM01 carts have the header of the menu in the last bank, the header in
+        // bank 0 belongs to the first game in the collection
+        let bank_count = self.cartridge.rom_bank_count().max(2);
+        let menu_cartridge_type = self.cartridge.read_catridge_data((bank_count - 2) * 0x4000 + 0x147);
+        if (0x0B..=0x0D).contains(&cartridge_type) || (0x0B..=0x0D).contains(&menu_cartridge_type) {
+            self.mapper = MapperType::Mmm01;
+        }
+
+        // Wisdom Tree carts are unlicensed and claim to be ROM only, but anything
+        // ROM only bigger than 32KB must be banking somehow
+        if (cartridge_type == 0x00 || cartridge_type == 0xC0) && self.cartridge.size() > 0x8000 {
+            self.mapper = MapperType::WisdomTree;
+            self.current_rom_bank = 0;
+        }
+
+        if self.mapper == MapperType::None {
+            log!("no memory banking necessary");
+        }
+    }
+
+    pub fn read_memory(&self, address: &usize) -> u8 {
+        if self.boot_rom_mapped && *address < 0x100 {
+            return self.boot_rom[*address];
+        }
+
+        match *address {
+            // If reading the Joypad memory byte, resolve our joypad object to what the
+		    // memory should actually look like
+            0xFF00                              => self.get_joypad_state(),
+
+            utils::DIVIDER_REGISTER_ADDR        => self.timer.read_div(),
+            utils::TIMER_ADDR                   => self.timer.read_tima(),
+            utils::TIMER_MODULATOR_ADDR         => self.timer.read_tma(),
+            utils::TIMER_CONTROLLER_ADDR        => self.timer.read_tac(),
+            utils::INTERRUPT_REQUEST_ADDR       => self.interrupts.read_request(),
+            utils::INTERRUPT_ENABLED_ADDR       => self.interrupts.read_enabled(),
+
+            // Some mappers can switch what is in ROM bank 0 as well
+            m if m < 0x4000 && self.is_bank_zero_switchable() => self.do_read_cartridge_data(m),
+
+            // If reading from ROM bank, find actual data we want in cartridge memory
+            m if (0x4000..=0x7FFF).contains(&m) => self.do_read_cartridge_data(m),
+
+            // If reading from RAM bank
+            m if (0xA000..=0xBFFF).contains(&m) => self.do_read_ram_bank(m),
+
+            // Anything else, read normally
+            _                                   => self.memory[*address]
+        }
+    }
+
+    pub fn read_slice(&self, address: &usize, length: usize) -> Cow<'_, [u8]> {
+        // Reads a block of memory, same as calling read_memory for each address
+        // (wrapping around at the end of the address space). When the block is all
+        // in one region it is borrowed straight out of whatever stores it
+        match self.get_contiguous(*address, length) {
+            Some(bytes) => Cow::Borrowed(bytes),
+            None        => Cow::Owned(
+                (0..length)
+                    .map(|i| self.read_memory(&((address + i) & 0xFFFF)))
+                    .collect()
+            )
+        }
+    }
+
+    pub fn peek_memory(&self, address: &usize) -> u8 {
+        // Debugger view of memory. Unlike read_memory, this must never trigger
+        // hardware behaviour (joypad resolution, and later things like RTC latching
+        // or serial transfers) so it returns whatever backs the address
+        if self.boot_rom_mapped && *address < 0x100 {
+            return self.boot_rom[*address];
+        }
+
+        match *address {
+            // Switchable ROM bank still needs resolving to the cartridge, that has no side effects
+            m if m < 0x4000 && self.is_bank_zero_switchable() => self.do_read_cartridge_data(m),
+            m if (0x4000..=0x7FFF).contains(&m) => self.do_read_cartridge_data(m),
+
+            // RAM bank is read regardless of whether RAM is currently enabled
+            m if (0xA000..=0xBFFF).contains(&m) => self.do_read_ram_bank(m),
+
+            utils::DIVIDER_REGISTER_ADDR        => self.timer.read_div(),
+            utils::TIMER_ADDR                   => self.timer.read_tima(),
+            utils::TIMER_MODULATOR_ADDR         => self.timer.read_tma(),
+            utils::TIMER_CONTROLLER_ADDR        => self.timer.read_tac(),
+            utils::INTERRUPT_REQUEST_ADDR       => self.interrupts.read_request(),
+            utils::INTERRUPT_ENABLED_ADDR       => self.interrupts.read_enabled(),
+
+            // Anything else (including the joypad register) is the raw byte
+            _                                   => self.memory[*address]
+        }
+    }
+
+    pub fn write_memory(&mut self, address: &usize, data: u8) {
+        match *address {
+            // If address is in Game ROM Area, don't write, this is read-only
+			// Handle ROM banking though
+            m if m < 0x8000                    => self.do_handle_banking(address, data),
+            m if (0xA000..0xC000).contains(&m) => self.do_handle_ram_banks(address, data),
+
+            // Tile data, the decoded copy needs to follow along
+            m if (0x8000..0x9800).contains(&m) => self.do_write_tile_data(address, data),
+
+            // This is the divider register and if we try and write to this,
+			// it should reset to 0
+            utils::DIVIDER_REGISTER_ADDR       => self.timer.reset_divider(),
+
+            utils::TIMER_ADDR                  => self.timer.write_tima(data),
+            utils::TIMER_MODULATOR_ADDR        => self.timer.write_tma(data),
+            utils::TIMER_CONTROLLER_ADDR       => self.timer.write_tac(data),
+
+            // This is the register that holds the current scanline, it's read
+			// only. The PPU sets it with set_scanline_value
+            utils::CURRENT_SCANLINE_ADDR       => {}
+
+            // When requesting this address, a Direct Memory Access is launched
+			// which is when data is copied to Sprite RAM (FE00-FE9F). This can
+			// be accessed during LCD Status Mode 2
+            utils::DMA_ADDR                    => self.do_dma_transer(data),
+
+            // Joypad select lines, these are also how the SGB receives commands
+            0xFF00                             => self.do_joypad_write(data),
+
+            // Serial transfer control
+            0xFF02                             => self.do_serial_transfer(data),
+
+            utils::BOOT_ROM_DISABLE_ADDR       => self.do_boot_rom_disable(data),
+
+            utils::INTERRUPT_REQUEST_ADDR      => self.interrupts.write_request(data),
+            utils::INTERRUPT_ENABLED_ADDR      => self.interrupts.write_enabled(data),
+
+            // This is not usable memory. Restricted access. Don't write
+            m if (0xFEA0..0xFEFF).contains(&m) => log!("Attempted to write to restricted memory - {}", m),
+
+            // If you write to ECHO, you also have to write to RAM
+            m if (0xE000..0xFDFF).contains(&m) => self.do_echo_write(address, data),
+
+            // Anything else, write to memory
+            _                                  => self.do_write_data(address, data)
+        }
+    }
+
+    pub fn is_locked_by_ppu(&self, address: &usize) -> bool {
+        // With the LCD on the PPU has OAM to itself through modes 2 and 3, and
+        // VRAM through mode 3. The mode is whatever the PPU last put in STAT
+        if self.memory[utils::LCD_CONTROL_ADDR] & 128 == 0 {
+            return false;
+        }
+
+        let mode = self.memory[utils::LCD_STATUS_ADDR] & 3;
+        match *address {
+            0x8000..=0x9FFF => mode == 3,
+            0xFE00..=0xFE9F => mode == 2 || mode == 3,
+            _               => false,
+        }
+    }
+
+    pub fn corrupt_oam(&mut self, row: usize, access: oam_bug::OamBugAccess) {
+        // See oam_bug.rs, row is the one the PPU's OAM scan is on
+        oam_bug::do_corrupt(&mut self.memory[0xFE00..0xFEA0], row, access);
+    }
+
+    pub fn set_scanline_value(&mut self, value: u8) {
+        self.memory[utils::CURRENT_SCANLINE_ADDR] = value;
+    }
+
+    pub fn get_mapper_type(&self) -> MapperType {
+        self.mapper
+    }
+
+    pub fn save_state(&self) -> savestate::MmuState {
+        // The timer registers, IF and IE go in the memory dump where the game sees them
+        let mut memory = self.memory.to_vec();
+        memory[utils::DIVIDER_REGISTER_ADDR] = self.timer.read_div();
+        memory[utils::TIMER_ADDR] = self.timer.read_tima();
+        memory[utils::TIMER_MODULATOR_ADDR] = self.timer.read_tma();
+        memory[utils::TIMER_CONTROLLER_ADDR] = self.timer.read_tac();
+        memory[utils::INTERRUPT_REQUEST_ADDR] = self.interrupts.read_request();
+        memory[utils::INTERRUPT_ENABLED_ADDR] = self.interrupts.read_enabled();
+
+        savestate::MmuState {
+            memory,
+            ram_banks: self.ram_banks.to_vec(),
+            current_rom_bank: self.current_rom_bank,
+            current_ram_bank: self.current_ram_bank,
+            enable_ram: self.enable_ram,
+            rom_banking: self.rom_banking,
+            timer_counter: self.timer.get_counter() as usize,
+            timer_reload_delay: self.timer.get_reload_delay(),
+            boot_rom_mapped: self.boot_rom_mapped,
+            dma_source: self.dma.get_source() as u16,
+            dma_cycles_left: self.dma.get_cycles_left() as u16,
+            mmm01: if self.mapper == MapperType::Mmm01 { Some(self.mmm01.clone()) } else { None },
+            tama5: if self.mapper == MapperType::Tama5 { Some(self.tama5.clone()) } else { None },
+        }
+    }
+
+    pub fn load_state(&mut self, state: &savestate::MmuState) {
+        // Copy as much as fits, a state from a build with a different layout
+        // shouldn't be able to panic us
+        let memory_length = state.memory.len().min(self.memory.len());
+        self.memory[..memory_length].copy_from_slice(&state.memory[..memory_length]);
+        self.timer.set_counter(state.timer_counter as u16);
+        self.timer.write_tima(self.memory[utils::TIMER_ADDR]);
+        self.timer.set_reload_delay(state.timer_reload_delay);
+        self.timer.write_tma(self.memory[utils::TIMER_MODULATOR_ADDR]);
+        self.timer.write_tac(self.memory[utils::TIMER_CONTROLLER_ADDR]);
+        self.interrupts.write_request(self.memory[utils::INTERRUPT_REQUEST_ADDR]);
+        self.interrupts.write_enabled(self.memory[utils::INTERRUPT_ENABLED_ADDR]);
+
+        let ram_length = state.ram_banks.len().min(self.ram_banks.len());
+        self.ram_banks[..ram_length].copy_from_slice(&state.ram_banks[..ram_length]);
+
+        self.current_rom_bank = state.current_rom_bank;
+        self.current_ram_bank = state.current_ram_bank;
+        self.enable_ram = state.enable_ram;
+        self.rom_banking = state.rom_banking;
+        self.boot_rom_mapped = state.boot_rom_mapped && !self.boot_rom.is_empty();
+        self.dma.set_state(state.dma_source as usize, state.dma_cycles_left as usize);
+
+        // Only taken from states saved with the same mapper
+        if let (MapperType::Mmm01, Some(mmm01)) = (self.mapper, state.mmm01.as_ref()) {
+            self.mmm01 = mmm01.clone();
+        }
+        if let (MapperType::Tama5, Some(tama5)) = (self.mapper, state.tama5.as_ref()) {
+            self.tama5 = tama5.clone();
+        }
+
+        self.rom_mapping_version = self.rom_mapping_version.wrapping_add(1);
+        self.tile_cache.rebuild(&self.memory[0x8000..0xA000]);
+    }
+
+    pub fn get_external_ram(&self) -> Vec<u8> {
+        // All external RAM banks, or the mapper's own storage for mappers that
+        // don't use regular RAM banks
+        match self.mapper {
+            MapperType::Tama5 => self.tama5.get_ram().to_vec(),
+            _                 => self.ram_banks.to_vec()
+        }
+    }
+
+    pub fn get_rtc_registers(&self) -> Vec<u8> {
+        // Only the TAMA5 has an RTC for now
+        match self.mapper {
+            MapperType::Tama5 => self.tama5.get_rtc().to_vec(),
+            _                 => Vec::new()
+        }
+    }
+
+    pub fn tick_cartridge(&mut self, cycles: &usize) {
+        // Cartridge hardware that runs on its own clock, like an RTC
+        if self.mapper == MapperType::Tama5 {
+            self.tama5.tick(*cycles);
+        }
+    }
+
+    pub fn get_mbc_state(&self) -> MbcState {
+        MbcState {
+            mapper: self.get_mapper_type(),
+            rom_bank: self.current_rom_bank,
+            ram_bank: self.current_ram_bank,
+            ram_enabled: self.enable_ram,
+            rom_banking_mode: self.rom_banking,
+            rtc: self.get_rtc_registers(),
+        }
+    }
+
+    pub fn set_event_recording(&mut self, enabled: bool) {
+        self.record_events = enabled;
+        if !enabled {
+            self.events.clear();
+        }
+    }
+
+    pub fn record_event(&mut self, event: debugger::HardwareEvent) {
+        if self.record_events {
+            self.events.push(event);
+        }
+    }
+
+    pub fn take_events(&mut self) -> Vec<debugger::HardwareEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn set_joypad(&mut self, player: u8, state: u8) {
+        // Player 0 is the regular joypad, 1 - 3 are only seen by the game through
+        // the SGB multiplayer adapter
+        match player {
+            0 => {
+                // Pressing a button (its bit going from 1 to 0) requests the joypad
+                // interrupt
+                if self.joypad & !state > 0 {
+                    self.interrupts.request(Interrupt::Joypad);
+                }
+                self.joypad = state;
+            }
+            1..=3 => self.extra_joypads[(player - 1) as usize] = state,
+            _ => log!("Invalid player for joypad - {}", player)
+        }
+    }
+
+    pub fn get_joypad(&self, player: u8) -> u8 {
+        match player {
+            0 => self.joypad,
+            1..=3 => self.extra_joypads[(player - 1) as usize],
+            _ => 0xFF
+        }
+    }
+
+    pub fn set_sgb_enabled(&mut self, enabled: bool) {
+        self.sgb.set_enabled(enabled);
+    }
+
+    pub fn skip_boot_rom(&mut self) {
+        // IO registers the boot ROM leaves behind that aren't set up in new. The
+        // divider has been running the whole time, and the V-Blank interrupt
+        // requested at the end of the logo scroll is never cleared
+        self.timer.set_counter(0xABCC);
+        self.interrupts.write_request(0x01);
+        self.memory[0xFF02] = 0x7E;
+    }
+
+    pub fn set_boot_rom(&mut self, boot_rom: &[u8]) {
+        // Maps a 256 byte boot ROM over the start of the cartridge, undoing what
+        // skip_boot_rom set up as the boot ROM is about to do that itself
+        self.timer.set_counter(0);
+        self.interrupts.write_request(0);
+        self.boot_rom = boot_rom.to_vec();
+        self.boot_rom_mapped = true;
+        self.rom_mapping_version = self.rom_mapping_version.wrapping_add(1);
+    }
+
+    pub fn get_rom_mapping_version(&self) -> u32 {
+        self.rom_mapping_version
+    }
+
+    pub fn tick_timer(&mut self, cycles: &usize) {
+        if self.timer.tick(*cycles) {
+            self.interrupts.request(Interrupt::Timer);
+        }
+    }
+
+    pub fn tick_dma(&mut self, cycles: &usize) {
+        // Copies whatever bytes of a running DMA are due
+        let due = self.dma.tick(*cycles);
+        if due.is_empty() {
+            return;
+        }
+
+        let mut sprite_data = [0; 0xA0];
+        let length = due.len();
+        sprite_data[..length].copy_from_slice(&self.read_slice(&(self.dma.get_source() + due.start), length));
+        self.memory[0xFE00 + due.start..0xFE00 + due.end].copy_from_slice(&sprite_data[..length]);
+    }
+
+    pub fn get_tile_cache(&self) -> &tiles::TileCache {
+        &self.tile_cache
+    }
+
+    pub fn get_dma(&self) -> &dma::Dma {
+        &self.dma
+    }
+
+    pub fn get_timer(&self) -> &timer::Timer {
+        &self.timer
+    }
+
+    pub fn get_timer_mut(&mut self) -> &mut timer::Timer {
+        &mut self.timer
+    }
+
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupts.request(interrupt);
+    }
+
+    pub fn get_interrupts(&self) -> &interrupts::InterruptController {
+        &self.interrupts
+    }
+
+    pub fn get_interrupts_mut(&mut self) -> &mut interrupts::InterruptController {
+        &mut self.interrupts
+    }
+
+    pub fn set_serial_capture(&mut self, enabled: bool) {
+        self.capture_serial = enabled;
+        if !enabled {
+            self.serial_output.clear();
+        }
+    }
+
+    pub fn get_serial_output(&self) -> &[u8] {
+        &self.serial_output
+    }
+
+    pub fn set_current_pc(&mut self, pc: u16) {
+        self.current_pc = pc;
+    }
+
+    pub fn enable_bank_log(&mut self, capacity: usize) {
+        self.bank_log = Some(debugger::BankSwitchLog::new(capacity));
+    }
+
+    pub fn disable_bank_log(&mut self) {
+        self.bank_log = None;
+    }
+
+    pub fn clear_bank_log(&mut self) {
+        if let Some(log) = self.bank_log.as_mut() {
+            log.clear();
+        }
+    }
+
+    pub fn get_bank_log(&self) -> Vec<debugger::BankSwitchEntry> {
+        match &self.bank_log {
+            Some(log) => log.entries(),
+            None      => Vec::new()
+        }
+    }
+
+    fn is_bank_zero_switchable(&self) -> bool {
+        self.mapper == MapperType::Mmm01 || self.mapper == MapperType::WisdomTree
+    }
+
+    fn do_read_cartridge_data(&self, address: usize) -> u8 {
+        self.cartridge.read_catridge_data(self.get_cartridge_address(address))
+    }
+
+    fn get_cartridge_address(&self, address: usize) -> usize {
+        match self.mapper {
+            MapperType::Mmm01      => self.mmm01.get_rom_address(address, self.cartridge.rom_bank_count()),
+
+            // Wisdom Tree switches all of 0000-7FFF in 32KB banks
+            MapperType::WisdomTree => address + ((self.current_rom_bank as usize) * 0x8000),
+
+            _                      => (address - 0x4000) + ((self.current_rom_bank as usize) * 0x4000)
+        }
+    }
+
+    fn get_rom_mapping(&self) -> (usize, usize) {
+        // Where 0000 and 4000 currently point to in the cartridge
+        let bank_zero = if self.is_bank_zero_switchable() { self.get_cartridge_address(0) } else { 0 };
+        (bank_zero, self.get_cartridge_address(0x4000))
+    }
+
+    fn get_contiguous(&self, address: usize, length: usize) -> Option<&[u8]> {
+        // Finds the storage behind a range of addresses, as long as the whole range
+        // is in one region that is stored in one piece and reading it has no side
+        // effects. Anything else (ranges crossing regions, the joypad register,
+        // mappers that scatter their banks) is left to read_memory
+        if length == 0 {
+            return Some(&[]);
+        }
+
+        let end = address + length - 1;
+        if end > 0xFFFF {
+            return None;
+        }
+
+        match address {
+            // Fixed ROM bank 0, unless the boot ROM is over the start of it
+            m if m < 0x4000 => {
+                let boot_rom = self.boot_rom_mapped && m < 0x100;
+                if end < 0x4000 && !self.is_bank_zero_switchable() && !boot_rom {
+                    Some(&self.memory[m..=end])
+                } else {
+                    None
+                }
+            }
+
+            // Switchable ROM bank, MMM01 banks aren't necessarily in one piece
+            m if m <= 0x7FFF => {
+                if end <= 0x7FFF && self.mapper != MapperType::Mmm01 {
+                    self.cartridge.get_slice(self.get_cartridge_address(m), length)
+                } else {
+                    None
+                }
+            }
+
+            // VRAM
+            m if m < 0xA000 => {
+                if end < 0xA000 { Some(&self.memory[m..=end]) } else { None }
+            }
+
+            // External RAM bank, TAMA5 only has registers here
+            m if m <= 0xBFFF => {
+                if end <= 0xBFFF && self.mapper != MapperType::Tama5 {
+                    let start = (m - 0xA000) + ((self.current_ram_bank as usize) * 0x2000);
+                    Some(&self.ram_banks[start..start + length])
+                } else {
+                    None
+                }
+            }
+
+            // Everything else is backed by memory, apart from the joypad register and
+            // the timer and interrupt registers
+            m => {
+                let registers = [
+                    0xFF00,
+                    utils::DIVIDER_REGISTER_ADDR,
+                    utils::TIMER_ADDR,
+                    utils::TIMER_MODULATOR_ADDR,
+                    utils::TIMER_CONTROLLER_ADDR,
+                    utils::INTERRUPT_REQUEST_ADDR,
+                    utils::INTERRUPT_ENABLED_ADDR,
+                ];
+                if registers.iter().any(|register| (m..=end).contains(register)) {
+                    None
+                } else {
+                    Some(&self.memory[m..=end])
+                }
+            }
+        }
+    }
+
+    fn do_read_ram_bank(&self, address: usize) -> u8 {
+        // TAMA5 has no RAM mapped here, just its registers
+        if self.mapper == MapperType::Tama5 {
+            return self.tama5.read(address);
+        }
+
+        let resolved_address = address - 0xA000;
+        self.ram_banks[resolved_address + ((self.current_ram_bank as usize) * 0x2000)]
+    }
+
+    fn do_write_data(&mut self, address: &usize, data: u8) {
+        self.memory[*address] = data;
+    }
+
+    fn do_write_tile_data(&mut self, address: &usize, data: u8) {
+        self.memory[*address] = data;
+
+        let row = *address & !1;
+        self.tile_cache.update_row(address, self.memory[row], self.memory[row + 1]);
+    }
+
+    fn do_handle_banking(&mut self, address: &usize, data: u8) {
+        let previous_rom_bank = self.current_rom_bank;
+        let previous_ram_bank = self.current_ram_bank;
+        let previous_rom_mapping = self.get_rom_mapping();
+
+        match *address {
+            // MMM01 handles all of its own registers
+            _ if self.mapper == MapperType::Mmm01 => self.do_mmm01_write(address, data),
+
+            // TAMA5 is controlled entirely through A000-A001
+            _ if self.mapper == MapperType::Tama5 => log!("Ignoring TAMA5 write to ROM - {}", address),
+
+            // Wisdom Tree ignores the data, the 32KB bank is the low byte of the address
+            _ if self.mapper == MapperType::WisdomTree => self.current_rom_bank = (*address & 0xFF) as u8,
+
+            // If the address is between 0x0000 and 0x2000, and ROM Banking is enabled
+			// then we attempt RAM enabling
+            m if m < 0x2000                    => self.do_enable_ram_banking(address, data),
+
+            // If the address is between 0x2000 and 0x4000, and ROM banking is enabled
+			// then we perform a ROM bank change
+            m if (0x2000..0x4000).contains(&m) => self.do_rom_lo_bank_change(data),
+
+            // If the address is between 0x4000 and 0x6000 then we perform either
+			// a RAM bank change or ROM bank change depending on what RAM/ROM mode
+			// is selected
+            m if (0x4000..0x6000).contains(&m) => self.do_rom_or_ram_bank_change(data),
+
+            // In mbc1, rom banking is flipped depending on data to signify
+			// a RAM banking change instead. If we are writing to an address
+			// between 0x6000 and 0x8000 that is how we know if we should change
+			// this flag or not
+            m if (0x6000..0x8000).contains(&m) => self.do_change_rom_ram_mode(data),
+
+            // Match for edge case - do nothing
+            _                                  => log!("Invalid address for rom banking - {}", address)
+        }
+
+        // Let the debugger know if the write actually switched banks
+        if self.current_rom_bank != previous_rom_bank {
+            self.record_event(debugger::HardwareEvent::RomBankSwitched(self.current_rom_bank));
+        }
+
+        if self.current_ram_bank != previous_ram_bank {
+            self.record_event(debugger::HardwareEvent::RamBankSwitched(self.current_ram_bank));
+        }
+
+        if self.get_rom_mapping() != previous_rom_mapping {
+            self.rom_mapping_version = self.rom_mapping_version.wrapping_add(1);
+        }
+
+        self.log_bank_write(address, data);
+    }
+
+    fn log_bank_write(&mut self, address: &usize, data: u8) {
+        // Every banking write is logged, even ones that don't change the bank, since
+        // those are usually the interesting ones (i.e. MBC1 writing 0x20/0x40/0x60)
+        if let Some(log) = self.bank_log.as_mut() {
+            log.push(debugger::BankSwitchEntry {
+                address: *address as u16,
+                value: data,
+                rom_bank: self.current_rom_bank,
+                ram_bank: self.current_ram_bank,
+                pc: self.current_pc,
+            });
+        }
+    }
+
+    fn do_mmm01_write(&mut self, address: &usize, data: u8) {
+        self.mmm01.write(*address, data);
+
+        // Keep our own view of the banks in sync so RAM access, events and the
+        // debugger work the same as for the other mappers
+        self.current_rom_bank = self.mmm01.get_rom_bank() as u8;
+        self.current_ram_bank = self.mmm01.get_ram_bank();
+        self.enable_ram = self.mmm01.is_ram_enabled();
+        self.rom_banking = !self.mmm01.is_mbc1_mode();
+    }
+
+    fn do_tama5_write(&mut self, address: &usize, data: u8) {
+        let previous_rom_bank = self.current_rom_bank;
+        let previous_rom_mapping = self.get_rom_mapping();
+
+        self.tama5.write(*address, data);
+        self.current_rom_bank = self.tama5.get_rom_bank();
+
+        if self.current_rom_bank != previous_rom_bank {
+            self.record_event(debugger::HardwareEvent::RomBankSwitched(self.current_rom_bank));
+        }
+
+        if self.get_rom_mapping() != previous_rom_mapping {
+            self.rom_mapping_version = self.rom_mapping_version.wrapping_add(1);
+        }
+
+        // The bank is one of the registers behind A000-A001, so every write to
+        // them counts as a banking write
+        self.log_bank_write(address, data);
+    }
+
+    fn do_handle_ram_banks(&mut self, address: &usize, data: u8) {
+        if self.mapper == MapperType::Tama5 {
+            self.do_tama5_write(address, data);
+        } else if self.enable_ram {
+            let resolved_address = address - 0xA000;
+            self.ram_banks[resolved_address + ((self.current_ram_bank as usize) * 0x2000)] = data;
+        }
+    }
+
+    fn do_dma_transer(&mut self, data: u8) {
+        // DMA writes data to the Sprite Attribute Table (OAM), addresses FE00-FE9F
+		// The source address of data to be written represented by the data passed in here
+		// However, this value is actually the source address divided by 100. The bytes
+		// are copied over the next 160 machine cycles (see dma.rs and tick_dma)
+        self.memory[utils::DMA_ADDR] = data;
+        self.dma.start(data);
+    }
+
+    fn do_echo_write(&mut self, address: &usize, data: u8) {
+        let echo_address = *address - 0x2000;
+        self.do_write_data(&echo_address, data);
+        self.do_write_data(address, data);
+    }
+
+    fn do_enable_ram_banking(&mut self, address: &usize, data: u8) {
+        // mbc2 says that bit 4 of the address must be 0 for RAM Banking to be enabled
+        if self.mapper == MapperType::Mbc2 {
+            // 0x100 == 0b1_0000_0000
+            if address & 0x100 != 0 {
+                // Bit-Wise AND showed us bit 8 was 1 and not 0 so return
+                log!("Bit 8 of address {} was 1 - do not enable ram banking", address);
+                return;
+			}
+
+			// If lower nibble of data being written is 0xA then we enable RAM Banking
+			// and if the lower nibble is 0 then it is disabled
+			// var lowerNibble = data & 0xF;
+            let lower_nibble = data & 0xF;
+            if lower_nibble == 0xA {
+                self.enable_ram = true;
+            } else if lower_nibble == 0 {
+                self.enable_ram = false;
+            }
+        }
+    }
+
+    fn do_rom_lo_bank_change(&mut self, data: u8) {
+        // if mbc1, bits 0-4 are changed but not 5 and 6
+		// if mbc2, bits 0-3 are changed and bits 5 and 6 are never set
+        if self.mapper == MapperType::Mbc2 {
+            self.current_rom_bank = data & 0xF; // Lower nibble (bits 0-3)
+            if self.current_rom_bank == 0 {
+                // This cannot be 0 as rom bank 0 is always in Memory 0000-3FFF
+                self.current_rom_bank += 1;
+            }
+
+        } else if self.mapper == MapperType::Mbc1 {
+            let lower_five_bits = data & 31; // 31 = 0b11111
+            self.current_rom_bank &= 224; // 224 = 0b11100000 Flip off lower 5 bits for now
+            self.current_rom_bank |= lower_five_bits; // Bit wise OR will give us new value for lower 5
+            if self.current_rom_bank == 0 {
+                // This cannot be 0 as rom bank 0 is always in Memory 0000-3FFF
+                self.current_rom_bank += 1;
+            }
+        }
+    }
+
+    fn do_rom_hi_bank_change(&mut self, data: u8) {
+        // Only used for mbc1, mbc2 doesn't concern itself with the upper bits
+		// of the current ROM bank
+
+        self.current_rom_bank &= 31; // 31 = 0b11111 - Flip off the upper 3 bits for now
+        let new_data = data & 224; // 224 = 0b11100000 - Flip off the lower 5 bits of data
+        self.current_rom_bank |= new_data; // Bit wise OR here should give us the bits we care about
+        if self.current_rom_bank == 0 {
+            // This cannot be 0 as rom bank 0 is always in Memory 0000-3FFF
+            self.current_rom_bank += 1;
+        }
+    }
+
+    fn do_ram_bank_change(&mut self, data: u8) {
+        // Only used for mbc1 as mbc2 holds External RAM on the cartridge not in memory
+		// Set RAM Bank to the lower 2 bits of the data
+		self.current_ram_bank = data & 0x2;
+    }
+
+    fn do_change_rom_ram_mode(&mut self, data: u8) {
+        if self.mapper == MapperType::Mbc1 {
+            // If least significant bit of data being written is 0 then romBanking is set to true
+            // otherwise it is set to false, signifying RAM banking
+            // Current RAM bank should be set to 0 if romBanking is true
+            let least_significant_bit = data & 0x1;
+            if least_significant_bit == 0 {
+                self.rom_banking = true;
+                self.current_ram_bank = 0;
+            } else if least_significant_bit == 1 {
+                self.rom_banking = false;
+            }
+        }
+    }
+
+    fn do_rom_or_ram_bank_change(&mut self, data: u8) {
+        if self.mapper == MapperType::Mbc1 {
+            // no RAM banking if mbc2
+            if self.rom_banking {
+                self.do_rom_hi_bank_change(data);
+            } else {
+                self.do_ram_bank_change(data);
+            }
+        }
+    }
+
+    fn do_joypad_write(&mut self, data: u8) {
+        self.sgb.write_joypad(data);
+        self.do_write_data(&0xFF00, data);
+    }
+
+    fn do_serial_transfer(&mut self, data: u8) {
+        self.do_write_data(&0xFF02, data);
+
+        // A transfer starts when bit 7 (start) and bit 0 (internal clock) are set
+        // 129 = 0b10000001
+        if data & 0x81 != 0x81 {
+            return;
+        }
+
+        // With no link cable attached the transfer finishes straight away. The
+        // byte in SB goes out, 0xFF (nothing connected) comes back in, the start
+        // bit is cleared and the serial interrupt (bit 3) is requested
+        if self.capture_serial {
+            self.serial_output.push(self.memory[0xFF01]);
+        }
+        self.memory[0xFF01] = 0xFF;
+        self.memory[0xFF02] = data & 0x7F;
+        self.interrupts.request(Interrupt::Serial);
+    }
+
+    fn do_boot_rom_disable(&mut self, data: u8) {
+        // The boot ROM's last act. Once unmapped there's no way to map it back
+        if data != 0 && self.boot_rom_mapped {
+            self.boot_rom_mapped = false;
+            self.rom_mapping_version = self.rom_mapping_version.wrapping_add(1);
+        }
+    }
+
+    fn get_player_joypad(&self) -> u8 {
+        // Whichever controller the SGB has currently selected
+        match self.sgb.get_current_player() {
+            0 => self.joypad,
+            player => self.extra_joypads[(player - 1) as usize]
+        }
+    }
+
+    fn get_joypad_state(&self) -> u8 {
+        // Our Joypad object represents this
+		// Right = 0
+		// Left = 1
+		// Up = 2
+		// Down = 3
+		// A = 4
+		// B = 5
+		// SELECT = 6
+		// START = 7
+
+		// Actual byte is this:
+		// Bit 7 - Not used
+		// Bit 6 - Not used
+		// Bit 5 - P15 Select Button Keys (0=Select)
+		// Bit 4 - P14 Select Direction Keys (0=Select)
+		// Bit 3 - P13 Input Down or Start (0=Pressed) (Read Only)
+		// Bit 2 - P12 Input Up or Select (0=Pressed) (Read Only)
+		// Bit 1 - P11 Input Left or Button B (0=Pressed) (Read Only)
+		// Bit 0 - P10 Input Right or Button A (0=Pressed) (Read Only)
+
+        let mut result = self.memory[0xFF00];
+
+        // With the SGB multiplayer adapter, deselecting both key groups reads back
+        // the ID of the current controller instead
+        // 48 = 0b00110000
+        if self.sgb.is_multiplayer() && result & 48 == 48 {
+            return (result & 0xF0) | self.sgb.get_controller_id();
+        }
+
+        let joypad = self.get_player_joypad();
+
+        // Flip the bits
+        result ^= 0xFF;
+
+        // If we are interested in the standard buttons
+        // 32 == 0b00100000, 16 = 0b00010000
+        if result & 32 > 0 {
+            // Move the top nibble of the byte that has the standard buttons into
+			// a lower nibble
+			let mut top_nibble = joypad >> 4;
+			top_nibble |= 0xF0;
+			result &= top_nibble;
+
+        } else if result & 16 > 0 {
+            // Directional buttons
+            let mut bottom_nibble = joypad & 0xF;
+            bottom_nibble |= 0xF0;
+            result &= bottom_nibble;
+        }
+
+        result
+    }
+}
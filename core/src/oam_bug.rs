@@ -0,0 +1,85 @@
+// DMG OAM corruption bug
+//
+// While the PPU is scanning OAM (mode 2) it reads a row of 8 bytes (2 sprites)
+// every machine cycle. If the CPU puts an address from 0xFE00 - 0xFEFF on the bus
+// at the same time, the row the PPU is on gets mixed with the one before it. It's
+// not only reads and writes that do it - INC rr and DEC rr put the register on
+// the address bus too, so just stepping a pointer through OAM corrupts it.
+//
+// Each row is treated as 4 little endian words. The first row is never corrupted.
+// The patterns are the ones worked out for the DMG in Pan Docs' "OAM Corruption
+// Bug" page.
+
+pub const OAM_ROW_COUNT: usize = 20;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OamBugAccess {
+    Read,
+    Write,
+
+    // A read that increments or decrements the same register in the same cycle,
+    // LD A, (HL+) / LD A, (HL-)
+    ReadIncrease,
+}
+
+pub fn do_corrupt(oam: &mut [u8], row: usize, access: OamBugAccess) {
+    // oam is the 160 bytes at 0xFE00, row is the one the PPU is reading
+    if row == 0 || row >= OAM_ROW_COUNT {
+        return;
+    }
+
+    match access {
+        OamBugAccess::Write        => do_corrupt_write(oam, row),
+        OamBugAccess::Read         => do_corrupt_read(oam, row),
+        OamBugAccess::ReadIncrease => {
+            // The rows either side get mixed up first, then it goes wrong the
+            // same as a read. Not for the first few rows or the last one
+            if (4..OAM_ROW_COUNT - 1).contains(&row) {
+                let a = get_word(oam, row - 2, 0);
+                let b = get_word(oam, row - 1, 0);
+                let c = get_word(oam, row, 0);
+                let d = get_word(oam, row - 1, 2);
+                set_word(oam, row - 1, 0, (b & (a | c | d)) | (a & c & d));
+
+                copy_row(oam, row - 1, row);
+                copy_row(oam, row - 1, row - 2);
+            }
+            do_corrupt_read(oam, row);
+        }
+    }
+}
+
+fn do_corrupt_write(oam: &mut [u8], row: usize) {
+    let a = get_word(oam, row, 0);
+    let b = get_word(oam, row - 1, 0);
+    let c = get_word(oam, row - 1, 2);
+    set_word(oam, row, 0, ((a ^ c) & (b ^ c)) ^ c);
+    copy_row_tail(oam, row - 1, row);
+}
+
+fn do_corrupt_read(oam: &mut [u8], row: usize) {
+    let a = get_word(oam, row, 0);
+    let b = get_word(oam, row - 1, 0);
+    let c = get_word(oam, row - 1, 2);
+    set_word(oam, row, 0, b | (a & c));
+    copy_row_tail(oam, row - 1, row);
+}
+
+fn get_word(oam: &[u8], row: usize, offset: usize) -> u16 {
+    let index = row * 8 + offset * 2;
+    u16::from_le_bytes([oam[index], oam[index + 1]])
+}
+
+fn set_word(oam: &mut [u8], row: usize, offset: usize, value: u16) {
+    let index = row * 8 + offset * 2;
+    oam[index..index + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn copy_row(oam: &mut [u8], from: usize, to: usize) {
+    oam.copy_within(from * 8..from * 8 + 8, to * 8);
+}
+
+fn copy_row_tail(oam: &mut [u8], from: usize, to: usize) {
+    // Everything but the first word
+    oam.copy_within(from * 8 + 2..from * 8 + 8, to * 8 + 2);
+}
@@ -0,0 +1,1440 @@
+use super::utils;
+use super::cpu;
+use super::cpu::PairName;
+
+// 8 bit operands. Most opcodes pick their operand with 3 bits, in this order
+// (B = 0, C = 1, ... (HL) = 6, A = 7)
+#[derive(Copy, Clone, PartialEq)]
+enum Operand {
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    HlIndirect, // The byte in memory that HL points to
+    A,
+}
+
+// Conditions for jumps, calls and returns
+#[derive(Copy, Clone)]
+enum Condition {
+    NotZero,
+    Zero,
+    NotCarry,
+    Carry,
+}
+
+// What running a single instruction did
+#[derive(Copy, Clone, Debug)]
+pub struct OpResult {
+    // T-cycles taken
+    pub cycles: usize,
+
+    // How far Cpu::execute_op should move the PC on afterwards. This is the
+    // length of the instruction, or 0 when it already set the PC (jumps, calls
+    // and returns that were taken)
+    pub pc_delta: u16,
+}
+
+impl OpResult {
+    fn next(cycles: usize, length: u16) -> OpResult {
+        // Carry on with the instruction after this one
+        OpResult { cycles, pc_delta: length }
+    }
+
+    fn jump(cycles: usize) -> OpResult {
+        // The PC has been set to the target already
+        OpResult { cycles, pc_delta: 0 }
+    }
+
+    fn not_taken(cycles: usize, length: u16) -> OpResult {
+        // A conditional jump, call or return whose condition failed. These are
+        // quicker than when taken as the PC (and for calls, the stack) is left alone
+        OpResult::next(cycles, length)
+    }
+}
+
+// An entry in the opcode tables. The mnemonic, length (in bytes, including any
+// prefix) and cycles are only informational, what actually happens is up to
+// execute. Conditional instructions list their cycles for when the condition fails.
+// The block cache uses the mnemonic and length to decode runs of code, cycles is
+// only there for debugging tools to describe code
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub struct OpHandler {
+    pub mnemonic: &'static str,
+    pub length: u16,
+    pub cycles: usize,
+
+    // Whether the PC might not go on to the next instruction afterwards, so a block
+    // of decoded ops has to stop here (see blocks.rs)
+    pub ends_block: bool,
+
+    // Gets the opcode as well, so a handler can pick its operands out of the bits
+    execute: fn(&mut cpu::Cpu, u8) -> OpResult,
+}
+
+const fn op(mnemonic: &'static str, length: u16, cycles: usize, execute: fn(&mut cpu::Cpu, u8) -> OpResult) -> OpHandler {
+    OpHandler { mnemonic, length, cycles, ends_block: false, execute }
+}
+
+// Jumps, calls, returns, and anything that stops the CPU
+const fn end(mnemonic: &'static str, length: u16, cycles: usize, execute: fn(&mut cpu::Cpu, u8) -> OpResult) -> OpHandler {
+    OpHandler { mnemonic, length, cycles, ends_block: true, execute }
+}
+
+// Opcodes that don't exist, fetching one locks up the CPU
+const ILLEGAL: OpHandler = end("???", 1, 4, cpu_illegal);
+
+impl OpHandler {
+    // The PC points at the opcode while it executes and operands are read relative
+    // to it, so nothing here moves the PC unless it's a jump.
+    pub fn run(&self, cpu: &mut cpu::Cpu, operation: u8) -> OpResult {
+        (self.execute)(cpu, operation)
+    }
+}
+
+pub fn get_handler(operation: u8) -> &'static OpHandler {
+    &OPCODES[operation as usize]
+}
+
+pub fn get_cb_handler(operation: u8) -> &'static OpHandler {
+    // operation is the byte after the 0xCB prefix
+    &CB_OPCODES[operation as usize]
+}
+
+// Base page, indexed by opcode (0x76 would be LD (HL), (HL) but is HALT)
+pub static OPCODES: [OpHandler; 256] = [
+    // 0x00 - 0x0F
+    op("NOP",             1,  4, |_, _| OpResult::next(4, 1)),                                   // 0x00
+    op("LD BC, d16",      3, 12, |cpu, _| cpu_16_bit_load(cpu, &PairName::BC)),                  // 0x01
+    op("LD (BC), A",      1,  8, |cpu, _| cpu_store_a_indirect(cpu, &PairName::BC)),             // 0x02
+    op("INC BC",          1,  8, |cpu, _| cpu_16_bit_inc(cpu, &PairName::BC)),                   // 0x03
+    op("INC B",           1,  4, |cpu, _| cpu_8_bit_inc(cpu, Operand::B)),                       // 0x04
+    op("DEC B",           1,  4, |cpu, _| cpu_8_bit_dec(cpu, Operand::B)),                       // 0x05
+    op("LD B, d8",        2,  8, |cpu, _| cpu_8_bit_load_immediate(cpu, Operand::B)),            // 0x06
+    op("RLCA",            1,  4, |cpu, _| cpu_shift_a(cpu, Shift::RotateLeftCircular)),          // 0x07
+    op("LD (a16), SP",    3, 20, |cpu, _| cpu_store_sp(cpu)),                                    // 0x08
+    op("ADD HL, BC",      1,  8, |cpu, _| cpu_16_bit_add(cpu, &PairName::BC)),                   // 0x09
+    op("LD A, (BC)",      1,  8, |cpu, _| cpu_load_a_indirect(cpu, &PairName::BC)),              // 0x0A
+    op("DEC BC",          1,  8, |cpu, _| cpu_16_bit_dec(cpu, &PairName::BC)),                   // 0x0B
+    op("INC C",           1,  4, |cpu, _| cpu_8_bit_inc(cpu, Operand::C)),                       // 0x0C
+    op("DEC C",           1,  4, |cpu, _| cpu_8_bit_dec(cpu, Operand::C)),                       // 0x0D
+    op("LD C, d8",        2,  8, |cpu, _| cpu_8_bit_load_immediate(cpu, Operand::C)),            // 0x0E
+    op("RRCA",            1,  4, |cpu, _| cpu_shift_a(cpu, Shift::RotateRightCircular)),         // 0x0F
+    // 0x10 - 0x1F
+    end("STOP",           2,  4, |cpu, _| cpu_stop(cpu)),                                        // 0x10
+    op("LD DE, d16",      3, 12, |cpu, _| cpu_16_bit_load(cpu, &PairName::DE)),                  // 0x11
+    op("LD (DE), A",      1,  8, |cpu, _| cpu_store_a_indirect(cpu, &PairName::DE)),             // 0x12
+    op("INC DE",          1,  8, |cpu, _| cpu_16_bit_inc(cpu, &PairName::DE)),                   // 0x13
+    op("INC D",           1,  4, |cpu, _| cpu_8_bit_inc(cpu, Operand::D)),                       // 0x14
+    op("DEC D",           1,  4, |cpu, _| cpu_8_bit_dec(cpu, Operand::D)),                       // 0x15
+    op("LD D, d8",        2,  8, |cpu, _| cpu_8_bit_load_immediate(cpu, Operand::D)),            // 0x16
+    op("RLA",             1,  4, |cpu, _| cpu_shift_a(cpu, Shift::RotateLeft)),                  // 0x17
+    end("JR e8",          2, 12, |cpu, _| cpu_jump_relative(cpu, None)),                         // 0x18
+    op("ADD HL, DE",      1,  8, |cpu, _| cpu_16_bit_add(cpu, &PairName::DE)),                   // 0x19
+    op("LD A, (DE)",      1,  8, |cpu, _| cpu_load_a_indirect(cpu, &PairName::DE)),              // 0x1A
+    op("DEC DE",          1,  8, |cpu, _| cpu_16_bit_dec(cpu, &PairName::DE)),                   // 0x1B
+    op("INC E",           1,  4, |cpu, _| cpu_8_bit_inc(cpu, Operand::E)),                       // 0x1C
+    op("DEC E",           1,  4, |cpu, _| cpu_8_bit_dec(cpu, Operand::E)),                       // 0x1D
+    op("LD E, d8",        2,  8, |cpu, _| cpu_8_bit_load_immediate(cpu, Operand::E)),            // 0x1E
+    op("RRA",             1,  4, |cpu, _| cpu_shift_a(cpu, Shift::RotateRight)),                 // 0x1F
+    // 0x20 - 0x2F
+    end("JR NZ, e8",      2,  8, |cpu, _| cpu_jump_relative(cpu, Some(Condition::NotZero))),     // 0x20
+    op("LD HL, d16",      3, 12, |cpu, _| cpu_16_bit_load(cpu, &PairName::HL)),                  // 0x21
+    op("LD (HL+), A",     1,  8, |cpu, _| cpu_store_a_hl(cpu, 1)),                               // 0x22
+    op("INC HL",          1,  8, |cpu, _| cpu_16_bit_inc(cpu, &PairName::HL)),                   // 0x23
+    op("INC H",           1,  4, |cpu, _| cpu_8_bit_inc(cpu, Operand::H)),                       // 0x24
+    op("DEC H",           1,  4, |cpu, _| cpu_8_bit_dec(cpu, Operand::H)),                       // 0x25
+    op("LD H, d8",        2,  8, |cpu, _| cpu_8_bit_load_immediate(cpu, Operand::H)),            // 0x26
+    op("DAA",             1,  4, |cpu, _| cpu_decimal_adjust(cpu)),                              // 0x27
+    end("JR Z, e8",       2,  8, |cpu, _| cpu_jump_relative(cpu, Some(Condition::Zero))),        // 0x28
+    op("ADD HL, HL",      1,  8, |cpu, _| cpu_16_bit_add(cpu, &PairName::HL)),                   // 0x29
+    op("LD A, (HL+)",     1,  8, |cpu, _| cpu_load_a_hl(cpu, 1)),                                // 0x2A
+    op("DEC HL",          1,  8, |cpu, _| cpu_16_bit_dec(cpu, &PairName::HL)),                   // 0x2B
+    op("INC L",           1,  4, |cpu, _| cpu_8_bit_inc(cpu, Operand::L)),                       // 0x2C
+    op("DEC L",           1,  4, |cpu, _| cpu_8_bit_dec(cpu, Operand::L)),                       // 0x2D
+    op("LD L, d8",        2,  8, |cpu, _| cpu_8_bit_load_immediate(cpu, Operand::L)),            // 0x2E
+    op("CPL",             1,  4, |cpu, _| cpu_complement_a(cpu)),                                // 0x2F
+    // 0x30 - 0x3F
+    end("JR NC, e8",      2,  8, |cpu, _| cpu_jump_relative(cpu, Some(Condition::NotCarry))),    // 0x30
+    op("LD SP, d16",      3, 12, |cpu, _| cpu_load_sp_immediate(cpu)),                           // 0x31
+    op("LD (HL-), A",     1,  8, |cpu, _| cpu_store_a_hl(cpu, -1)),                              // 0x32
+    op("INC SP",          1,  8, |cpu, _| cpu_inc_sp(cpu)),                                      // 0x33
+    op("INC (HL)",        1, 12, |cpu, _| cpu_8_bit_inc(cpu, Operand::HlIndirect)),              // 0x34
+    op("DEC (HL)",        1, 12, |cpu, _| cpu_8_bit_dec(cpu, Operand::HlIndirect)),              // 0x35
+    op("LD (HL), d8",     2, 12, |cpu, _| cpu_8_bit_load_immediate(cpu, Operand::HlIndirect)),   // 0x36
+    op("SCF",             1,  4, |cpu, _| cpu_set_carry(cpu)),                                   // 0x37
+    end("JR C, e8",       2,  8, |cpu, _| cpu_jump_relative(cpu, Some(Condition::Carry))),       // 0x38
+    op("ADD HL, SP",      1,  8, |cpu, _| cpu_add_hl_sp(cpu)),                                   // 0x39
+    op("LD A, (HL-)",     1,  8, |cpu, _| cpu_load_a_hl(cpu, -1)),                               // 0x3A
+    op("DEC SP",          1,  8, |cpu, _| cpu_dec_sp(cpu)),                                      // 0x3B
+    op("INC A",           1,  4, |cpu, _| cpu_8_bit_inc(cpu, Operand::A)),                       // 0x3C
+    op("DEC A",           1,  4, |cpu, _| cpu_8_bit_dec(cpu, Operand::A)),                       // 0x3D
+    op("LD A, d8",        2,  8, |cpu, _| cpu_8_bit_load_immediate(cpu, Operand::A)),            // 0x3E
+    op("CCF",             1,  4, |cpu, _| cpu_complement_carry(cpu)),                            // 0x3F
+    // 0x40 - 0x4F
+    op("LD B, B",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::B, Operand::B)),          // 0x40
+    op("LD B, C",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::B, Operand::C)),          // 0x41
+    op("LD B, D",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::B, Operand::D)),          // 0x42
+    op("LD B, E",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::B, Operand::E)),          // 0x43
+    op("LD B, H",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::B, Operand::H)),          // 0x44
+    op("LD B, L",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::B, Operand::L)),          // 0x45
+    op("LD B, (HL)",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::B, Operand::HlIndirect)), // 0x46
+    op("LD B, A",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::B, Operand::A)),          // 0x47
+    op("LD C, B",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::C, Operand::B)),          // 0x48
+    op("LD C, C",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::C, Operand::C)),          // 0x49
+    op("LD C, D",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::C, Operand::D)),          // 0x4A
+    op("LD C, E",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::C, Operand::E)),          // 0x4B
+    op("LD C, H",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::C, Operand::H)),          // 0x4C
+    op("LD C, L",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::C, Operand::L)),          // 0x4D
+    op("LD C, (HL)",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::C, Operand::HlIndirect)), // 0x4E
+    op("LD C, A",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::C, Operand::A)),          // 0x4F
+    // 0x50 - 0x5F
+    op("LD D, B",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::D, Operand::B)),          // 0x50
+    op("LD D, C",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::D, Operand::C)),          // 0x51
+    op("LD D, D",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::D, Operand::D)),          // 0x52
+    op("LD D, E",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::D, Operand::E)),          // 0x53
+    op("LD D, H",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::D, Operand::H)),          // 0x54
+    op("LD D, L",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::D, Operand::L)),          // 0x55
+    op("LD D, (HL)",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::D, Operand::HlIndirect)), // 0x56
+    op("LD D, A",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::D, Operand::A)),          // 0x57
+    op("LD E, B",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::E, Operand::B)),          // 0x58
+    op("LD E, C",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::E, Operand::C)),          // 0x59
+    op("LD E, D",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::E, Operand::D)),          // 0x5A
+    op("LD E, E",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::E, Operand::E)),          // 0x5B
+    op("LD E, H",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::E, Operand::H)),          // 0x5C
+    op("LD E, L",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::E, Operand::L)),          // 0x5D
+    op("LD E, (HL)",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::E, Operand::HlIndirect)), // 0x5E
+    op("LD E, A",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::E, Operand::A)),          // 0x5F
+    // 0x60 - 0x6F
+    op("LD H, B",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::H, Operand::B)),          // 0x60
+    op("LD H, C",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::H, Operand::C)),          // 0x61
+    op("LD H, D",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::H, Operand::D)),          // 0x62
+    op("LD H, E",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::H, Operand::E)),          // 0x63
+    op("LD H, H",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::H, Operand::H)),          // 0x64
+    op("LD H, L",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::H, Operand::L)),          // 0x65
+    op("LD H, (HL)",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::H, Operand::HlIndirect)), // 0x66
+    op("LD H, A",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::H, Operand::A)),          // 0x67
+    op("LD L, B",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::L, Operand::B)),          // 0x68
+    op("LD L, C",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::L, Operand::C)),          // 0x69
+    op("LD L, D",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::L, Operand::D)),          // 0x6A
+    op("LD L, E",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::L, Operand::E)),          // 0x6B
+    op("LD L, H",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::L, Operand::H)),          // 0x6C
+    op("LD L, L",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::L, Operand::L)),          // 0x6D
+    op("LD L, (HL)",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::L, Operand::HlIndirect)), // 0x6E
+    op("LD L, A",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::L, Operand::A)),          // 0x6F
+    // 0x70 - 0x7F
+    op("LD (HL), B",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::HlIndirect, Operand::B)), // 0x70
+    op("LD (HL), C",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::HlIndirect, Operand::C)), // 0x71
+    op("LD (HL), D",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::HlIndirect, Operand::D)), // 0x72
+    op("LD (HL), E",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::HlIndirect, Operand::E)), // 0x73
+    op("LD (HL), H",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::HlIndirect, Operand::H)), // 0x74
+    op("LD (HL), L",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::HlIndirect, Operand::L)), // 0x75
+    end("HALT",           1,  4, |cpu, _| cpu_halt(cpu)),                                        // 0x76
+    op("LD (HL), A",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::HlIndirect, Operand::A)), // 0x77
+    op("LD A, B",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::A, Operand::B)),          // 0x78
+    op("LD A, C",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::A, Operand::C)),          // 0x79
+    op("LD A, D",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::A, Operand::D)),          // 0x7A
+    op("LD A, E",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::A, Operand::E)),          // 0x7B
+    op("LD A, H",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::A, Operand::H)),          // 0x7C
+    op("LD A, L",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::A, Operand::L)),          // 0x7D
+    op("LD A, (HL)",      1,  8, |cpu, _| cpu_8_bit_load(cpu, Operand::A, Operand::HlIndirect)), // 0x7E
+    op("LD A, A",         1,  4, |cpu, _| cpu_8_bit_load(cpu, Operand::A, Operand::A)),          // 0x7F
+    // 0x80 - 0x8F
+    op("ADD A, B",        1,  4, |cpu, _| cpu_8_bit_add(cpu, Operand::B)),                       // 0x80
+    op("ADD A, C",        1,  4, |cpu, _| cpu_8_bit_add(cpu, Operand::C)),                       // 0x81
+    op("ADD A, D",        1,  4, |cpu, _| cpu_8_bit_add(cpu, Operand::D)),                       // 0x82
+    op("ADD A, E",        1,  4, |cpu, _| cpu_8_bit_add(cpu, Operand::E)),                       // 0x83
+    op("ADD A, H",        1,  4, |cpu, _| cpu_8_bit_add(cpu, Operand::H)),                       // 0x84
+    op("ADD A, L",        1,  4, |cpu, _| cpu_8_bit_add(cpu, Operand::L)),                       // 0x85
+    op("ADD A, (HL)",     1,  8, |cpu, _| cpu_8_bit_add(cpu, Operand::HlIndirect)),              // 0x86
+    op("ADD A, A",        1,  4, |cpu, _| cpu_8_bit_add(cpu, Operand::A)),                       // 0x87
+    op("ADC A, B",        1,  4, |cpu, _| cpu_8_bit_adc(cpu, Operand::B)),                       // 0x88
+    op("ADC A, C",        1,  4, |cpu, _| cpu_8_bit_adc(cpu, Operand::C)),                       // 0x89
+    op("ADC A, D",        1,  4, |cpu, _| cpu_8_bit_adc(cpu, Operand::D)),                       // 0x8A
+    op("ADC A, E",        1,  4, |cpu, _| cpu_8_bit_adc(cpu, Operand::E)),                       // 0x8B
+    op("ADC A, H",        1,  4, |cpu, _| cpu_8_bit_adc(cpu, Operand::H)),                       // 0x8C
+    op("ADC A, L",        1,  4, |cpu, _| cpu_8_bit_adc(cpu, Operand::L)),                       // 0x8D
+    op("ADC A, (HL)",     1,  8, |cpu, _| cpu_8_bit_adc(cpu, Operand::HlIndirect)),              // 0x8E
+    op("ADC A, A",        1,  4, |cpu, _| cpu_8_bit_adc(cpu, Operand::A)),                       // 0x8F
+    // 0x90 - 0x9F
+    op("SUB B",           1,  4, |cpu, _| cpu_8_bit_sub(cpu, Operand::B)),                       // 0x90
+    op("SUB C",           1,  4, |cpu, _| cpu_8_bit_sub(cpu, Operand::C)),                       // 0x91
+    op("SUB D",           1,  4, |cpu, _| cpu_8_bit_sub(cpu, Operand::D)),                       // 0x92
+    op("SUB E",           1,  4, |cpu, _| cpu_8_bit_sub(cpu, Operand::E)),                       // 0x93
+    op("SUB H",           1,  4, |cpu, _| cpu_8_bit_sub(cpu, Operand::H)),                       // 0x94
+    op("SUB L",           1,  4, |cpu, _| cpu_8_bit_sub(cpu, Operand::L)),                       // 0x95
+    op("SUB (HL)",        1,  8, |cpu, _| cpu_8_bit_sub(cpu, Operand::HlIndirect)),              // 0x96
+    op("SUB A",           1,  4, |cpu, _| cpu_8_bit_sub(cpu, Operand::A)),                       // 0x97
+    op("SBC A, B",        1,  4, |cpu, _| cpu_8_bit_sbc(cpu, Operand::B)),                       // 0x98
+    op("SBC A, C",        1,  4, |cpu, _| cpu_8_bit_sbc(cpu, Operand::C)),                       // 0x99
+    op("SBC A, D",        1,  4, |cpu, _| cpu_8_bit_sbc(cpu, Operand::D)),                       // 0x9A
+    op("SBC A, E",        1,  4, |cpu, _| cpu_8_bit_sbc(cpu, Operand::E)),                       // 0x9B
+    op("SBC A, H",        1,  4, |cpu, _| cpu_8_bit_sbc(cpu, Operand::H)),                       // 0x9C
+    op("SBC A, L",        1,  4, |cpu, _| cpu_8_bit_sbc(cpu, Operand::L)),                       // 0x9D
+    op("SBC A, (HL)",     1,  8, |cpu, _| cpu_8_bit_sbc(cpu, Operand::HlIndirect)),              // 0x9E
+    op("SBC A, A",        1,  4, |cpu, _| cpu_8_bit_sbc(cpu, Operand::A)),                       // 0x9F
+    // 0xA0 - 0xAF
+    op("AND B",           1,  4, |cpu, _| cpu_8_bit_and(cpu, Operand::B)),                       // 0xA0
+    op("AND C",           1,  4, |cpu, _| cpu_8_bit_and(cpu, Operand::C)),                       // 0xA1
+    op("AND D",           1,  4, |cpu, _| cpu_8_bit_and(cpu, Operand::D)),                       // 0xA2
+    op("AND E",           1,  4, |cpu, _| cpu_8_bit_and(cpu, Operand::E)),                       // 0xA3
+    op("AND H",           1,  4, |cpu, _| cpu_8_bit_and(cpu, Operand::H)),                       // 0xA4
+    op("AND L",           1,  4, |cpu, _| cpu_8_bit_and(cpu, Operand::L)),                       // 0xA5
+    op("AND (HL)",        1,  8, |cpu, _| cpu_8_bit_and(cpu, Operand::HlIndirect)),              // 0xA6
+    op("AND A",           1,  4, |cpu, _| cpu_8_bit_and(cpu, Operand::A)),                       // 0xA7
+    op("XOR B",           1,  4, |cpu, _| cpu_8_bit_xor(cpu, Operand::B)),                       // 0xA8
+    op("XOR C",           1,  4, |cpu, _| cpu_8_bit_xor(cpu, Operand::C)),                       // 0xA9
+    op("XOR D",           1,  4, |cpu, _| cpu_8_bit_xor(cpu, Operand::D)),                       // 0xAA
+    op("XOR E",           1,  4, |cpu, _| cpu_8_bit_xor(cpu, Operand::E)),                       // 0xAB
+    op("XOR H",           1,  4, |cpu, _| cpu_8_bit_xor(cpu, Operand::H)),                       // 0xAC
+    op("XOR L",           1,  4, |cpu, _| cpu_8_bit_xor(cpu, Operand::L)),                       // 0xAD
+    op("XOR (HL)",        1,  8, |cpu, _| cpu_8_bit_xor(cpu, Operand::HlIndirect)),              // 0xAE
+    op("XOR A",           1,  4, |cpu, _| cpu_8_bit_xor(cpu, Operand::A)),                       // 0xAF
+    // 0xB0 - 0xBF
+    op("OR B",            1,  4, |cpu, _| cpu_8_bit_or(cpu, Operand::B)),                        // 0xB0
+    op("OR C",            1,  4, |cpu, _| cpu_8_bit_or(cpu, Operand::C)),                        // 0xB1
+    op("OR D",            1,  4, |cpu, _| cpu_8_bit_or(cpu, Operand::D)),                        // 0xB2
+    op("OR E",            1,  4, |cpu, _| cpu_8_bit_or(cpu, Operand::E)),                        // 0xB3
+    op("OR H",            1,  4, |cpu, _| cpu_8_bit_or(cpu, Operand::H)),                        // 0xB4
+    op("OR L",            1,  4, |cpu, _| cpu_8_bit_or(cpu, Operand::L)),                        // 0xB5
+    op("OR (HL)",         1,  8, |cpu, _| cpu_8_bit_or(cpu, Operand::HlIndirect)),               // 0xB6
+    op("OR A",            1,  4, |cpu, _| cpu_8_bit_or(cpu, Operand::A)),                        // 0xB7
+    op("CP B",            1,  4, |cpu, _| cpu_8_bit_cp(cpu, Operand::B)),                        // 0xB8
+    op("CP C",            1,  4, |cpu, _| cpu_8_bit_cp(cpu, Operand::C)),                        // 0xB9
+    op("CP D",            1,  4, |cpu, _| cpu_8_bit_cp(cpu, Operand::D)),                        // 0xBA
+    op("CP E",            1,  4, |cpu, _| cpu_8_bit_cp(cpu, Operand::E)),                        // 0xBB
+    op("CP H",            1,  4, |cpu, _| cpu_8_bit_cp(cpu, Operand::H)),                        // 0xBC
+    op("CP L",            1,  4, |cpu, _| cpu_8_bit_cp(cpu, Operand::L)),                        // 0xBD
+    op("CP (HL)",         1,  8, |cpu, _| cpu_8_bit_cp(cpu, Operand::HlIndirect)),               // 0xBE
+    op("CP A",            1,  4, |cpu, _| cpu_8_bit_cp(cpu, Operand::A)),                        // 0xBF
+    // 0xC0 - 0xCF
+    end("RET NZ",         1,  8, |cpu, _| cpu_return(cpu, Some(Condition::NotZero))),            // 0xC0
+    op("POP BC",          1, 12, |cpu, _| cpu_pop(cpu, &PairName::BC)),                          // 0xC1
+    end("JP NZ, a16",     3, 12, |cpu, _| cpu_jump(cpu, Some(Condition::NotZero))),              // 0xC2
+    end("JP a16",         3, 16, |cpu, _| cpu_jump(cpu, None)),                                  // 0xC3
+    end("CALL NZ, a16",   3, 12, |cpu, _| cpu_call(cpu, Some(Condition::NotZero))),              // 0xC4
+    op("PUSH BC",         1, 16, |cpu, _| cpu_push(cpu, &PairName::BC)),                         // 0xC5
+    op("ADD A, d8",       2,  8, |cpu, _| cpu_8_bit_add_immediate(cpu)),                         // 0xC6
+    end("RST 00H",        1, 16, |cpu, _| cpu_restart(cpu, 0x00)),                               // 0xC7
+    end("RET Z",          1,  8, |cpu, _| cpu_return(cpu, Some(Condition::Zero))),               // 0xC8
+    end("RET",            1, 16, |cpu, _| cpu_return(cpu, None)),                                // 0xC9
+    end("JP Z, a16",      3, 12, |cpu, _| cpu_jump(cpu, Some(Condition::Zero))),                 // 0xCA
+    op("PREFIX CB",       2,  8, cpu_prefix_cb),                                                  // 0xCB
+    end("CALL Z, a16",    3, 12, |cpu, _| cpu_call(cpu, Some(Condition::Zero))),                 // 0xCC
+    end("CALL a16",       3, 24, |cpu, _| cpu_call(cpu, None)),                                  // 0xCD
+    op("ADC A, d8",       2,  8, |cpu, _| cpu_8_bit_adc_immediate(cpu)),                         // 0xCE
+    end("RST 08H",        1, 16, |cpu, _| cpu_restart(cpu, 0x08)),                               // 0xCF
+    // 0xD0 - 0xDF
+    end("RET NC",         1,  8, |cpu, _| cpu_return(cpu, Some(Condition::NotCarry))),           // 0xD0
+    op("POP DE",          1, 12, |cpu, _| cpu_pop(cpu, &PairName::DE)),                          // 0xD1
+    end("JP NC, a16",     3, 12, |cpu, _| cpu_jump(cpu, Some(Condition::NotCarry))),             // 0xD2
+    ILLEGAL,                                                                                     // 0xD3
+    end("CALL NC, a16",   3, 12, |cpu, _| cpu_call(cpu, Some(Condition::NotCarry))),             // 0xD4
+    op("PUSH DE",         1, 16, |cpu, _| cpu_push(cpu, &PairName::DE)),                         // 0xD5
+    op("SUB d8",          2,  8, |cpu, _| cpu_8_bit_sub_immediate(cpu)),                         // 0xD6
+    end("RST 10H",        1, 16, |cpu, _| cpu_restart(cpu, 0x10)),                               // 0xD7
+    end("RET C",          1,  8, |cpu, _| cpu_return(cpu, Some(Condition::Carry))),              // 0xD8
+    end("RETI",           1, 16, |cpu, _| cpu_return_from_interrupt(cpu)),                       // 0xD9
+    end("JP C, a16",      3, 12, |cpu, _| cpu_jump(cpu, Some(Condition::Carry))),                // 0xDA
+    ILLEGAL,                                                                                     // 0xDB
+    end("CALL C, a16",    3, 12, |cpu, _| cpu_call(cpu, Some(Condition::Carry))),                // 0xDC
+    ILLEGAL,                                                                                     // 0xDD
+    op("SBC A, d8",       2,  8, |cpu, _| cpu_8_bit_sbc_immediate(cpu)),                         // 0xDE
+    end("RST 18H",        1, 16, |cpu, _| cpu_restart(cpu, 0x18)),                               // 0xDF
+    // 0xE0 - 0xEF
+    op("LDH (a8), A",     2, 12, |cpu, _| cpu_store_a_high(cpu)),                                // 0xE0
+    op("POP HL",          1, 12, |cpu, _| cpu_pop(cpu, &PairName::HL)),                          // 0xE1
+    op("LD (C), A",       1,  8, |cpu, _| cpu_store_a_high_c(cpu)),                              // 0xE2
+    ILLEGAL,                                                                                     // 0xE3
+    ILLEGAL,                                                                                     // 0xE4
+    op("PUSH HL",         1, 16, |cpu, _| cpu_push(cpu, &PairName::HL)),                         // 0xE5
+    op("AND d8",          2,  8, |cpu, _| cpu_8_bit_and_immediate(cpu)),                         // 0xE6
+    end("RST 20H",        1, 16, |cpu, _| cpu_restart(cpu, 0x20)),                               // 0xE7
+    op("ADD SP, e8",      2, 16, |cpu, _| cpu_add_sp_offset(cpu)),                               // 0xE8
+    end("JP HL",          1,  4, |cpu, _| cpu_jump_hl(cpu)),                                     // 0xE9
+    op("LD (a16), A",     3, 16, |cpu, _| cpu_store_a_absolute(cpu)),                            // 0xEA
+    ILLEGAL,                                                                                     // 0xEB
+    ILLEGAL,                                                                                     // 0xEC
+    ILLEGAL,                                                                                     // 0xED
+    op("XOR d8",          2,  8, |cpu, _| cpu_8_bit_xor_immediate(cpu)),                         // 0xEE
+    end("RST 28H",        1, 16, |cpu, _| cpu_restart(cpu, 0x28)),                               // 0xEF
+    // 0xF0 - 0xFF
+    op("LDH A, (a8)",     2, 12, |cpu, _| cpu_load_a_high(cpu)),                                 // 0xF0
+    op("POP AF",          1, 12, |cpu, _| cpu_pop(cpu, &PairName::AF)),                          // 0xF1
+    op("LD A, (C)",       1,  8, |cpu, _| cpu_load_a_high_c(cpu)),                               // 0xF2
+    op("DI",              1,  4, |cpu, _| cpu_disable_interrupts(cpu)),                          // 0xF3
+    ILLEGAL,                                                                                     // 0xF4
+    op("PUSH AF",         1, 16, |cpu, _| cpu_push(cpu, &PairName::AF)),                         // 0xF5
+    op("OR d8",           2,  8, |cpu, _| cpu_8_bit_or_immediate(cpu)),                          // 0xF6
+    end("RST 30H",        1, 16, |cpu, _| cpu_restart(cpu, 0x30)),                               // 0xF7
+    op("LD HL, SP + e8",  2, 12, |cpu, _| cpu_load_hl_sp_offset(cpu)),                           // 0xF8
+    op("LD SP, HL",       1,  8, |cpu, _| cpu_load_sp_hl(cpu)),                                  // 0xF9
+    op("LD A, (a16)",     3, 16, |cpu, _| cpu_load_a_absolute(cpu)),                             // 0xFA
+    op("EI",              1,  4, |cpu, _| cpu_enable_interrupts(cpu)),                           // 0xFB
+    ILLEGAL,                                                                                     // 0xFC
+    ILLEGAL,                                                                                     // 0xFD
+    op("CP d8",           2,  8, |cpu, _| cpu_8_bit_cp_immediate(cpu)),                          // 0xFE
+    end("RST 38H",        1, 16, |cpu, _| cpu_restart(cpu, 0x38)),                               // 0xFF
+];
+
+// The 0xCB page is completely regular. Bits 2 - 0 pick the operand the same way as
+// the base page, bits 5 - 3 pick the shift (or the bit for BIT/RES/SET) and bits
+// 7 - 6 pick the group. The cycle counts include fetching the prefix.
+pub static CB_OPCODES: [OpHandler; 256] = [
+    // 0xCB 0x00 - 0x0F
+    op("RLC B",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeftCircular)),
+    op("RLC C",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeftCircular)),
+    op("RLC D",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeftCircular)),
+    op("RLC E",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeftCircular)),
+    op("RLC H",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeftCircular)),
+    op("RLC L",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeftCircular)),
+    op("RLC (HL)",        2, 16, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeftCircular)),
+    op("RLC A",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeftCircular)),
+    op("RRC B",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRightCircular)),
+    op("RRC C",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRightCircular)),
+    op("RRC D",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRightCircular)),
+    op("RRC E",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRightCircular)),
+    op("RRC H",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRightCircular)),
+    op("RRC L",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRightCircular)),
+    op("RRC (HL)",        2, 16, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRightCircular)),
+    op("RRC A",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRightCircular)),
+    // 0xCB 0x10 - 0x1F
+    op("RL B",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeft)),
+    op("RL C",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeft)),
+    op("RL D",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeft)),
+    op("RL E",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeft)),
+    op("RL H",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeft)),
+    op("RL L",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeft)),
+    op("RL (HL)",         2, 16, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeft)),
+    op("RL A",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateLeft)),
+    op("RR B",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRight)),
+    op("RR C",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRight)),
+    op("RR D",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRight)),
+    op("RR E",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRight)),
+    op("RR H",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRight)),
+    op("RR L",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRight)),
+    op("RR (HL)",         2, 16, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRight)),
+    op("RR A",            2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RotateRight)),
+    // 0xCB 0x20 - 0x2F
+    op("SLA B",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::LeftArithmetic)),
+    op("SLA C",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::LeftArithmetic)),
+    op("SLA D",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::LeftArithmetic)),
+    op("SLA E",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::LeftArithmetic)),
+    op("SLA H",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::LeftArithmetic)),
+    op("SLA L",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::LeftArithmetic)),
+    op("SLA (HL)",        2, 16, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::LeftArithmetic)),
+    op("SLA A",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::LeftArithmetic)),
+    op("SRA B",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightArithmetic)),
+    op("SRA C",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightArithmetic)),
+    op("SRA D",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightArithmetic)),
+    op("SRA E",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightArithmetic)),
+    op("SRA H",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightArithmetic)),
+    op("SRA L",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightArithmetic)),
+    op("SRA (HL)",        2, 16, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightArithmetic)),
+    op("SRA A",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightArithmetic)),
+    // 0xCB 0x30 - 0x3F
+    op("SWAP B",          2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::Swap)),
+    op("SWAP C",          2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::Swap)),
+    op("SWAP D",          2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::Swap)),
+    op("SWAP E",          2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::Swap)),
+    op("SWAP H",          2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::Swap)),
+    op("SWAP L",          2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::Swap)),
+    op("SWAP (HL)",       2, 16, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::Swap)),
+    op("SWAP A",          2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::Swap)),
+    op("SRL B",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightLogical)),
+    op("SRL C",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightLogical)),
+    op("SRL D",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightLogical)),
+    op("SRL E",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightLogical)),
+    op("SRL H",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightLogical)),
+    op("SRL L",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightLogical)),
+    op("SRL (HL)",        2, 16, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightLogical)),
+    op("SRL A",           2,  8, |cpu, op| cpu_shift(cpu, get_operand_from_bits(op), Shift::RightLogical)),
+    // 0xCB 0x40 - 0x4F
+    op("BIT 0, B",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 0, C",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 0, D",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 0, E",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 0, H",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 0, L",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 0, (HL)",     2, 12, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 0, A",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 1, B",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 1, C",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 1, D",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 1, E",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 1, H",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 1, L",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 1, (HL)",     2, 12, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 1, A",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    // 0xCB 0x50 - 0x5F
+    op("BIT 2, B",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 2, C",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 2, D",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 2, E",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 2, H",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 2, L",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 2, (HL)",     2, 12, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 2, A",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 3, B",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 3, C",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 3, D",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 3, E",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 3, H",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 3, L",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 3, (HL)",     2, 12, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 3, A",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    // 0xCB 0x60 - 0x6F
+    op("BIT 4, B",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 4, C",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 4, D",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 4, E",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 4, H",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 4, L",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 4, (HL)",     2, 12, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 4, A",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 5, B",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 5, C",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 5, D",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 5, E",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 5, H",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 5, L",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 5, (HL)",     2, 12, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 5, A",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    // 0xCB 0x70 - 0x7F
+    op("BIT 6, B",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 6, C",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 6, D",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 6, E",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 6, H",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 6, L",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 6, (HL)",     2, 12, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 6, A",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 7, B",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 7, C",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 7, D",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 7, E",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 7, H",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 7, L",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 7, (HL)",     2, 12, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("BIT 7, A",        2,  8, |cpu, op| cpu_test_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    // 0xCB 0x80 - 0x8F
+    op("RES 0, B",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 0, C",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 0, D",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 0, E",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 0, H",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 0, L",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 0, (HL)",     2, 16, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 0, A",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 1, B",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 1, C",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 1, D",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 1, E",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 1, H",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 1, L",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 1, (HL)",     2, 16, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 1, A",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    // 0xCB 0x90 - 0x9F
+    op("RES 2, B",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 2, C",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 2, D",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 2, E",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 2, H",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 2, L",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 2, (HL)",     2, 16, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 2, A",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 3, B",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 3, C",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 3, D",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 3, E",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 3, H",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 3, L",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 3, (HL)",     2, 16, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 3, A",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    // 0xCB 0xA0 - 0xAF
+    op("RES 4, B",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 4, C",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 4, D",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 4, E",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 4, H",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 4, L",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 4, (HL)",     2, 16, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 4, A",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 5, B",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 5, C",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 5, D",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 5, E",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 5, H",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 5, L",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 5, (HL)",     2, 16, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 5, A",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    // 0xCB 0xB0 - 0xBF
+    op("RES 6, B",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 6, C",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 6, D",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 6, E",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 6, H",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 6, L",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 6, (HL)",     2, 16, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 6, A",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 7, B",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 7, C",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 7, D",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 7, E",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 7, H",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 7, L",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 7, (HL)",     2, 16, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("RES 7, A",        2,  8, |cpu, op| cpu_reset_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    // 0xCB 0xC0 - 0xCF
+    op("SET 0, B",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 0, C",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 0, D",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 0, E",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 0, H",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 0, L",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 0, (HL)",     2, 16, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 0, A",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 1, B",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 1, C",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 1, D",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 1, E",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 1, H",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 1, L",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 1, (HL)",     2, 16, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 1, A",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    // 0xCB 0xD0 - 0xDF
+    op("SET 2, B",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 2, C",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 2, D",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 2, E",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 2, H",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 2, L",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 2, (HL)",     2, 16, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 2, A",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 3, B",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 3, C",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 3, D",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 3, E",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 3, H",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 3, L",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 3, (HL)",     2, 16, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 3, A",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    // 0xCB 0xE0 - 0xEF
+    op("SET 4, B",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 4, C",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 4, D",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 4, E",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 4, H",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 4, L",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 4, (HL)",     2, 16, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 4, A",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 5, B",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 5, C",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 5, D",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 5, E",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 5, H",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 5, L",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 5, (HL)",     2, 16, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 5, A",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    // 0xCB 0xF0 - 0xFF
+    op("SET 6, B",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 6, C",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 6, D",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 6, E",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 6, H",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 6, L",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 6, (HL)",     2, 16, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 6, A",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 7, B",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 7, C",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 7, D",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 7, E",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 7, H",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 7, L",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 7, (HL)",     2, 16, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+    op("SET 7, A",        2,  8, |cpu, op| cpu_set_bit(cpu, get_operand_from_bits(op), (op >> 3) & 7)),
+];
+
+fn cpu_prefix_cb(cpu: &mut cpu::Cpu, _: u8) -> OpResult {
+    // Prefix for the second page of opcodes
+    let cb_operation = read_immediate_byte(cpu);
+    (CB_OPCODES[cb_operation as usize].execute)(cpu, cb_operation)
+}
+
+fn cpu_illegal(cpu: &mut cpu::Cpu, operation: u8) -> OpResult {
+    // 0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB - 0xED, 0xF4, 0xFC and 0xFD don't exist
+    // on the hardware. The CPU hangs on them for good, leaving the PC where it is
+    log!("Illegal opcode {:#04x} at {:#06x}, the CPU has locked up", operation, cpu.program_counter);
+    cpu.lock();
+    OpResult::jump(4)
+}
+
+// Register and memory access
+
+fn get_operand_from_bits(operation: u8) -> Operand {
+    match operation & 7 {
+        0 => Operand::B,
+        1 => Operand::C,
+        2 => Operand::D,
+        3 => Operand::E,
+        4 => Operand::H,
+        5 => Operand::L,
+        6 => Operand::HlIndirect,
+        _ => Operand::A,
+    }
+}
+
+fn get_pair(cpu: &cpu::Cpu, pair: &PairName) -> u16 {
+    cpu.get_pair(pair).value()
+}
+
+fn set_pair(cpu: &mut cpu::Cpu, pair: &PairName, value: u16) {
+    cpu.get_pair_mut(pair).set_value(value);
+}
+
+fn get_operand(cpu: &mut cpu::Cpu, operand: Operand) -> u8 {
+    match operand {
+        Operand::B          => cpu.bc.hi(),
+        Operand::C          => cpu.bc.lo(),
+        Operand::D          => cpu.de.hi(),
+        Operand::E          => cpu.de.lo(),
+        Operand::H          => cpu.hl.hi(),
+        Operand::L          => cpu.hl.lo(),
+        Operand::HlIndirect => cpu.read_cycle(&(get_pair(cpu, &PairName::HL) as usize)),
+        Operand::A          => cpu.af.hi(),
+    }
+}
+
+fn set_operand(cpu: &mut cpu::Cpu, operand: Operand, value: u8) {
+    let (pair, hi) = match operand {
+        Operand::B          => (PairName::BC, true),
+        Operand::C          => (PairName::BC, false),
+        Operand::D          => (PairName::DE, true),
+        Operand::E          => (PairName::DE, false),
+        Operand::H          => (PairName::HL, true),
+        Operand::L          => (PairName::HL, false),
+        Operand::A          => (PairName::AF, true),
+        Operand::HlIndirect => {
+            let address = get_pair(cpu, &PairName::HL) as usize;
+            cpu.write_cycle(&address, value);
+            return;
+        }
+    };
+
+    let register = cpu.get_pair_mut(&pair);
+    if hi {
+        register.set_hi(value);
+    } else {
+        register.set_lo(value);
+    }
+}
+
+fn get_a(cpu: &cpu::Cpu) -> u8 {
+    cpu.af.hi()
+}
+
+fn set_a(cpu: &mut cpu::Cpu, value: u8) {
+    set_operand(cpu, Operand::A, value);
+}
+
+fn read_immediate_byte(cpu: &mut cpu::Cpu) -> u8 {
+    // The byte after the opcode
+    cpu.read_cycle(&(cpu.program_counter.wrapping_add(1) as usize))
+}
+
+fn read_immediate_word(cpu: &mut cpu::Cpu) -> u16 {
+    // The two bytes after the opcode, low byte first
+    let lo = cpu.read_cycle(&(cpu.program_counter.wrapping_add(1) as usize)) as u16;
+    let hi = cpu.read_cycle(&(cpu.program_counter.wrapping_add(2) as usize)) as u16;
+    (hi << 8) | lo
+}
+
+fn get_sp(cpu: &cpu::Cpu) -> u16 {
+    cpu.stack_pointer.value()
+}
+
+fn set_sp(cpu: &mut cpu::Cpu, value: u16) {
+    cpu.stack_pointer.set_value(value);
+}
+
+fn do_jump(cpu: &mut cpu::Cpu, address: u16) {
+    cpu.program_counter = address;
+}
+
+fn get_next_instruction(cpu: &cpu::Cpu, length: u16) -> u16 {
+    // Where execution carries on after an instruction of this length
+    cpu.program_counter.wrapping_add(length)
+}
+
+// Flags
+
+fn check_condition(cpu: &cpu::Cpu, condition: Option<Condition>) -> bool {
+    match condition {
+        None                      => true,
+        Some(Condition::NotZero)  => !cpu.is_flag_set(utils::ZERO_BIT),
+        Some(Condition::Zero)     => cpu.is_flag_set(utils::ZERO_BIT),
+        Some(Condition::NotCarry) => !cpu.is_flag_set(utils::CARRY_BIT),
+        Some(Condition::Carry)    => cpu.is_flag_set(utils::CARRY_BIT),
+    }
+}
+
+// 16 Bit Loads
+
+fn cpu_16_bit_load(cpu: &mut cpu::Cpu, pair: &PairName) -> OpResult {
+    // LD rr, nn
+    let data = read_immediate_word(cpu);
+    set_pair(cpu, pair, data);
+    OpResult::next(12, 3)
+}
+
+fn cpu_load_sp_immediate(cpu: &mut cpu::Cpu) -> OpResult {
+    // LD SP, nn
+    let data = read_immediate_word(cpu);
+    set_sp(cpu, data);
+    OpResult::next(12, 3)
+}
+
+fn cpu_store_sp(cpu: &mut cpu::Cpu) -> OpResult {
+    // LD (nn), SP - low byte first
+    let address = read_immediate_word(cpu) as usize;
+    let sp = get_sp(cpu);
+    cpu.write_cycle(&address, (sp & 0xFF) as u8);
+    cpu.write_cycle(&((address + 1) & 0xFFFF), (sp >> 8) as u8);
+    OpResult::next(20, 3)
+}
+
+fn cpu_load_sp_hl(cpu: &mut cpu::Cpu) -> OpResult {
+    // LD SP, HL
+    let hl = get_pair(cpu, &PairName::HL);
+    set_sp(cpu, hl);
+    OpResult::next(8, 1)
+}
+
+fn cpu_push(cpu: &mut cpu::Cpu, pair: &PairName) -> OpResult {
+    let value = get_pair(cpu, pair);
+    // SP is decremented in an internal cycle before the writes
+    cpu.tick(4);
+    cpu.push_word_to_stack(&value);
+    OpResult::next(16, 1)
+}
+
+fn cpu_pop(cpu: &mut cpu::Cpu, pair: &PairName) -> OpResult {
+    let mut value = cpu.pop_word_from_stack();
+
+    // The low 4 bits of F don't exist and always read back as 0
+    if *pair == PairName::AF {
+        value &= 0xFFF0;
+    }
+
+    set_pair(cpu, pair, value);
+    OpResult::next(12, 1)
+}
+
+// 8 Bit Loads
+
+fn cpu_8_bit_load_immediate(cpu: &mut cpu::Cpu, operand: Operand) -> OpResult {
+    // LD r, n
+    let data = read_immediate_byte(cpu);
+    set_operand(cpu, operand, data);
+    let cycles = if operand == Operand::HlIndirect { 12 } else { 8 };
+    OpResult::next(cycles, 2)
+}
+
+fn cpu_8_bit_load(cpu: &mut cpu::Cpu, destination: Operand, source: Operand) -> OpResult {
+    // LD r, r
+    let data = get_operand(cpu, source);
+    set_operand(cpu, destination, data);
+    let cycles = if destination == Operand::HlIndirect || source == Operand::HlIndirect { 8 } else { 4 };
+    OpResult::next(cycles, 1)
+}
+
+fn cpu_store_a_indirect(cpu: &mut cpu::Cpu, pair: &PairName) -> OpResult {
+    // LD (BC), A / LD (DE), A
+    let address = get_pair(cpu, pair) as usize;
+    let a = get_a(cpu);
+    cpu.write_cycle(&address, a);
+    OpResult::next(8, 1)
+}
+
+fn cpu_load_a_indirect(cpu: &mut cpu::Cpu, pair: &PairName) -> OpResult {
+    // LD A, (BC) / LD A, (DE)
+    let address = get_pair(cpu, pair) as usize;
+    let data = cpu.read_cycle(&address);
+    set_a(cpu, data);
+    OpResult::next(8, 1)
+}
+
+fn cpu_store_a_hl(cpu: &mut cpu::Cpu, step: i16) -> OpResult {
+    // LD (HL+), A / LD (HL-), A
+    let hl = get_pair(cpu, &PairName::HL);
+    let a = get_a(cpu);
+    cpu.write_cycle(&(hl as usize), a);
+    set_pair(cpu, &PairName::HL, hl.wrapping_add(step as u16));
+    OpResult::next(8, 1)
+}
+
+fn cpu_load_a_hl(cpu: &mut cpu::Cpu, step: i16) -> OpResult {
+    // LD A, (HL+) / LD A, (HL-)
+    let hl = get_pair(cpu, &PairName::HL);
+    let data = cpu.read_cycle_increment(&(hl as usize));
+    set_a(cpu, data);
+    set_pair(cpu, &PairName::HL, hl.wrapping_add(step as u16));
+    OpResult::next(8, 1)
+}
+
+fn cpu_store_a_absolute(cpu: &mut cpu::Cpu) -> OpResult {
+    // LD (nn), A
+    let address = read_immediate_word(cpu) as usize;
+    let a = get_a(cpu);
+    cpu.write_cycle(&address, a);
+    OpResult::next(16, 3)
+}
+
+fn cpu_load_a_absolute(cpu: &mut cpu::Cpu) -> OpResult {
+    // LD A, (nn)
+    let address = read_immediate_word(cpu) as usize;
+    let data = cpu.read_cycle(&address);
+    set_a(cpu, data);
+    OpResult::next(16, 3)
+}
+
+fn cpu_store_a_high(cpu: &mut cpu::Cpu) -> OpResult {
+    // LDH (n), A - writes to 0xFF00 + n
+    let address = 0xFF00 + read_immediate_byte(cpu) as usize;
+    let a = get_a(cpu);
+    cpu.write_cycle(&address, a);
+    OpResult::next(12, 2)
+}
+
+fn cpu_load_a_high(cpu: &mut cpu::Cpu) -> OpResult {
+    // LDH A, (n) - reads from 0xFF00 + n
+    let address = 0xFF00 + read_immediate_byte(cpu) as usize;
+    let data = cpu.read_cycle(&address);
+    set_a(cpu, data);
+    OpResult::next(12, 2)
+}
+
+fn cpu_store_a_high_c(cpu: &mut cpu::Cpu) -> OpResult {
+    // LD (C), A - writes to 0xFF00 + C
+    let address = 0xFF00 + get_operand(cpu, Operand::C) as usize;
+    let a = get_a(cpu);
+    cpu.write_cycle(&address, a);
+    OpResult::next(8, 1)
+}
+
+fn cpu_load_a_high_c(cpu: &mut cpu::Cpu) -> OpResult {
+    // LD A, (C) - reads from 0xFF00 + C
+    let address = 0xFF00 + get_operand(cpu, Operand::C) as usize;
+    let data = cpu.read_cycle(&address);
+    set_a(cpu, data);
+    OpResult::next(8, 1)
+}
+
+// 8 Bit ALU
+
+fn get_alu_cycles(operand: Operand) -> usize {
+    // Going through (HL) costs an extra memory read
+    if operand == Operand::HlIndirect { 8 } else { 4 }
+}
+
+fn cpu_8_bit_inc(cpu: &mut cpu::Cpu, operand: Operand) -> OpResult {
+    // INC r - carry is left alone
+    let value = get_operand(cpu, operand);
+    let result = value.wrapping_add(1);
+    set_operand(cpu, operand, result);
+
+    let carry = cpu.is_flag_set(utils::CARRY_BIT);
+    cpu.set_flags(result == 0, false, utils::is_half_carry_add(value, 1, 0), carry);
+    let cycles = if operand == Operand::HlIndirect { 12 } else { 4 };
+    OpResult::next(cycles, 1)
+}
+
+fn cpu_8_bit_dec(cpu: &mut cpu::Cpu, operand: Operand) -> OpResult {
+    // DEC r - carry is left alone
+    let value = get_operand(cpu, operand);
+    let result = value.wrapping_sub(1);
+    set_operand(cpu, operand, result);
+
+    let carry = cpu.is_flag_set(utils::CARRY_BIT);
+    cpu.set_flags(result == 0, true, utils::is_half_carry_sub(value, 1, 0), carry);
+    let cycles = if operand == Operand::HlIndirect { 12 } else { 4 };
+    OpResult::next(cycles, 1)
+}
+
+fn do_add(cpu: &mut cpu::Cpu, value: u8, use_carry: bool) {
+    let a = get_a(cpu);
+    let carry_in = if use_carry && cpu.is_flag_set(utils::CARRY_BIT) { 1 } else { 0 };
+    let result = a.wrapping_add(value).wrapping_add(carry_in);
+
+    set_a(cpu, result);
+    cpu.set_flags(
+        result == 0,
+        false,
+        utils::is_half_carry_add(a, value, carry_in),
+        utils::is_carry_add(a, value, carry_in),
+    );
+}
+
+fn do_sub(cpu: &mut cpu::Cpu, value: u8, use_carry: bool, store: bool) {
+    // SUB, SBC and CP (which is a SUB that throws the result away)
+    let a = get_a(cpu);
+    let carry_in = if use_carry && cpu.is_flag_set(utils::CARRY_BIT) { 1 } else { 0 };
+    let result = a.wrapping_sub(value).wrapping_sub(carry_in);
+
+    if store {
+        set_a(cpu, result);
+    }
+    cpu.set_flags(
+        result == 0,
+        true,
+        utils::is_half_carry_sub(a, value, carry_in),
+        utils::is_carry_sub(a, value, carry_in),
+    );
+}
+
+fn do_and(cpu: &mut cpu::Cpu, value: u8) {
+    let result = get_a(cpu) & value;
+    set_a(cpu, result);
+    cpu.set_flags(result == 0, false, true, false);
+}
+
+fn do_xor(cpu: &mut cpu::Cpu, value: u8) {
+    let result = get_a(cpu) ^ value;
+    set_a(cpu, result);
+    cpu.set_flags(result == 0, false, false, false);
+}
+
+fn do_or(cpu: &mut cpu::Cpu, value: u8) {
+    let result = get_a(cpu) | value;
+    set_a(cpu, result);
+    cpu.set_flags(result == 0, false, false, false);
+}
+
+fn cpu_8_bit_add(cpu: &mut cpu::Cpu, operand: Operand) -> OpResult {
+    let value = get_operand(cpu, operand);
+    do_add(cpu, value, false);
+    OpResult::next(get_alu_cycles(operand), 1)
+}
+
+fn cpu_8_bit_add_immediate(cpu: &mut cpu::Cpu) -> OpResult {
+    let value = read_immediate_byte(cpu);
+    do_add(cpu, value, false);
+    OpResult::next(8, 2)
+}
+
+fn cpu_8_bit_adc(cpu: &mut cpu::Cpu, operand: Operand) -> OpResult {
+    let value = get_operand(cpu, operand);
+    do_add(cpu, value, true);
+    OpResult::next(get_alu_cycles(operand), 1)
+}
+
+fn cpu_8_bit_adc_immediate(cpu: &mut cpu::Cpu) -> OpResult {
+    let value = read_immediate_byte(cpu);
+    do_add(cpu, value, true);
+    OpResult::next(8, 2)
+}
+
+fn cpu_8_bit_sub(cpu: &mut cpu::Cpu, operand: Operand) -> OpResult {
+    let value = get_operand(cpu, operand);
+    do_sub(cpu, value, false, true);
+    OpResult::next(get_alu_cycles(operand), 1)
+}
+
+fn cpu_8_bit_sub_immediate(cpu: &mut cpu::Cpu) -> OpResult {
+    let value = read_immediate_byte(cpu);
+    do_sub(cpu, value, false, true);
+    OpResult::next(8, 2)
+}
+
+fn cpu_8_bit_sbc(cpu: &mut cpu::Cpu, operand: Operand) -> OpResult {
+    let value = get_operand(cpu, operand);
+    do_sub(cpu, value, true, true);
+    OpResult::next(get_alu_cycles(operand), 1)
+}
+
+fn cpu_8_bit_sbc_immediate(cpu: &mut cpu::Cpu) -> OpResult {
+    let value = read_immediate_byte(cpu);
+    do_sub(cpu, value, true, true);
+    OpResult::next(8, 2)
+}
+
+fn cpu_8_bit_and(cpu: &mut cpu::Cpu, operand: Operand) -> OpResult {
+    let value = get_operand(cpu, operand);
+    do_and(cpu, value);
+    OpResult::next(get_alu_cycles(operand), 1)
+}
+
+fn cpu_8_bit_and_immediate(cpu: &mut cpu::Cpu) -> OpResult {
+    let value = read_immediate_byte(cpu);
+    do_and(cpu, value);
+    OpResult::next(8, 2)
+}
+
+fn cpu_8_bit_xor(cpu: &mut cpu::Cpu, operand: Operand) -> OpResult {
+    let value = get_operand(cpu, operand);
+    do_xor(cpu, value);
+    OpResult::next(get_alu_cycles(operand), 1)
+}
+
+fn cpu_8_bit_xor_immediate(cpu: &mut cpu::Cpu) -> OpResult {
+    let value = read_immediate_byte(cpu);
+    do_xor(cpu, value);
+    OpResult::next(8, 2)
+}
+
+fn cpu_8_bit_or(cpu: &mut cpu::Cpu, operand: Operand) -> OpResult {
+    let value = get_operand(cpu, operand);
+    do_or(cpu, value);
+    OpResult::next(get_alu_cycles(operand), 1)
+}
+
+fn cpu_8_bit_or_immediate(cpu: &mut cpu::Cpu) -> OpResult {
+    let value = read_immediate_byte(cpu);
+    do_or(cpu, value);
+    OpResult::next(8, 2)
+}
+
+fn cpu_8_bit_cp(cpu: &mut cpu::Cpu, operand: Operand) -> OpResult {
+    let value = get_operand(cpu, operand);
+    do_sub(cpu, value, false, false);
+    OpResult::next(get_alu_cycles(operand), 1)
+}
+
+fn cpu_8_bit_cp_immediate(cpu: &mut cpu::Cpu) -> OpResult {
+    let value = read_immediate_byte(cpu);
+    do_sub(cpu, value, false, false);
+    OpResult::next(8, 2)
+}
+
+fn cpu_decimal_adjust(cpu: &mut cpu::Cpu) -> OpResult {
+    // DAA - turns A back into binary coded decimal after adding or subtracting two
+    // BCD numbers. N tells us which of the two the last operation was, and H and C
+    // say whether each digit overflowed. After an addition a digit can also have
+    // gone past 9 without a carry, so that has to be checked as well
+    let mut a = get_a(cpu);
+    let subtract = cpu.is_flag_set(utils::SUBTRACT_BIT);
+    let half_carry = cpu.is_flag_set(utils::HALF_CARRY_BIT);
+    let mut carry = cpu.is_flag_set(utils::CARRY_BIT);
+
+    let mut correction = 0;
+    if half_carry || (!subtract && (a & 0xF) > 0x9) {
+        correction |= 0x06;
+    }
+    if carry || (!subtract && a > 0x99) {
+        correction |= 0x60;
+        carry = true;
+    }
+
+    a = if subtract { a.wrapping_sub(correction) } else { a.wrapping_add(correction) };
+    set_a(cpu, a);
+
+    // N is kept, H is always cleared and C stays set once set
+    cpu.set_flags(a == 0, subtract, false, carry);
+    OpResult::next(4, 1)
+}
+
+fn cpu_complement_a(cpu: &mut cpu::Cpu) -> OpResult {
+    // CPL - flips every bit of A, zero and carry are left alone
+    let a = get_a(cpu);
+    set_a(cpu, !a);
+
+    cpu.set_flag(utils::SUBTRACT_BIT);
+    cpu.set_flag(utils::HALF_CARRY_BIT);
+    OpResult::next(4, 1)
+}
+
+fn cpu_set_carry(cpu: &mut cpu::Cpu) -> OpResult {
+    // SCF
+    cpu.clear_flag(utils::SUBTRACT_BIT);
+    cpu.clear_flag(utils::HALF_CARRY_BIT);
+    cpu.set_flag(utils::CARRY_BIT);
+    OpResult::next(4, 1)
+}
+
+fn cpu_complement_carry(cpu: &mut cpu::Cpu) -> OpResult {
+    // CCF
+    cpu.clear_flag(utils::SUBTRACT_BIT);
+    cpu.clear_flag(utils::HALF_CARRY_BIT);
+    if cpu.is_flag_set(utils::CARRY_BIT) {
+        cpu.clear_flag(utils::CARRY_BIT);
+    } else {
+        cpu.set_flag(utils::CARRY_BIT);
+    }
+    OpResult::next(4, 1)
+}
+
+// 16 Bit ALU - none of these touch Z
+
+fn cpu_16_bit_inc(cpu: &mut cpu::Cpu, pair: &PairName) -> OpResult {
+    // INC rr - no flags at all
+    let value = get_pair(cpu, pair);
+    cpu.address_cycle(&(value as usize));
+    set_pair(cpu, pair, value.wrapping_add(1));
+    OpResult::next(8, 1)
+}
+
+fn cpu_inc_sp(cpu: &mut cpu::Cpu) -> OpResult {
+    // INC SP
+    let sp = get_sp(cpu);
+    cpu.address_cycle(&(sp as usize));
+    set_sp(cpu, sp.wrapping_add(1));
+    OpResult::next(8, 1)
+}
+
+fn cpu_16_bit_dec(cpu: &mut cpu::Cpu, pair: &PairName) -> OpResult {
+    // DEC rr - no flags at all
+    let value = get_pair(cpu, pair);
+    cpu.address_cycle(&(value as usize));
+    set_pair(cpu, pair, value.wrapping_sub(1));
+    OpResult::next(8, 1)
+}
+
+fn cpu_dec_sp(cpu: &mut cpu::Cpu) -> OpResult {
+    // DEC SP
+    let sp = get_sp(cpu);
+    cpu.address_cycle(&(sp as usize));
+    set_sp(cpu, sp.wrapping_sub(1));
+    OpResult::next(8, 1)
+}
+
+fn do_add_hl(cpu: &mut cpu::Cpu, value: u16) {
+    // ADD HL - half carry is out of bit 11 and carry out of bit 15, the same
+    // as the high bytes being added with the carry from the low bytes
+    let hl = get_pair(cpu, &PairName::HL);
+    let zero = cpu.is_flag_set(utils::ZERO_BIT);
+    cpu.set_flags(zero, false, utils::is_half_carry_add_16(hl, value), utils::is_carry_add_16(hl, value));
+    set_pair(cpu, &PairName::HL, hl.wrapping_add(value));
+}
+
+fn cpu_16_bit_add(cpu: &mut cpu::Cpu, pair: &PairName) -> OpResult {
+    // ADD HL, rr
+    let value = get_pair(cpu, pair);
+    do_add_hl(cpu, value);
+    OpResult::next(8, 1)
+}
+
+fn cpu_add_hl_sp(cpu: &mut cpu::Cpu) -> OpResult {
+    // ADD HL, SP
+    let sp = get_sp(cpu);
+    do_add_hl(cpu, sp);
+    OpResult::next(8, 1)
+}
+
+fn do_add_sp_offset(cpu: &mut cpu::Cpu) -> u16 {
+    // SP + e for ADD SP, e and LD HL, SP + e. e is signed, but the flags come from
+    // adding it to the low byte of SP as if it were unsigned. Z and N are cleared
+    let offset = read_immediate_byte(cpu);
+    let sp = get_sp(cpu);
+    let low = (sp & 0xFF) as u8;
+    cpu.set_flags(false, false, utils::is_half_carry_add(low, offset, 0), utils::is_carry_add(low, offset, 0));
+    sp.wrapping_add(offset as i8 as u16)
+}
+
+fn cpu_add_sp_offset(cpu: &mut cpu::Cpu) -> OpResult {
+    // ADD SP, e
+    let result = do_add_sp_offset(cpu);
+    set_sp(cpu, result);
+    OpResult::next(16, 2)
+}
+
+fn cpu_load_hl_sp_offset(cpu: &mut cpu::Cpu) -> OpResult {
+    // LD HL, SP + e
+    let result = do_add_sp_offset(cpu);
+    set_pair(cpu, &PairName::HL, result);
+    OpResult::next(12, 2)
+}
+
+// Jumps, calls and returns. Conditional ones take longer when the branch is taken:
+// JP cc 16 / 12, JR cc 12 / 8, CALL cc 24 / 12 and RET cc 20 / 8
+
+fn cpu_jump(cpu: &mut cpu::Cpu, condition: Option<Condition>) -> OpResult {
+    // JP nn / JP cc, nn
+    let address = read_immediate_word(cpu);
+    if !check_condition(cpu, condition) {
+        return OpResult::not_taken(12, 3);
+    }
+
+    do_jump(cpu, address);
+    OpResult::jump(16)
+}
+
+fn cpu_jump_hl(cpu: &mut cpu::Cpu) -> OpResult {
+    // JP HL
+    let hl = get_pair(cpu, &PairName::HL);
+    do_jump(cpu, hl);
+    OpResult::jump(4)
+}
+
+fn cpu_jump_relative(cpu: &mut cpu::Cpu, condition: Option<Condition>) -> OpResult {
+    // JR e / JR cc, e - e is signed and relative to the next instruction
+    let offset = read_immediate_byte(cpu) as i8;
+    if !check_condition(cpu, condition) {
+        return OpResult::not_taken(8, 2);
+    }
+
+    let address = get_next_instruction(cpu, 2).wrapping_add(offset as u16);
+    do_jump(cpu, address);
+    OpResult::jump(12)
+}
+
+fn cpu_call(cpu: &mut cpu::Cpu, condition: Option<Condition>) -> OpResult {
+    // CALL nn / CALL cc, nn
+    let address = read_immediate_word(cpu);
+    if !check_condition(cpu, condition) {
+        return OpResult::not_taken(12, 3);
+    }
+
+    let return_address = get_next_instruction(cpu, 3);
+    cpu.tick(4);
+    cpu.push_word_to_stack(&return_address);
+    do_jump(cpu, address);
+    OpResult::jump(24)
+}
+
+fn cpu_return(cpu: &mut cpu::Cpu, condition: Option<Condition>) -> OpResult {
+    // RET / RET cc
+    // Checking the condition takes an internal cycle of its own
+    if condition.is_some() {
+        cpu.tick(4);
+    }
+
+    match condition {
+        None => {
+            let address = cpu.pop_word_from_stack();
+            do_jump(cpu, address);
+            OpResult::jump(16)
+        }
+
+        Some(_) if check_condition(cpu, condition) => {
+            let address = cpu.pop_word_from_stack();
+            do_jump(cpu, address);
+            OpResult::jump(20)
+        }
+
+        Some(_) => OpResult::not_taken(8, 1)
+    }
+}
+
+fn cpu_return_from_interrupt(cpu: &mut cpu::Cpu) -> OpResult {
+    // RETI - RET that also turns interrupts back on, with no delay unlike EI
+    let address = cpu.pop_word_from_stack();
+    do_jump(cpu, address);
+    cpu.restore_interrupts();
+    OpResult::jump(16)
+}
+
+fn cpu_restart(cpu: &mut cpu::Cpu, vector: u16) -> OpResult {
+    // RST - a one byte CALL to one of the fixed addresses at the start of ROM
+    let return_address = get_next_instruction(cpu, 1);
+    cpu.tick(4);
+    cpu.push_word_to_stack(&return_address);
+    do_jump(cpu, vector);
+    OpResult::jump(16)
+}
+
+// Interrupts
+
+fn cpu_halt(cpu: &mut cpu::Cpu) -> OpResult {
+    // HALT - the PC still moves on, execution carries on after it once woken
+    cpu.halt();
+    OpResult::next(4, 1)
+}
+
+fn cpu_stop(cpu: &mut cpu::Cpu) -> OpResult {
+    // STOP - followed by a padding byte that is skipped over
+    cpu.stop();
+    OpResult::next(4, 2)
+}
+
+fn cpu_disable_interrupts(cpu: &mut cpu::Cpu) -> OpResult {
+    // DI
+    cpu.disable_interrupts();
+    OpResult::next(4, 1)
+}
+
+fn cpu_enable_interrupts(cpu: &mut cpu::Cpu) -> OpResult {
+    // EI
+    cpu.enable_interrupts();
+    OpResult::next(4, 1)
+}
+
+// 0xCB page - rotates, shifts and single bit operations
+
+fn get_cb_result(operand: Operand) -> OpResult {
+    // (HL) is read and written back
+    OpResult::next(if operand == Operand::HlIndirect { 16 } else { 8 }, 2)
+}
+
+// The rotates and shifts. The accumulator versions on the base page (RLCA, RRCA,
+// RLA, RRA) work the same as the 0xCB ones on A, but always clear Z
+#[derive(Copy, Clone)]
+enum Shift {
+    RotateLeftCircular,  // RLC - bit 7 goes to both carry and bit 0
+    RotateRightCircular, // RRC - bit 0 goes to both carry and bit 7
+    RotateLeft,          // RL - rotates through the carry flag
+    RotateRight,         // RR - rotates through the carry flag
+    LeftArithmetic,      // SLA - bit 0 becomes 0
+    RightArithmetic,     // SRA - bit 7 keeps its value
+    Swap,                // SWAP - exchanges the nibbles, carry is always cleared
+    RightLogical,        // SRL - bit 7 becomes 0
+}
+
+fn do_shift(cpu: &mut cpu::Cpu, shift: Shift, value: u8, accumulator: bool) -> u8 {
+    // Works out the result and sets the flags, N and H are always cleared
+    let carry_in = if cpu.is_flag_set(utils::CARRY_BIT) { 1 } else { 0 };
+    let (result, carry) = match shift {
+        Shift::RotateLeftCircular  => (value.rotate_left(1), value & 0x80 > 0),
+        Shift::RotateRightCircular => (value.rotate_right(1), value & 1 > 0),
+        Shift::RotateLeft          => ((value << 1) | carry_in, value & 0x80 > 0),
+        Shift::RotateRight         => ((value >> 1) | (carry_in << 7), value & 1 > 0),
+        Shift::LeftArithmetic      => (value << 1, value & 0x80 > 0),
+        Shift::RightArithmetic     => ((value >> 1) | (value & 0x80), value & 1 > 0),
+        Shift::Swap                => (value.rotate_left(4), false),
+        Shift::RightLogical        => (value >> 1, value & 1 > 0),
+    };
+
+    cpu.set_flags(result == 0 && !accumulator, false, false, carry);
+    result
+}
+
+fn cpu_shift(cpu: &mut cpu::Cpu, operand: Operand, shift: Shift) -> OpResult {
+    // RLC, RRC, RL, RR, SLA, SRA, SWAP and SRL on any operand
+    let value = get_operand(cpu, operand);
+    let result = do_shift(cpu, shift, value, false);
+    set_operand(cpu, operand, result);
+    get_cb_result(operand)
+}
+
+fn cpu_shift_a(cpu: &mut cpu::Cpu, shift: Shift) -> OpResult {
+    // RLCA, RRCA, RLA and RRA
+    let value = get_a(cpu);
+    let result = do_shift(cpu, shift, value, true);
+    set_a(cpu, result);
+    OpResult::next(4, 1)
+}
+
+fn cpu_test_bit(cpu: &mut cpu::Cpu, operand: Operand, bit: u8) -> OpResult {
+    // BIT - Z is set when the bit is 0, carry is left alone. (HL) is only read,
+    // so it's quicker than the other (HL) operations
+    let value = get_operand(cpu, operand);
+    let carry = cpu.is_flag_set(utils::CARRY_BIT);
+    cpu.set_flags(value & (1 << bit) == 0, false, true, carry);
+    OpResult::next(if operand == Operand::HlIndirect { 12 } else { 8 }, 2)
+}
+
+fn cpu_reset_bit(cpu: &mut cpu::Cpu, operand: Operand, bit: u8) -> OpResult {
+    // RES - flags are untouched
+    let value = get_operand(cpu, operand);
+    set_operand(cpu, operand, value & !(1 << bit));
+    get_cb_result(operand)
+}
+
+fn cpu_set_bit(cpu: &mut cpu::Cpu, operand: Operand, bit: u8) -> OpResult {
+    // SET - flags are untouched
+    let value = get_operand(cpu, operand);
+    set_operand(cpu, operand, value | (1 << bit));
+    get_cb_result(operand)
+}
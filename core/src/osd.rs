@@ -0,0 +1,147 @@
+// On-screen display
+//
+// Draws short messages ("State saved", "Turbo ON", FPS counters) straight into the
+// output frame with a tiny built-in font, so frontends don't need to keep an HTML
+// overlay lined up with the canvas. Messages are drawn onto the converted frame
+// only, the emulated screen is never touched.
+
+// Each glyph is 3 pixels wide and 5 tall. Every row is 3 bits, bit 2 is the left
+// most pixel
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+
+// Space between characters and around the message box
+const SPACING: usize = 1;
+
+fn get_glyph(character: char) -> [u8; 5] {
+    // Lower case letters are drawn as upper case, anything unknown is a ?
+    match character.to_ascii_uppercase() {
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '(' => [0b010, 0b100, 0b100, 0b100, 0b010],
+        ')' => [0b010, 0b001, 0b001, 0b001, 0b010],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        _   => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+pub struct Osd {
+    message: String,
+    frames_remaining: u32,
+}
+
+impl Osd {
+    pub fn new() -> Osd {
+        Osd {
+            message: String::new(),
+            frames_remaining: 0,
+        }
+    }
+
+    pub fn show(&mut self, message: &str, frames: u32) {
+        self.message = String::from(message);
+        self.frames_remaining = frames;
+    }
+
+    pub fn clear(&mut self) {
+        self.frames_remaining = 0;
+    }
+
+    // Called once per emulated frame
+    pub fn tick(&mut self) {
+        if self.frames_remaining > 0 {
+            self.frames_remaining -= 1;
+        }
+    }
+
+    // Draws the current message (if any) into the bottom left of an RGBA frame,
+    // white text on a black box. Text that doesn't fit is cut off
+    pub fn draw(&self, frame: &mut [u8], width: usize, height: usize) {
+        if self.frames_remaining == 0 || self.message.is_empty() {
+            return;
+        }
+
+        let characters = self.message.chars().count();
+        let box_width = (characters * (GLYPH_WIDTH + SPACING) + SPACING).min(width);
+        let box_height = GLYPH_HEIGHT + SPACING * 2;
+        if box_height > height {
+            return;
+        }
+
+        let box_y = height - box_height;
+
+        for y in box_y..height {
+            for x in 0..box_width {
+                self.do_set_pixel(frame, width, x, y, 0);
+            }
+        }
+
+        for (i, character) in self.message.chars().enumerate() {
+            let glyph = get_glyph(character);
+            let glyph_x = SPACING + i * (GLYPH_WIDTH + SPACING);
+            let glyph_y = box_y + SPACING;
+
+            for (row, bits) in glyph.iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    let x = glyph_x + column;
+
+                    // Bit 2 is the left most pixel
+                    if x < box_width && (bits >> (GLYPH_WIDTH - 1 - column)) & 1 > 0 {
+                        self.do_set_pixel(frame, width, x, glyph_y + row, 255);
+                    }
+                }
+            }
+        }
+    }
+
+    fn do_set_pixel(&self, frame: &mut [u8], width: usize, x: usize, y: usize, value: u8) {
+        let index = (y * width + x) * 4;
+        frame[index] = value;
+        frame[index + 1] = value;
+        frame[index + 2] = value;
+    }
+}
@@ -0,0 +1,68 @@
+// Audio driven pacing
+//
+// When update() is called from requestAnimationFrame, emulation runs at whatever
+// the display refresh happens to be, which never quite matches the real 4.19MHz
+// clock. The audio buffer slowly drains or fills until it pops or skips.
+//
+// In this mode the audio consumer is the clock instead. The frontend reports how
+// many samples it played since the last call and how many are still buffered, and
+// we run exactly enough cycles to replace what was played, nudged towards keeping
+// the buffer at its target level.
+
+// Cycles per second of the Gameboy clock
+pub const CLOCK_SPEED: f64 = 4194304.0;
+
+// Cycles in a single frame (see Emulator::update)
+pub const CYCLES_PER_FRAME: usize = 69905;
+
+pub struct AudioPacer {
+    // Cycles asked for but not run yet (or run past what was asked for when
+    // negative), carried over so nothing is lost to rounding or overshoot
+    cycle_debt: f64,
+
+    // Cycles run since the last full frame
+    frame_cycles: usize,
+}
+
+impl AudioPacer {
+    pub fn new() -> AudioPacer {
+        AudioPacer {
+            cycle_debt: 0.0,
+            frame_cycles: 0,
+        }
+    }
+
+    // How many cycles to run to make up for samples_consumed, and correct the
+    // buffer towards target_fill. The correction is limited to a frame's worth of
+    // samples per call so a big underrun doesn't stall the frontend
+    pub fn get_cycles(&mut self, samples_consumed: u32, buffer_fill: u32, target_fill: u32, sample_rate: u32) -> usize {
+        if sample_rate == 0 {
+            return 0;
+        }
+
+        let max_correction = sample_rate as f64 / 60.0;
+        let correction = (target_fill as f64 - buffer_fill as f64)
+            .max(-max_correction)
+            .min(max_correction);
+
+        let samples = (samples_consumed as f64 + correction).max(0.0);
+        self.cycle_debt += samples * CLOCK_SPEED / sample_rate as f64;
+
+        self.cycle_debt.max(0.0) as usize
+    }
+
+    // Records the cycles that actually ran, returns how many frames were finished
+    pub fn add_cycles(&mut self, cycles: usize) -> usize {
+        self.cycle_debt -= cycles as f64;
+        self.frame_cycles += cycles;
+
+        let frames = self.frame_cycles / CYCLES_PER_FRAME;
+        self.frame_cycles %= CYCLES_PER_FRAME;
+        frames
+    }
+
+    pub fn reset(&mut self) {
+        self.cycle_debt = 0.0;
+        self.frame_cycles = 0;
+    }
+}
@@ -0,0 +1,1044 @@
+use super::utils;
+use super::mmu;
+use super::debugger;
+use super::fifo;
+use super::interrupts::Interrupt;
+use super::timeline;
+use super::ppu_stats;
+
+// PPU
+//
+// Draws the screen a scanline at a time and moves the LCD through its modes. Each
+// scanline takes 456 cycles - 80 searching OAM (mode 2), 172 transferring pixels to
+// the LCD (mode 3) and the rest in H-Blank (mode 0). The line is drawn as mode 3
+// starts, so SCX, SCY, LCDC and the palettes are read after anything written to
+// them in H-Blank or by a LY=LYC interrupt at the start of the line has landed.
+// For changes partway through mode 3 there is the pixel FIFO (see fifo.rs).
+// Lines 144 - 153 are V-Blank (mode 1) and nothing is drawn. LY doesn't quite
+// follow the line the PPU is on - it reads 0 for all but the first few dots of
+// line 153 - and LYC is compared against it a few dots after it changes (see
+// get_ly and get_lyc_compare).
+//
+// Turning the LCD off (LCDC bit 7) stops all of this where it is. LY reads 0, STAT
+// reads mode 0 and the screen is blank until it's turned on again, which starts
+// over from line 0. That first line is a little shorter and skips the OAM scan,
+// and the frame it starts isn't shown, the screen stays blank until the next one.
+//
+// The registers (LCDC, STAT, LY...), VRAM and OAM all live in the Mmu, so the Mmu
+// is passed in whenever the PPU runs. Any interrupts it wants are handed back from
+// tick for the Cpu to request.
+
+// Indexes into the palettes used for turning shades into RGB
+pub const BG_PALETTE: usize = 0;
+pub const OBJ_0_PALETTE: usize = 1;
+pub const OBJ_1_PALETTE: usize = 2;
+
+// Scanline counter values where modes 2 and 3 end. It counts down from 456, so
+// mode 2 is while it's above MODE_2_BOUNDS (the first 80 dots), then mode 3 while
+// it's above the line's mode_3_end. Mode 3 takes 172 dots at the least, ending at
+// MODE_3_BOUNDS, but runs longer with fine scroll, the window and sprites on the
+// line (see get_mode_3_length)
+const MODE_2_BOUNDS: u16 = 456 - 80;
+pub const MODE_3_BOUNDS: u16 = MODE_2_BOUNDS - 172;
+
+// What the PPU draws, for hiding any of them while debugging
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Layer {
+    Background = 0,
+    Window = 1,
+    Sprites = 2,
+}
+
+// Most sprites the OAM scan picks for a single line
+const MAX_SPRITES_PER_LINE: usize = 10;
+
+// The default four shades, from white to black
+pub const DEFAULT_SHADES: [[u8; 3]; 4] = [
+    [0xFF, 0xFF, 0xFF],
+    [0xAA, 0xAA, 0xAA],
+    [0x55, 0x55, 0x55],
+    [0x00, 0x00, 0x00],
+];
+
+// A sprite on the current line, with the row of its tile that's on the line
+// x: As stored in OAM, 8 more than its left edge on screen
+// palette: Which of palettes to turn shades into RGB with
+// pixels: Colour numbers left to right, already flipped if the sprite is
+struct LineSprite {
+    index: u8,
+    x: u8,
+    behind_background: bool,
+    palette: usize,
+    pallette_addr: usize,
+    pixels: [u8; 8],
+}
+
+pub struct Ppu {
+    // Cycles left in the current scanline
+    scanline_counter: u16,
+
+    // The line the PPU is on (0 - 153). LY is worked out from it (see get_ly)
+    line: u8,
+
+    // Scanline counter value where mode 3 ends on this line
+    mode_3_end: u16,
+
+    // LCDC bit 7 as of the last tick, to catch the LCD being turned on or off
+    lcd_enabled: bool,
+
+    // Set for the first line and the first frame after the LCD is turned on
+    first_line: bool,
+    blank_frame: bool,
+
+    // The STAT interrupt line, every enabled STAT condition ORed together. The
+    // interrupt is only requested as it goes high, so a condition that starts
+    // while another is still holding the line up doesn't request another one
+    stat_line: bool,
+
+    // Rows of 160 RGBA pixels, ready to go straight into an ImageData. Lines are
+    // drawn into screen_data, which is copied to front_screen_data as V-Blank
+    // starts. Only the front one is handed out, so a frame is never seen half
+    // drawn
+    screen_data: Vec<u8>,
+    front_screen_data: Vec<u8>,
+
+    // RGB for each of the four shades, for the background and both sprite palettes.
+    // These only change how shades are displayed, not the palette registers
+    palettes: [[[u8; 3]; 4]; 3],
+
+    // The last full frame, kept for blending into the one being drawn (LCD
+    // ghosting). Empty unless frame blending is on
+    previous_screen_data: Vec<u8>,
+
+    // The background/window colour number (0 - 3, before BGP) of each pixel on the
+    // current line, for sprites that sit behind the background
+    line_colors: [u8; 160],
+
+    // The window's own line counter. It only moves on for lines the window was
+    // drawn on, so a window hidden for part of the frame carries on from where it
+    // left off rather than skipping ahead with LY
+    window_line: u8,
+
+    // Set once LY has matched WY this frame. The window can only be drawn from
+    // then until the end of the frame, whatever WY is changed to in between
+    window_y_triggered: bool,
+
+    // The sprites (OAM indexes) on the current line, from the OAM scan
+    line_sprites: Vec<u8>,
+
+    // One bit per OAM index for the sprites drawn on any line so far this frame,
+    // and the same for the last full frame for the sprite viewer
+    frame_sprites: u64,
+    last_frame_sprites: u64,
+
+    // Draws lines a dot at a time through mode 3 when set, rather than all at once
+    // as they start (see fifo.rs)
+    pixel_fifo: bool,
+    fifo: fifo::PixelFifo,
+
+    // Layers to draw, indexed by Layer. Hidden layers are left out of the frame
+    // (the background and window as shade 0) but still work the same otherwise,
+    // the game can't tell
+    layers_enabled: [bool; 3],
+
+    // Frames to skip drawing after each one that is drawn, for slow devices.
+    // Skipped frames run the same, only the pixels aren't written, so the last
+    // drawn frame stays on screen
+    frame_skip: u8,
+    skipped_frame: bool,
+
+    // Frames finished since power on, counted as V-Blank starts
+    frame_number: u32,
+
+    // Interrupts wanted since the last tick returned
+    interrupts: Vec<Interrupt>,
+
+    // Per scanline record of the last frame for debugging, off unless enabled
+    pub timeline: timeline::PpuTimeline,
+
+    // Counters for the last frame for debugging, always kept (see ppu_stats.rs)
+    stats: ppu_stats::PpuStats,
+}
+
+impl Ppu {
+    pub fn new() -> Ppu {
+        Ppu {
+            scanline_counter: 456,
+            line: 0,
+            mode_3_end: MODE_3_BOUNDS,
+            lcd_enabled: true,
+            first_line: false,
+            blank_frame: false,
+            stat_line: false,
+            screen_data: [0, 0, 0, 0xFF].repeat(160 * 144),
+            front_screen_data: [0, 0, 0, 0xFF].repeat(160 * 144),
+            palettes: [DEFAULT_SHADES; 3],
+            previous_screen_data: Vec::new(),
+            line_colors: [0; 160],
+            window_line: 0,
+            window_y_triggered: false,
+            line_sprites: Vec::with_capacity(MAX_SPRITES_PER_LINE),
+            frame_sprites: 0,
+            last_frame_sprites: 0,
+            pixel_fifo: false,
+            fifo: fifo::PixelFifo::new(),
+            layers_enabled: [true; 3],
+            frame_skip: 0,
+            skipped_frame: false,
+            frame_number: 0,
+            interrupts: Vec::new(),
+            timeline: timeline::PpuTimeline::new(),
+            stats: ppu_stats::PpuStats::new(),
+        }
+    }
+
+    pub fn tick(&mut self, mmu: &mut mmu::Mmu, cycles: usize) -> Vec<Interrupt> {
+        // Moves the LCD on by some cycles, drawing any scanlines that finish, and
+        // returns the interrupts that should be requested
+        self.check_lcd_enabled(mmu);
+
+        if cycles > 0 {
+            self.update_graphics(mmu, &cycles);
+        }
+
+        // update_graphics sets the mode before moving the scanline counter on, so
+        // bring STAT up to date now rather than at the next tick
+        self.set_lcd_status(mmu);
+
+        std::mem::take(&mut self.interrupts)
+    }
+
+    pub fn get_scanline_counter(&self) -> u16 {
+        self.scanline_counter
+    }
+
+    pub fn set_scanline_counter(&mut self, scanline_counter: u16) {
+        // Only for loading states
+        self.scanline_counter = scanline_counter;
+    }
+
+    pub fn get_line(&self) -> u8 {
+        self.line
+    }
+
+    pub fn set_line(&mut self, line: u8) {
+        // Only for loading states
+        self.line = line.min(153);
+    }
+
+    pub fn get_mode_3_end(&self) -> u16 {
+        self.mode_3_end
+    }
+
+    pub fn set_mode_3_end(&mut self, mode_3_end: u16) {
+        // Only for loading states
+        self.mode_3_end = mode_3_end.clamp(1, MODE_3_BOUNDS);
+    }
+
+    pub fn get_stat_line(&self) -> bool {
+        self.stat_line
+    }
+
+    pub fn set_stat_line(&mut self, stat_line: bool) {
+        // Only for loading states
+        self.stat_line = stat_line;
+    }
+
+    pub fn set_lcd_enabled(&mut self, enabled: bool) {
+        // Only for loading states, so the state's LCDC isn't taken as the LCD
+        // being turned on or off
+        self.lcd_enabled = enabled;
+        self.first_line = false;
+        self.blank_frame = false;
+    }
+
+    pub fn is_sprite_rendered(&self, sprite: usize) -> bool {
+        // Whether the sprite at this OAM index was drawn on any line of the last
+        // full frame
+        self.last_frame_sprites & (1 << sprite) > 0
+    }
+
+    pub fn get_oam_scan_row(&self, mmu: &mmu::Mmu) -> Option<usize> {
+        // The row of OAM (8 bytes, 2 sprites) being read by the OAM scan right now,
+        // it moves on a row every machine cycle of mode 2. None outside of mode 2,
+        // including the first line after the LCD is turned on which has no scan
+        let lcd_on = mmu.read_memory(&utils::LCD_CONTROL_ADDR) & 128 > 0;
+        if !lcd_on || self.line >= 144 || self.first_line || self.scanline_counter <= MODE_2_BOUNDS {
+            return None;
+        }
+
+        Some(456usize.saturating_sub(self.scanline_counter as usize) / 4)
+    }
+
+    pub fn get_frame_stats(&self) -> &ppu_stats::FrameStats {
+        self.stats.get_last_frame()
+    }
+
+    pub fn get_window_line(&self) -> u8 {
+        self.window_line
+    }
+
+    pub fn is_window_y_triggered(&self) -> bool {
+        self.window_y_triggered
+    }
+
+    pub fn set_window_state(&mut self, window_line: u8, window_y_triggered: bool) {
+        // Only for loading states
+        self.window_line = window_line;
+        self.window_y_triggered = window_y_triggered;
+    }
+
+    pub fn set_palette(&mut self, palette: usize, shades: [[u8; 3]; 4]) {
+        if palette < self.palettes.len() {
+            self.palettes[palette] = shades;
+        }
+    }
+
+    pub fn set_pixel_fifo(&mut self, enabled: bool) {
+        self.pixel_fifo = enabled;
+    }
+
+    pub fn set_layer_enabled(&mut self, layer: Layer, enabled: bool) {
+        self.layers_enabled[layer as usize] = enabled;
+        self.fifo.set_layers_enabled(self.layers_enabled[Layer::Background as usize], self.layers_enabled[Layer::Window as usize]);
+    }
+
+    pub fn set_frame_skip(&mut self, frames: u8) {
+        // Takes effect from the next frame
+        self.frame_skip = frames;
+    }
+
+    pub fn get_frame_number(&self) -> u32 {
+        self.frame_number
+    }
+
+    pub fn get_screen_data(&self) -> &[u8] {
+        // The last finished frame
+        &self.front_screen_data
+    }
+
+    pub fn set_frame_blending(&mut self, enabled: bool) {
+        // Starts out as a copy of the current frame so there's nothing stale to
+        // blend in before the next frame finishes
+        self.previous_screen_data = if enabled { self.front_screen_data.clone() } else { Vec::new() };
+    }
+
+    pub fn get_previous_screen_data(&self) -> Option<&[u8]> {
+        // None unless frame blending is on
+        if self.previous_screen_data.is_empty() {
+            None
+        } else {
+            Some(&self.previous_screen_data)
+        }
+    }
+
+    pub fn get_cycles_until_event(&self, mmu: &mmu::Mmu) -> usize {
+        // usize::MAX while the LCD is off, as nothing happens until it's turned on
+        if !self.is_lcd_enabled(mmu) {
+            return usize::MAX;
+        }
+
+        // Counter values where something changes: LY and the LY=LYC comparison
+        // over the first few dots of the line, the mode 2 and 3 ends on visible
+        // lines, and the end of the line
+        let visible = self.line < 144;
+        let boundaries = [
+            (456 - 4, true),
+            (456 - 8, self.line == 153),
+            (456 - 12, self.line == 153),
+            (MODE_2_BOUNDS, visible),
+            (self.mode_3_end, visible),
+        ];
+
+        let counter = self.scanline_counter;
+        let next_boundary = boundaries
+            .iter()
+            .filter(|(boundary, applies)| *applies && *boundary < counter)
+            .map(|(boundary, _)| *boundary)
+            .max()
+            .unwrap_or(0);
+
+        (counter - next_boundary) as usize
+    }
+
+    fn update_graphics(&mut self, mmu: &mut mmu::Mmu, cycles: &usize) {
+        // Deal with setting LCD status
+        self.set_lcd_status(mmu);
+
+        // If LCD Display is enabled, decerement counter by number of cycles
+        // Otherwise do nothing
+        if self.is_lcd_enabled(mmu) {
+            let mode = mmu.read_memory(&utils::LCD_STATUS_ADDR) & 0x3;
+            self.stats.add_mode_dots(mode, *cycles);
+            if self.timeline.is_enabled() {
+                self.timeline.add_mode_dots(self.line, mode, *cycles);
+            }
+
+            // Whether mode 3 starts during these cycles. How long it runs for is
+            // worked out as it starts
+            let counter = self.scanline_counter as usize;
+            let mode_3_starting = self.line < 144 && counter > MODE_2_BOUNDS as usize && counter <= MODE_2_BOUNDS as usize + *cycles;
+            if mode_3_starting {
+                self.check_window_y(mmu, self.line);
+                self.mode_3_end = MODE_2_BOUNDS - self.get_mode_3_length(mmu);
+            }
+
+            if self.pixel_fifo {
+                self.run_fifo(mmu, *cycles);
+            }
+
+            self.scanline_counter = self.scanline_counter.saturating_sub(*cycles as u16);
+            mmu.set_scanline_value(self.get_ly());
+
+            // Without the FIFO visible lines are drawn in one go as mode 3 starts,
+            // which is when the hardware starts reading the registers for them
+            if mode_3_starting && !self.pixel_fifo && !self.blank_frame && !self.skipped_frame {
+                self.draw_scanline(mmu);
+            }
+        } else {
+            return;
+        }
+
+        // If scanline counter hit 0, we need to move onto the next scanline
+        // Current scanline is shown in memory in 0xFF44 (LY), which is read only
+        // so the value is set in memory directly
+        // Scanline 0 - 143 (144 in total) need to be rendered onto the screen
+        // Scanline 144 - 153 is the Vertical Blank Period and we need to
+        // request the Vertical Blank Interrupt
+        // If Scanline is greater than 153, reset to 0
+        if self.scanline_counter == 0 {
+            // Move onto next scanline
+            self.line += 1;
+            let current_line = self.line;
+
+            self.scanline_counter = 456;
+            self.first_line = false;
+
+            // Are we in vertical blank period?
+            if current_line == 144 {
+                self.request_interrupt(Interrupt::VBlank);
+                self.frame_number = self.frame_number.wrapping_add(1);
+                if !self.skipped_frame {
+                    self.present_frame();
+                }
+            } else if current_line > 153 {
+                // Reset if passed scanline 153 (max scanline)
+                self.line = 0;
+                self.window_line = 0;
+                self.window_y_triggered = false;
+                self.blank_frame = false;
+                if !self.skipped_frame {
+                    self.last_frame_sprites = self.frame_sprites;
+                }
+                self.frame_sprites = 0;
+                self.skipped_frame = !self.frame_number.is_multiple_of(self.frame_skip as u32 + 1);
+                self.timeline.end_frame();
+                self.stats.end_frame(self.frame_number);
+                self.scan_oam(mmu, 0);
+            } else if current_line < 144 {
+                self.scan_oam(mmu, current_line);
+            }
+
+            mmu.set_scanline_value(self.get_ly());
+        }
+    }
+
+    fn get_mode_3_length(&self, mmu: &mmu::Mmu) -> u16 {
+        // 172 cycles, plus the SCX % 8 pixels thrown away at the start of the line,
+        // 6 for the fetcher starting over if the window comes in, and 6 - 11 for
+        // each sprite on the line
+        let lcd_control = mmu.read_memory(&utils::LCD_CONTROL_ADDR);
+        let scroll_x = mmu.read_memory(&utils::SCROLL_X_ADDR);
+        let window_x = mmu.read_memory(&utils::WINDOW_X_ADDR);
+
+        let mut length = 172 + (scroll_x % 8) as u16;
+
+        if lcd_control & 32 > 0 && self.window_y_triggered && window_x <= 166 {
+            length += 6;
+        }
+
+        // Each sprite holds the fetcher up for 6 cycles. The first sprite over
+        // each background tile also waits for the fetch of that tile to finish,
+        // up to 5 more depending on how far into the tile the sprite starts. A
+        // sprite at X 0 always costs the full 11
+        if lcd_control & 2 > 0 {
+            let mut tiles_waited = Vec::with_capacity(MAX_SPRITES_PER_LINE);
+            for sprite in self.line_sprites.iter() {
+                let x = mmu.read_memory(&(utils::SPRITE_ATTRIBUTE_ADDR + *sprite as usize * 4 + 1));
+                if x == 0 {
+                    length += 11;
+                    continue;
+                }
+
+                let background_x = x.wrapping_sub(8).wrapping_add(scroll_x);
+                let tile = background_x / 8;
+                if !tiles_waited.contains(&tile) {
+                    tiles_waited.push(tile);
+                    length += 5u16.saturating_sub(background_x as u16 % 8);
+                }
+                length += 6;
+            }
+        }
+
+        length
+    }
+
+    fn get_ly(&self) -> u8 {
+        // What LY reads. Line 153 only shows as 153 for its first 4 dots, LY
+        // moves on to 0 early for the rest of it
+        if self.line == 153 && self.scanline_counter <= 456 - 4 {
+            0
+        } else {
+            self.line
+        }
+    }
+
+    fn get_lyc_compare(&self) -> Option<u8> {
+        // What LYC is compared against, nothing while the comparison is held off.
+        // For the first 4 dots of each line it is, as LY changes. Line 153 then
+        // compares 153 for 4 dots, nothing for 4 more, and 0 from then on, so the
+        // LYC=0 match (and its interrupt) comes during line 153 rather than line 0
+        let dots = 456 - self.scanline_counter;
+        match (self.line, dots) {
+            (0, _)                => Some(0),
+            (_, d) if d < 4       => None,
+            (153, d) if d < 8     => Some(153),
+            (153, d) if d < 12    => None,
+            (153, _)              => Some(0),
+            (line, _)             => Some(line),
+        }
+    }
+
+    fn run_fifo(&mut self, mmu: &mmu::Mmu, cycles: usize) {
+        // Runs the pixel FIFO for whichever of the next cycles fall in mode 3, then
+        // finishes the line off (sprites included) once mode 3 is over. Each dot
+        // belongs to the mode the counter is in after it
+        let line = self.line;
+        if line >= 144 || self.blank_frame || self.skipped_frame {
+            return;
+        }
+
+        let start = self.scanline_counter as usize;
+        let end = start.saturating_sub(cycles);
+        let first = start.saturating_sub(1).min(MODE_2_BOUNDS as usize);
+        let last = end.max(self.mode_3_end as usize + 1);
+
+        if first >= last && !self.fifo.is_active() {
+            self.check_window_y(mmu, line);
+            self.fifo.start_line(mmu, line, self.window_line, self.window_y_triggered);
+        }
+
+        let row_start = line as usize * 160 * 4;
+        let row = &mut self.screen_data[row_start..row_start + 160 * 4];
+        let palette = &self.palettes[BG_PALETTE];
+
+        if first >= last {
+            for _ in last..=first {
+                self.fifo.tick(mmu, row, &mut self.line_colors, palette);
+            }
+        }
+
+        if end <= self.mode_3_end as usize && self.fifo.is_active() {
+            self.fifo.finish_line(mmu, row, &mut self.line_colors, palette);
+            if self.fifo.is_using_window() {
+                self.timeline.set_window(line);
+                self.stats.add_window_line();
+                self.window_line = self.window_line.wrapping_add(1);
+            }
+
+            let lcd_control = mmu.read_memory(&utils::LCD_CONTROL_ADDR);
+            if lcd_control & 2 > 0 {
+                self.render_sprites(mmu, &lcd_control);
+            }
+        }
+    }
+
+    fn set_lcd_status(&mut self, mmu: &mut mmu::Mmu) {
+        // LCD status is stored in memory address 0xFF41
+        // The first 2 bits represent the mode of the LCD and are as follows:
+        // 00 (0): Horizontal-Blank
+        // 01 (1): Vertical-Blank
+        // 10 (2): Searching Sprites Atts
+        // 11 (3): Transfering Data to LCD Driver
+
+        let mut lcd_status = mmu.read_memory(&utils::LCD_STATUS_ADDR);
+        if !self.is_lcd_enabled(mmu) {
+            // If LCD is disabled, the mode reads as 0 (see check_lcd_enabled)
+            // and nothing holds the STAT line up
+            self.stat_line = false;
+            lcd_status &= 252; // 252 = 0b11111100
+            mmu.write_memory(&utils::LCD_STATUS_ADDR, lcd_status);
+            return;
+        }
+
+        // Each scanline takes 456 clock cycles and this is further split up
+        // If within the first 80 cycles of the 456, we should be in mode 2
+        // If within the next 172 cycles of the 456, we should be in  mode 3
+        // Past this point up to the end of the 456, we should be in mode 0
+        // If within V-Blank (scanline 144 - 153) we should be in mode 1
+
+        let current_scanline = self.line;
+        let current_mode = lcd_status & 0x3;
+
+        let mode: u8;
+        let mut mode_interrupt = false;
+
+        if current_scanline >= 144 {
+            // If in V-Blank (recall drawing line greater than or equal to 144)
+            // In this case we need to set the mode to 1
+            mode = 1;
+            lcd_status |= 1; // Set bit 0 to 1
+            lcd_status &= 253; // 253 = 0b11111101 - Unsets bit 1
+            mode_interrupt = lcd_status & 16 > 0; // 16 = 0b00010000 - Tests bit 4 for interrupt enabled
+
+        } else {
+            if self.scanline_counter > MODE_2_BOUNDS && self.first_line {
+                // The first line after the LCD is turned on has no OAM scan, it
+                // stays in mode 0 until mode 3 (without an interrupt)
+                mode = 0;
+                lcd_status &= 252; // 252 = 0b11111100 - Set bit 1 and 0 to 0
+
+            } else if self.scanline_counter > MODE_2_BOUNDS {
+                // mode 2
+                mode = 2;
+                lcd_status &= 254; // 254 = 0b11111110 - Set bit 0 to 0
+                lcd_status |= 2; // 2 = 0b00000010 - Sets bit 1 to 1
+                mode_interrupt = lcd_status & 32 > 0; // 32 = 0b00100000 - Tests bit 5 for interrupt enabled
+
+            } else if self.scanline_counter > self.mode_3_end {
+                // mode 3
+                mode = 3;
+                lcd_status |= 3; // 3 = 0b00000011 - Sets bit 1 and 0 to 1
+
+            } else {
+                // mode 0
+                mode = 0;
+                lcd_status &= 252; // 252 = 0b11111100 - Set bit 1 and 0 to 0
+                mode_interrupt = lcd_status & 8 > 0; // 8 = 0b00001000 - Tests bit 3 for interrupt enabled
+            }
+        }
+
+        if mode != current_mode {
+            mmu.record_event(debugger::HardwareEvent::ModeChanged(mode));
+        }
+
+        // Check coincidence flag
+        // Bit 2 of Status register is Coincedence Flag
+        // This should be set to true if current scanline (0xFF44) is equal to
+        // value in  register 0xFF45. Otherwise turn it off.
+        // If bit 6 is set in the Status register the coincedence flag holds the
+        // STAT line up as well
+        let mut coincidence_interrupt = false;
+        if self.get_lyc_compare() == Some(mmu.read_memory(&0xFF45)) {
+            lcd_status |= 4; // 4 = 0b00000100 - Sets bit 2 to 1
+            coincidence_interrupt = lcd_status & 64 > 0; // 64 = 0b01000000 - Checks bit 6
+
+        } else {
+            lcd_status &= 251; // 251 = 0b11111011 - Reset bit 2 to 0
+        }
+
+        // Only request an LCD interrupt as the STAT line goes from low to high
+        let stat_line = mode_interrupt || coincidence_interrupt;
+        if stat_line && !self.stat_line {
+            self.request_interrupt(Interrupt::Lcd);
+            self.timeline.set_stat_interrupt(current_scanline);
+            self.stats.add_stat_interrupt();
+        }
+        self.stat_line = stat_line;
+
+        // Ensure LCD status is properly written to memory
+        mmu.write_memory(&utils::LCD_STATUS_ADDR, lcd_status);
+    }
+
+    fn check_lcd_enabled(&mut self, mmu: &mut mmu::Mmu) {
+        // Either way the PPU starts again from the top of line 0 with the
+        // window and FIFO reset
+        let enabled = self.is_lcd_enabled(mmu);
+        if enabled == self.lcd_enabled {
+            return;
+        }
+
+        self.lcd_enabled = enabled;
+        self.window_line = 0;
+        self.window_y_triggered = false;
+        self.fifo = fifo::PixelFifo::new();
+        self.line = 0;
+        mmu.set_scanline_value(0);
+
+        if enabled {
+            // Line 0 is 4 dots shorter than the rest, and has no sprites as there
+            // was no OAM scan for it
+            self.scanline_counter = 456 - 4;
+            self.line_sprites.clear();
+            self.first_line = true;
+            self.blank_frame = true;
+        } else {
+            self.scanline_counter = 456;
+            self.first_line = false;
+            self.blank_frame = false;
+            self.clear_screen();
+        }
+    }
+
+    fn clear_screen(&mut self) {
+        // Blank is shade 0 of the background palette, whatever colour that's set to.
+        // The LCD goes blank straight away, not at the next V-Blank
+        let [red, green, blue] = self.palettes[BG_PALETTE][0];
+        for pixel in self.screen_data.chunks_mut(4) {
+            pixel.copy_from_slice(&[red, green, blue, 0xFF]);
+        }
+        self.present_frame();
+    }
+
+    fn present_frame(&mut self) {
+        // The finished frame goes to the front, and the one it replaces is kept
+        // for frame blending if that's on
+        if !self.previous_screen_data.is_empty() {
+            std::mem::swap(&mut self.previous_screen_data, &mut self.front_screen_data);
+        }
+        self.front_screen_data.copy_from_slice(&self.screen_data);
+    }
+
+    pub fn is_lcd_enabled(&self, mmu: &mmu::Mmu) -> bool {
+        // Bit 7 of LCD control register specifies if LCD is enabled or not
+        let lcd_control = mmu.read_memory(&utils::LCD_CONTROL_ADDR);
+        lcd_control & 128 > 0 // 128 = 0b10000000
+    }
+
+    fn draw_scanline(&mut self, mmu: &mmu::Mmu) {
+        let lcd_control = mmu.read_memory(&utils::LCD_CONTROL_ADDR);
+        self.check_window_y(mmu, self.line);
+
+        // If bit 0 is set, than the background display is enabled and we should draw
+        // Otherwise the background and window are left blank, as shade 0
+        if lcd_control & 1 > 0 {
+            self.render_tiles(mmu, &lcd_control);
+        } else {
+            self.line_colors = [0; 160];
+
+            let [red, green, blue] = self.palettes[BG_PALETTE][0];
+            let row_start = self.line as usize * 160 * 4;
+            for pixel in self.screen_data[row_start..row_start + 160 * 4].chunks_mut(4) {
+                pixel.copy_from_slice(&[red, green, blue, 0xFF]);
+            }
+        }
+
+        // If bit 1 is set, tham the sprite display is enabled and we should draw
+        if lcd_control & 2 > 0 {
+            self.render_sprites(mmu, &lcd_control);
+        }
+    }
+
+    fn check_window_y(&mut self, mmu: &mmu::Mmu, line: u8) {
+        // Checked as each line starts, whether or not the window is turned on
+        if line == mmu.read_memory(&utils::WINDOW_Y_ADDR) {
+            self.window_y_triggered = true;
+        }
+    }
+
+    fn render_tiles(&mut self, mmu: &mmu::Mmu, lcd_control: &u8) {
+        // Determine where to draw the visual background and the window
+        let scroll_y = mmu.read_memory(&utils::SCROLL_Y_ADDR);
+        let scroll_x = mmu.read_memory(&utils::SCROLL_X_ADDR);
+        let window_x = mmu.read_memory(&utils::WINDOW_X_ADDR);
+        let current_scanline = self.line;
+
+        // Bit 5 of LCD control register determines if the window is enabled or not
+        // 32 = 0b00100000
+        // Even then it's only drawn once LY has matched WY this frame, and while
+        // WX (the window's left edge plus 7) is 166 or less so it's on screen
+        let using_window = lcd_control & 32 > 0 && self.window_y_triggered && window_x <= 166;
+        if using_window {
+            self.timeline.set_window(current_scanline);
+        }
+
+        // The first pixel the window covers. With WX below 7 the window starts at
+        // the left edge, with its first 7 - WX pixels cut off instead
+        let window_start = window_x.saturating_sub(7);
+        let window_offset = 7u8.saturating_sub(window_x);
+
+        // We need to determine where the tile data is located (region determined by bit 4 of lcd_control)
+        // 16 = 0b00010000
+        // Set it's the region of 0x8000 - 0x8FFF, otherwise 0x8800 - 0x97FF which
+        // uses signed bytes as tile identifiers
+        let unsigned = lcd_control & 16 > 0;
+
+        // We need to determine which background memory region to use
+        // The window's is picked by bit 6 of LCD control, the background's by bit 3
+        // 64 = 0b01000000
+        // 8 = 0b00001000
+        // Either is the region of 0x9C00 - 0x9FFF when set, 0x9800 - 0x9BFF otherwise
+        let window_memory: u16 = if lcd_control & 64 > 0 { 0x9C00 } else { 0x9800 };
+        let background_memory: u16 = if lcd_control & 8 > 0 { 0x9C00 } else { 0x9800 };
+
+        // The y position is used to calculate which of the 32 vertical tiles the scanline is drawing.
+        // The window uses its own line counter rather than LY - WY
+        let window_y_pos = self.window_line;
+        let background_y_pos = scroll_y.wrapping_add(current_scanline);
+
+        // We have 160 horizontal pixels to draw for this scanline
+        for pixel in 0..160u8 {
+            // Pixels from the window's left edge onwards are the window, translated
+            // into window space. Everything else is the scrolled background
+            let (layer, memory, x_pos, y_pos) = if using_window && pixel >= window_start {
+                (Layer::Window, window_memory, pixel - window_start + window_offset, window_y_pos)
+            } else {
+                (Layer::Background, background_memory, pixel.wrapping_add(scroll_x), background_y_pos)
+            };
+
+            // We also need to know which pixel of the current tile the scanline is on
+            let tile_row: u16 = ((y_pos / 8) as u16) * 32;
+
+            // We want to determine which tile this pixel is in - recall each tile is 8x8 pixels
+            let tile_col: u16 = (x_pos / 8) as u16;
+            // We need to get the tile identity number. Based on region of data though, it might be signed or unsigned
+            let tile_address: u16 = memory + tile_row + tile_col;
+            let tile_num = mmu.read_memory(&(tile_address as usize));
+
+            // Deduce which of the 384 tiles in VRAM that is. With signed tile
+            // numbers 0 is the tile at 0x9000, 256 tiles in
+            let tile = if unsigned {
+                tile_num as usize
+            } else {
+                (256 + tile_num as i8 as i16) as usize
+            };
+
+            // Find the correct vertical line we're on of the tile, and the pixel
+            // along it. The colour numbers come ready decoded (see tiles.rs)
+            let color_num = mmu.get_tile_cache().get_row(tile, (y_pos % 8) as usize)[(x_pos % 8) as usize];
+
+            // Get the shade from the colour palette in memory 0xFF47, then the RGB
+            // value for that shade from the background palette. A hidden layer is
+            // blank, though sprites still see its colour number
+            let shade = if self.layers_enabled[layer as usize] {
+                self.get_color(mmu, &color_num, &utils::COLOR_PALLETTE_ADDR)
+            } else {
+                0
+            };
+            let [red, green, blue] = self.palettes[BG_PALETTE][shade as usize];
+
+            let finaly = self.line;
+
+            // safety check to make sure what im about
+            // to set is int the 160x144 bounds
+            if finaly > 143 || pixel > 159 {
+                continue;
+            }
+
+            // Rows of 160 RGBA pixels, alpha is always 0xFF
+            self.line_colors[pixel as usize] = color_num;
+            let index = (finaly as usize * 160 + pixel as usize) * 4;
+            self.screen_data[index] = red;
+            self.screen_data[index + 1] = green;
+            self.screen_data[index + 2] = blue;
+        }
+
+        if using_window {
+            self.stats.add_window_line();
+            self.window_line = self.window_line.wrapping_add(1);
+        }
+    }
+
+    fn render_sprites(&mut self, mmu: &mmu::Mmu, lcd_control: &u8) {
+        // Only lines that are on screen have sprites drawn on them
+        let current_scanline = self.line;
+        if current_scanline > 143 {
+            return;
+        }
+
+        // Where sprites overlap, the one with the lower X coordinate is on top, and
+        // the lower OAM index if they're level. They're drawn in that order, so
+        // whichever covers a pixel first keeps it and anything drawn later
+        // underneath it is left out
+        let mut sprites = self.fetch_line_sprites(mmu, lcd_control);
+        sprites.sort_by_key(|sprite| (sprite.x, sprite.index));
+
+        // Hidden sprites are still fetched, so they show up as drawn for debugging
+        if !self.layers_enabled[Layer::Sprites as usize] {
+            return;
+        }
+
+        let mut covered = [false; 160];
+        for sprite in sprites.iter() {
+            let pallette = mmu.read_memory(&sprite.pallette_addr);
+
+            for (i, color_num) in sprite.pixels.iter().enumerate() {
+                // The stored X is 8 more than the screen position, sprites partly
+                // off the left edge wrap around to well past 159
+                let pixel = sprite.x.wrapping_sub(8).wrapping_add(i as u8) as usize;
+
+                // colour 0 is transparent for sprites
+                if pixel > 159 || *color_num == 0 || covered[pixel] {
+                    continue;
+                }
+                covered[pixel] = true;
+
+                // Behind the background, the sprite only shows over background
+                // colour 0. It still hides any sprites under it either way
+                if sprite.behind_background && self.line_colors[pixel] != 0 {
+                    continue;
+                }
+
+                // Get the shade from whichever sprite palette attribute bit 4 picked
+                // (0xFF48 or 0xFF49), then the RGB value for that shade
+                let shade = (pallette >> (color_num * 2)) & 3;
+                let [red, green, blue] = self.palettes[sprite.palette][shade as usize];
+
+                let index = (current_scanline as usize * 160 + pixel) * 4;
+                self.screen_data[index] = red;
+                self.screen_data[index + 1] = green;
+                self.screen_data[index + 2] = blue;
+            }
+        }
+    }
+
+    fn fetch_line_sprites(&mut self, mmu: &mmu::Mmu, lcd_control: &u8) -> Vec<LineSprite> {
+        // Sprite data is located at 0x8000-0x8FFF
+        // Sprite attributes are located at 0xFE00-0xFE9F and in this region
+        // each sprite has 4 bytes of attributes. These are what are in each byte
+        // of sprite attributes
+        // 0: Sprite Y Position: Position of the sprite on the Y axis of the
+        //    viewing display minus 16
+        // 1: Sprite X Position: Position of the sprite on the X axis of the
+        //    viewing display minus 8
+        // 2: Pattern number: This is the sprite identifier used for looking up
+        //    the sprite data in memory region 0x8000-0x8FFF
+        // 3: Attributes: These are the attributes of the sprite
+        //
+        // Goes through the sprites the OAM scan found on this line (see scan_oam)
+        // and picks out the row of each one's tile that's on the line, ready to be
+        // drawn
+
+        // The size of the sprite is determined by bit 2 of LCD control
+        // 4 == 0b00000100
+        let sprite_height: u8 = if lcd_control & 4 > 0 { 16 } else { 8 };
+        let current_scanline = self.line;
+
+        let mut sprites = Vec::with_capacity(self.line_sprites.len());
+        for i in 0..self.line_sprites.len() {
+            let sprite = self.line_sprites[i];
+
+            // get Index offset of sprite attributes. Remember there are 4 bytes
+            // of attributes per sprite
+            let index = utils::SPRITE_ATTRIBUTE_ADDR + sprite as usize * 4;
+
+            let y_pos = mmu.read_memory(&index) as i16 - 16;
+            let x = mmu.read_memory(&(index + 1));
+            let mut tile_location = mmu.read_memory(&(index + 2));
+            let attributes = mmu.read_memory(&(index + 3));
+
+            // The following are what the bits represent in the attributes
+            // Bit7: Sprite to Background Priority
+            // Bit6: Y flip
+            // Bit5: X flip
+            // Bit4: Palette number. 0 then it gets it palette from 0xFF48 otherwise 0xFF49
+            // Bit3: Not used in standard gameboy
+            // Bit2-0: Not used in standard gameboy
+            let y_flip = attributes & 64 > 0;
+            let x_flip = attributes & 32 > 0;
+
+            // 16 = 0b00010000
+            let (palette, pallette_addr) = if attributes & 16 > 0 {
+                (OBJ_1_PALETTE, utils::SPRITE_PALLETTE_1_ADDR)
+            } else {
+                (OBJ_0_PALETTE, utils::SPRITE_PALLETTE_0_ADDR)
+            };
+
+            // 8x16 sprites are an even tile and the odd one after it, whatever
+            // bit 0 of the pattern number is
+            if sprite_height == 16 {
+                tile_location &= 0xFE;
+            }
+
+            self.timeline.add_sprite(current_scanline, sprite);
+            self.frame_sprites |= 1 << sprite;
+
+            // The OAM scan picked the sprite with the height set back then. If it's
+            // been changed since, only the rows the current height has are left
+            let mut line = (current_scanline as i16 - y_pos) as u8 & (sprite_height - 1);
+
+            // If we are flipping the sprite vertically (y_flip) read the sprite in backwards
+            if y_flip {
+                line = sprite_height - 1 - line;
+            }
+
+            // The bottom half of an 8x16 sprite is the second tile. The colour
+            // numbers come ready decoded (see tiles.rs)
+            let tile = tile_location as usize + line as usize / 8;
+            let mut pixels = [0; 8];
+            pixels.copy_from_slice(mmu.get_tile_cache().get_row(tile, line as usize % 8));
+
+            // Read the sprite backwards for the x axis
+            if x_flip {
+                pixels.reverse();
+            }
+
+            sprites.push(LineSprite {
+                index: sprite,
+                x,
+                behind_background: attributes & 128 > 0,
+                palette,
+                pallette_addr,
+                pixels,
+            });
+        }
+
+        sprites
+    }
+
+    fn scan_oam(&mut self, mmu: &mmu::Mmu, line: u8) {
+        // Mode 2 goes through OAM in order and picks out the first 10 sprites that
+        // are on the line, only those can be drawn on it. Sprites off the left or
+        // right of the screen still count towards the 10
+        let lcd_control = mmu.read_memory(&utils::LCD_CONTROL_ADDR);
+        let sprite_height = if lcd_control & 4 > 0 { 16 } else { 8 };
+
+        // The ones past the 10 are only counted, for the stats
+        self.line_sprites.clear();
+        let mut dropped = 0;
+        for sprite in 0..40 {
+            let y_pos = mmu.read_memory(&(utils::SPRITE_ATTRIBUTE_ADDR + sprite * 4)) as i16 - 16;
+            if (line as i16) >= y_pos && (line as i16) < y_pos + sprite_height {
+                if self.line_sprites.len() < MAX_SPRITES_PER_LINE {
+                    self.line_sprites.push(sprite as u8);
+                } else {
+                    dropped += 1;
+                }
+            }
+        }
+        self.stats.set_line_sprites(line, self.line_sprites.len(), dropped);
+    }
+
+    fn get_color(&self, mmu: &mmu::Mmu, color_num: &u8, pallette_addr: &usize) -> u8 {
+        // Returns the shade for the color, from 0 (lightest) to 3 (darkest)
+        let pallette = mmu.read_memory(pallette_addr);
+
+        let mut hi = 0;
+        let mut lo = 0;
+
+        if *color_num == 0 {
+            hi = 1;
+            lo = 0;
+        } else if *color_num == 1 {
+            hi = 3;
+            lo = 2;
+        } else if *color_num == 2 {
+            hi = 5;
+            lo = 4;
+        } else if *color_num == 3 {
+            hi = 7;
+            lo = 6;
+        }
+
+        // Using the pallette, fetch the colour
+        let mut color: u8;
+        color = ((pallette >> hi) & 1) << 1;
+        color |= (pallette >> lo) & 1;
+
+        color
+    }
+
+    fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupts.push(interrupt);
+    }
+}
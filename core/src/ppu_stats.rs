@@ -0,0 +1,84 @@
+use serde::Serialize;
+
+// PPU statistics
+//
+// Counters for a whole frame, for working out why a game flickers or loses
+// sprites without going through the timeline (see timeline.rs) line by line. They
+// cost next to nothing so they're always kept. Once a frame is finished its counts
+// become the "last frame" that gets handed out, while the next one is counted.
+
+// frame: Frame number (see Ppu's frame_number) the counts are for
+// sprites_per_line: Sprites the OAM scan selected for each visible line, 10 at most
+// sprites_dropped_per_line: Sprites on each visible line past the first 10, which
+//                           the hardware never draws
+// window_lines: Lines the window was drawn on, none for frames skipped by frame
+//               skip as nothing is drawn
+// mode_dots: Dots spent in each mode (0 - 3) over the frame
+// stat_interrupts: STAT (LCD) interrupts requested over the frame
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FrameStats {
+    pub frame: u32,
+    pub sprites_per_line: Vec<u8>,
+    pub sprites_dropped_per_line: Vec<u8>,
+    pub window_lines: u32,
+    pub mode_dots: [u32; 4],
+    pub stat_interrupts: u32,
+}
+
+impl Default for FrameStats {
+    fn default() -> FrameStats {
+        FrameStats {
+            frame: 0,
+            sprites_per_line: vec![0; 144],
+            sprites_dropped_per_line: vec![0; 144],
+            window_lines: 0,
+            mode_dots: [0; 4],
+            stat_interrupts: 0,
+        }
+    }
+}
+
+pub struct PpuStats {
+    current: FrameStats,
+    last_frame: FrameStats,
+}
+
+impl PpuStats {
+    pub fn new() -> PpuStats {
+        PpuStats {
+            current: FrameStats::default(),
+            last_frame: FrameStats::default(),
+        }
+    }
+
+    pub fn set_line_sprites(&mut self, line: u8, selected: usize, dropped: usize) {
+        if let Some(count) = self.current.sprites_per_line.get_mut(line as usize) {
+            *count = selected as u8;
+            self.current.sprites_dropped_per_line[line as usize] = dropped as u8;
+        }
+    }
+
+    pub fn add_window_line(&mut self) {
+        self.current.window_lines += 1;
+    }
+
+    pub fn add_mode_dots(&mut self, mode: u8, dots: usize) {
+        self.current.mode_dots[(mode & 0x3) as usize] += dots as u32;
+    }
+
+    pub fn add_stat_interrupt(&mut self) {
+        self.current.stat_interrupts += 1;
+    }
+
+    // Called when the PPU wraps back around to line 0, frame is the number of the
+    // frame just finished
+    pub fn end_frame(&mut self, frame: u32) {
+        let mut finished = std::mem::take(&mut self.current);
+        finished.frame = frame;
+        self.last_frame = finished;
+    }
+
+    pub fn get_last_frame(&self) -> &FrameStats {
+        &self.last_frame
+    }
+}
@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+
+use super::mmm01;
+use super::tama5;
+
+// Savestates
+//
+// A snapshot of everything needed to put the emulator back exactly where it was.
+// States are stored as JSON so they are easy to inspect and diff, which is also
+// what the diff API below is built on.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CpuState {
+    pub af: u16,
+    pub bc: u16,
+    pub de: u16,
+    pub hl: u16,
+    pub sp: u16,
+    pub pc: u16,
+    pub interrupt_master: bool,
+    #[serde(default)]
+    pub interrupt_master_pending: bool,
+    pub halted: bool,
+    #[serde(default)]
+    pub halt_bug: bool,
+    #[serde(default)]
+    pub stopped: bool,
+    #[serde(default)]
+    pub locked: bool,
+    pub scanline_counter: u16,
+    // The PPU's line, older states only have LY
+    #[serde(default)]
+    pub line: Option<u8>,
+    #[serde(default)]
+    pub mode_3_end: Option<u16>,
+    #[serde(default)]
+    pub window_line: u8,
+    #[serde(default)]
+    pub window_y_triggered: bool,
+    #[serde(default)]
+    pub stat_line: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MmuState {
+    // Full 64KB address space as stored (0000-7FFF is whatever was last loaded
+    // from the cartridge)
+    pub memory: Vec<u8>,
+    pub ram_banks: Vec<u8>,
+    pub current_rom_bank: u8,
+    pub current_ram_bank: u8,
+    pub enable_ram: bool,
+    pub rom_banking: bool,
+
+    // The timer's internal 16 bit counter, DIV is the upper 8 bits of it
+    pub timer_counter: usize,
+    #[serde(default)]
+    pub timer_reload_delay: u8,
+
+    // Whether the boot ROM was still mapped in. The boot ROM itself isn't saved
+    #[serde(default)]
+    pub boot_rom_mapped: bool,
+
+    // An OAM DMA that was partway through, dma_cycles_left is 0 if there wasn't one
+    #[serde(default)]
+    pub dma_source: u16,
+    #[serde(default)]
+    pub dma_cycles_left: u16,
+
+    // The registers of mappers that keep their own, only there for carts with
+    // one of them. The TAMA5's includes its EEPROM and RTC
+    #[serde(default)]
+    pub mmm01: Option<mmm01::Mmm01>,
+    #[serde(default)]
+    pub tama5: Option<tama5::Tama5>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SaveState {
+    pub cpu: CpuState,
+    pub mmu: MmuState,
+}
+
+impl SaveState {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<SaveState, String> {
+        serde_json::from_slice(bytes).map_err(|e| format!("Invalid savestate - {}", e))
+    }
+}
+
+// A register or flag that differs between two states
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ValueChange {
+    pub name: String,
+    pub before: u32,
+    pub after: u32,
+}
+
+// A single IO register (FF00-FF7F, FFFF) that differs
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct IoChange {
+    pub address: u16,
+    pub before: u8,
+    pub after: u8,
+}
+
+// A run of memory with differences in it. Nearby changes are merged into one
+// range so a diff of a busy frame doesn't list thousands of single bytes
+// region: What part of memory this is (wram, vram, oam, etc.)
+// start/end: Inclusive address range, relative to the start of the external RAM
+//            banks for the external_ram region
+// changed_bytes: How many bytes in the range actually differ
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RangeChange {
+    pub region: String,
+    pub start: u32,
+    pub end: u32,
+    pub changed_bytes: u32,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct StateDiff {
+    pub registers: Vec<ValueChange>,
+    pub io_registers: Vec<IoChange>,
+    pub memory: Vec<RangeChange>,
+}
+
+// Changes closer together than this are reported as one range
+const MERGE_DISTANCE: usize = 8;
+
+pub fn diff(before: &SaveState, after: &SaveState) -> StateDiff {
+    let mut registers = Vec::new();
+    let mut add_change = |name: &str, before: u32, after: u32| {
+        if before != after {
+            registers.push(ValueChange {
+                name: String::from(name),
+                before,
+                after,
+            });
+        }
+    };
+
+    let (a, b) = (&before.cpu, &after.cpu);
+    add_change("af", a.af as u32, b.af as u32);
+    add_change("bc", a.bc as u32, b.bc as u32);
+    add_change("de", a.de as u32, b.de as u32);
+    add_change("hl", a.hl as u32, b.hl as u32);
+    add_change("sp", a.sp as u32, b.sp as u32);
+    add_change("pc", a.pc as u32, b.pc as u32);
+    add_change("ime", a.interrupt_master as u32, b.interrupt_master as u32);
+    add_change("ime_pending", a.interrupt_master_pending as u32, b.interrupt_master_pending as u32);
+    add_change("halted", a.halted as u32, b.halted as u32);
+    add_change("halt_bug", a.halt_bug as u32, b.halt_bug as u32);
+    add_change("stopped", a.stopped as u32, b.stopped as u32);
+    add_change("locked", a.locked as u32, b.locked as u32);
+    add_change("scanline_counter", a.scanline_counter as u32, b.scanline_counter as u32);
+    add_change("line", a.line.unwrap_or(0) as u32, b.line.unwrap_or(0) as u32);
+    add_change("window_line", a.window_line as u32, b.window_line as u32);
+    add_change("window_y_triggered", a.window_y_triggered as u32, b.window_y_triggered as u32);
+    add_change("stat_line", a.stat_line as u32, b.stat_line as u32);
+
+    let (a, b) = (&before.mmu, &after.mmu);
+    add_change("rom_bank", a.current_rom_bank as u32, b.current_rom_bank as u32);
+    add_change("ram_bank", a.current_ram_bank as u32, b.current_ram_bank as u32);
+    add_change("ram_enabled", a.enable_ram as u32, b.enable_ram as u32);
+    add_change("rom_banking", a.rom_banking as u32, b.rom_banking as u32);
+    add_change("timer_counter", a.timer_counter as u32, b.timer_counter as u32);
+
+    let mut io_registers = Vec::new();
+    for address in (0xFF00..0xFF80).chain(0xFFFF..=0xFFFF) {
+        if a.memory[address] != b.memory[address] {
+            io_registers.push(IoChange {
+                address: address as u16,
+                before: a.memory[address],
+                after: b.memory[address],
+            });
+        }
+    }
+
+    // Everything else in the address space, split up by region
+    let regions: [(&str, usize, usize); 8] = [
+        ("rom", 0x0000, 0x7FFF),
+        ("vram", 0x8000, 0x9FFF),
+        ("external_ram_window", 0xA000, 0xBFFF),
+        ("wram", 0xC000, 0xDFFF),
+        ("echo", 0xE000, 0xFDFF),
+        ("oam", 0xFE00, 0xFE9F),
+        ("unusable", 0xFEA0, 0xFEFF),
+        ("hram", 0xFF80, 0xFFFE),
+    ];
+
+    let mut memory = Vec::new();
+    for (region, start, end) in regions.iter() {
+        diff_range(&a.memory, &b.memory, *start, *end, region, &mut memory);
+    }
+
+    let ram_end = a.ram_banks.len().min(b.ram_banks.len());
+    if ram_end > 0 {
+        diff_range(&a.ram_banks, &b.ram_banks, 0, ram_end - 1, "external_ram", &mut memory);
+    }
+
+    StateDiff {
+        registers,
+        io_registers,
+        memory,
+    }
+}
+
+fn diff_range(before: &[u8], after: &[u8], start: usize, end: usize, region: &str, changes: &mut Vec<RangeChange>) {
+    let mut current: Option<RangeChange> = None;
+
+    for address in start..=end {
+        if before[address] == after[address] {
+            continue;
+        }
+
+        match current.as_mut() {
+            // Close enough to the last change to be part of the same range
+            Some(range) if address - range.end as usize <= MERGE_DISTANCE => {
+                range.end = address as u32;
+                range.changed_bytes += 1;
+            }
+
+            _ => {
+                if let Some(range) = current.take() {
+                    changes.push(range);
+                }
+
+                current = Some(RangeChange {
+                    region: String::from(region),
+                    start: address as u32,
+                    end: address as u32,
+                    changed_bytes: 1,
+                });
+            }
+        }
+    }
+
+    if let Some(range) = current {
+        changes.push(range);
+    }
+}
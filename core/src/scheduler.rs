@@ -0,0 +1,56 @@
+// Scheduler
+//
+// Rather than moving the timers and PPU on after every machine cycle, the cycles
+// that pass are banked here and only handed over once something is due to happen -
+// an LCD mode or scanline boundary, TIMA overflowing or an OAM DMA finishing. Until
+// then nothing they do can be noticed by the CPU short of reading their registers,
+// so accesses to those catch everything up first (see Cpu::read_cycle). While a DMA
+// is running every access catches up, as it decides what the CPU can reach.
+
+pub struct Scheduler {
+    // Cycles that have passed but haven't been given to the hardware yet
+    pending: usize,
+
+    // Cycles from the last catch up until the next event is due
+    next_event: usize,
+}
+
+impl Scheduler {
+    pub fn new() -> Scheduler {
+        Scheduler {
+            pending: 0,
+            next_event: 0,
+        }
+    }
+
+    pub fn add_cycles(&mut self, cycles: usize) -> bool {
+        // Banks some cycles, returns true once the next event is due
+        self.pending += cycles;
+        self.pending >= self.next_event
+    }
+
+    pub fn take_pending(&mut self) -> usize {
+        std::mem::take(&mut self.pending)
+    }
+
+    pub fn get_cycles_until_event(&self) -> usize {
+        self.next_event.saturating_sub(self.pending)
+    }
+
+    pub fn schedule(&mut self, cycles: usize) {
+        // How long until the next event, counted from now (after a catch up)
+        self.next_event = cycles;
+    }
+}
+
+pub fn is_hardware_address(address: &usize) -> bool {
+    // Addresses whose reads or writes depend on where the hardware is up to. That's
+    // the IO registers, IE and cartridge RAM (which some mappers put an RTC behind)
+    matches!(*address, 0xA000..=0xBFFF | 0xFF00..=0xFF7F | 0xFFFF)
+}
+
+pub fn is_video_address(address: &usize) -> bool {
+    // VRAM and OAM. Whether the CPU can get at them depends on the PPU's mode (see
+    // Mmu::is_locked_by_ppu), though accessing them doesn't move any events
+    matches!(*address, 0x8000..=0x9FFF | 0xFE00..=0xFE9F)
+}
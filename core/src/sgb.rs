@@ -0,0 +1,123 @@
+// Super Gameboy multiplayer support
+//
+// The SGB receives commands from the game as packets pulsed through the joypad
+// register (P14/P15, bits 4 and 5 of 0xFF00):
+// P14 = 0, P15 = 0: Reset pulse, starts a new packet
+// P14 = 0, P15 = 1: A 0 bit
+// P14 = 1, P15 = 0: A 1 bit
+// P14 = 1, P15 = 1: Between bits
+//
+// A packet is 16 bytes sent least significant bit first, followed by a 0 stop bit.
+// The first byte is (command * 8) + packet count. The only command we care about
+// here is MLT_REQ (0x11) which turns on the 4-player adapter. Byte 1 selects the
+// number of players:
+// 0: 1 Player
+// 1: 2 Players
+// 3: 4 Players
+//
+// With multiplayer on, reading 0xFF00 with both P14 and P15 high returns the ID of
+// the current controller in the lower nibble (0xF = player 1, 0xE = player 2, etc.)
+// and the next controller is selected every time P15 goes from low to high.
+
+const PACKET_BITS: usize = 16 * 8;
+const MLT_REQ: u8 = 0x11;
+
+pub struct Sgb {
+    enabled: bool,
+
+    packet: [u8; 16],
+    bit_index: usize,
+    receiving: bool,
+    previous_select: u8,
+
+    players: u8,
+    current_player: u8,
+}
+
+impl Sgb {
+    pub fn new() -> Sgb {
+        Sgb {
+            enabled: false,
+            packet: [0; 16],
+            bit_index: 0,
+            receiving: false,
+            previous_select: 0x30,
+            players: 1,
+            current_player: 0,
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.receiving = false;
+        self.players = 1;
+        self.current_player = 0;
+    }
+
+    pub fn get_current_player(&self) -> u8 {
+        self.current_player
+    }
+
+    pub fn is_multiplayer(&self) -> bool {
+        self.enabled && self.players > 1
+    }
+
+    // Controller ID returned in the lower nibble while no keys are selected
+    pub fn get_controller_id(&self) -> u8 {
+        0xF - self.current_player
+    }
+
+    pub fn write_joypad(&mut self, data: u8) {
+        if !self.enabled {
+            return;
+        }
+
+        // 48 = 0b00110000 - P14 and P15
+        let select = data & 48;
+
+        // Rising edge of P15 moves onto the next controller
+        if self.is_multiplayer() && self.previous_select & 32 == 0 && select & 32 > 0 {
+            self.current_player = (self.current_player + 1) % self.players;
+        }
+
+        match select {
+            // Reset pulse, start a new packet
+            0x00 => {
+                self.packet = [0; 16];
+                self.bit_index = 0;
+                self.receiving = true;
+            }
+
+            // Bits are only clocked in when coming from the idle (both high) state
+            0x10 | 0x20 if self.receiving && self.previous_select == 0x30 => {
+                let bit = if select == 0x10 { 1 } else { 0 };
+
+                if self.bit_index < PACKET_BITS {
+                    self.packet[self.bit_index / 8] |= bit << (self.bit_index % 8);
+                    self.bit_index += 1;
+                } else {
+                    // This is the stop bit, the packet is complete
+                    self.receiving = false;
+                    self.do_command();
+                }
+            }
+
+            _ => {}
+        }
+
+        self.previous_select = select;
+    }
+
+    fn do_command(&mut self) {
+        let command = self.packet[0] >> 3;
+
+        if command == MLT_REQ {
+            self.players = match self.packet[1] & 0x3 {
+                1 => 2,
+                3 => 4,
+                _ => 1,
+            };
+            self.current_player = 0;
+        }
+    }
+}
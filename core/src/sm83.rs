@@ -0,0 +1,123 @@
+use super::bus::{Bus, FlatMemory};
+use super::cpu::{Cpu, Register};
+use super::game;
+
+use serde::Deserialize;
+
+// Runner for the single instruction SM83 test vectors (SingleStepTests/sm83)
+//
+// Each file covers one opcode with a list of tests. A test gives the registers and
+// the RAM it touches before and after running one instruction, and what was on the
+// bus for each machine cycle. The CPU runs against flat memory (see bus.rs) so
+// nothing but the listed RAM is involved. Only the number of machine cycles is
+// checked, not what was accessed in each of them.
+//
+// Only built with the sm83-tests feature, which the tests turn on for themselves.
+
+#[derive(Deserialize)]
+struct TestVector {
+    name: String,
+    initial: TestState,
+    #[serde(rename = "final")]
+    expected: TestState,
+    cycles: Vec<serde_json::Value>,
+}
+
+// ie is only in some versions of the vectors, it's just the byte at 0xFFFF
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+struct TestState {
+    pc: u16,
+    sp: u16,
+    a: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+    e: u8,
+    f: u8,
+    h: u8,
+    l: u8,
+    ime: u8,
+    #[serde(default)]
+    ie: Option<u8>,
+    ram: Vec<(u16, u8)>,
+}
+
+pub fn run_sm83_tests(json: &str) -> Result<Vec<String>, String> {
+    // Runs every test in a file of vectors, returns a description of each one
+    // that failed
+    let tests: Vec<TestVector> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    Ok(tests.iter().filter_map(run_test).collect())
+}
+
+fn run_test(test: &TestVector) -> Option<String> {
+    let mut cpu = Cpu::new(game::Game::from_bytes(&[]));
+    let mut memory = FlatMemory::new();
+    set_state(&mut cpu, &mut memory, &test.initial);
+    cpu.set_bus(Box::new(memory));
+
+    let cycles = cpu.execute_op();
+
+    let mut memory = match cpu.take_bus() {
+        Some(memory) => memory,
+        None         => return Some(format!("{}: memory went missing", test.name)),
+    };
+    let state = get_state(&cpu, memory.as_mut(), &test.expected);
+    if state != test.expected {
+        return Some(format!("{}: expected {:?}, got {:?}", test.name, test.expected, state));
+    }
+
+    if cycles != test.cycles.len() * 4 {
+        return Some(format!("{}: expected {} cycles, took {}", test.name, test.cycles.len() * 4, cycles));
+    }
+
+    None
+}
+
+fn set_state(cpu: &mut Cpu, memory: &mut FlatMemory, state: &TestState) {
+    cpu.af = Register::new(get_pair(state.a, state.f));
+    cpu.bc = Register::new(get_pair(state.b, state.c));
+    cpu.de = Register::new(get_pair(state.d, state.e));
+    cpu.hl = Register::new(get_pair(state.h, state.l));
+    cpu.stack_pointer = Register::new(state.sp);
+    cpu.program_counter = state.pc;
+
+    if state.ime > 0 {
+        cpu.restore_interrupts();
+    } else {
+        cpu.disable_interrupts();
+    }
+
+    if let Some(ie) = state.ie {
+        memory.write8(&0xFFFF, ie);
+    }
+
+    for (address, value) in state.ram.iter() {
+        memory.write8(&(*address as usize), *value);
+    }
+}
+
+fn get_state(cpu: &Cpu, memory: &mut dyn Bus, expected: &TestState) -> TestState {
+    // Reads back the same RAM addresses as the expected state lists
+    TestState {
+        pc: cpu.program_counter,
+        sp: cpu.stack_pointer.value(),
+        a: cpu.af.hi(),
+        b: cpu.bc.hi(),
+        c: cpu.bc.lo(),
+        d: cpu.de.hi(),
+        e: cpu.de.lo(),
+        f: cpu.af.lo(),
+        h: cpu.hl.hi(),
+        l: cpu.hl.lo(),
+        ime: cpu.get_registers().ime as u8,
+        ie: expected.ie.map(|_| memory.read8(&0xFFFF)),
+        ram: expected.ram
+            .iter()
+            .map(|(address, _)| (*address, memory.read8(&(*address as usize))))
+            .collect(),
+    }
+}
+
+fn get_pair(hi: u8, lo: u8) -> u16 {
+    ((hi as u16) << 8) | lo as u16
+}
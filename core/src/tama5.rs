@@ -0,0 +1,165 @@
+// Bandai TAMA5 mapper
+//
+// Used by Game de Hakken!! Tamagotchi Osutchi to Mesutchi. Instead of writing to
+// the ROM area, everything goes through two registers in the RAM area:
+// A000: Write a 4 bit value into the selected register, or read one back
+// A001: Select which register A000 refers to
+//
+// Registers:
+// 0x0: ROM bank bits 0-3
+// 0x1: ROM bank bit 4
+// 0x4: Data to write, bits 0-3
+// 0x5: Data to write, bits 4-7
+// 0x6: Bit 0 is address bit 4, bits 1-3 are the command
+// 0x7: Address bits 0-3, writing this carries out the command
+// 0xA: Reads back 0x1 once the mapper is ready (games poll for this first)
+// 0xC: Data read, bits 0-3
+// 0xD: Data read, bits 4-7
+//
+// Commands:
+// 0: Write the data registers to memory at the address
+// 1: Read memory at the address into the read registers
+// 2: Write the low data nibble to RTC register (address & 0xF)
+// 3: Read RTC register (address & 0xF) into the low read register
+//
+// Memory is 32 bytes of EEPROM-like storage (the save). The RTC is a TC8521,
+// simplified here to the BCD time registers below, ticked by emulated time.
+
+use serde::{Deserialize, Serialize};
+
+const CPU_CLOCK: usize = 4194304;
+
+// RTC register indexes (one BCD digit each)
+const RTC_SECONDS_LOW: usize = 0x0;
+const RTC_SECONDS_HIGH: usize = 0x1;
+const RTC_MINUTES_LOW: usize = 0x2;
+const RTC_MINUTES_HIGH: usize = 0x3;
+const RTC_HOURS_LOW: usize = 0x4;
+const RTC_HOURS_HIGH: usize = 0x5;
+const RTC_DAY_OF_WEEK: usize = 0x6;
+const RTC_DAYS_LOW: usize = 0x7;
+const RTC_DAYS_HIGH: usize = 0x8;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Tama5 {
+    registers: [u8; 16],
+    selected_register: usize,
+    ram: [u8; 32],
+    rtc: [u8; 16],
+    rtc_cycles: usize,
+}
+
+impl Tama5 {
+    pub fn new() -> Tama5 {
+        Tama5 {
+            registers: [0; 16],
+            selected_register: 0,
+            ram: [0; 32],
+            rtc: [0; 16],
+            rtc_cycles: 0,
+        }
+    }
+
+    pub fn get_rom_bank(&self) -> u8 {
+        ((self.registers[0x1] & 1) << 4) | (self.registers[0x0] & 0xF)
+    }
+
+    pub fn get_ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn get_rtc(&self) -> &[u8] {
+        &self.rtc
+    }
+
+    pub fn read(&self, address: usize) -> u8 {
+        // Only A000 is readable, the upper nibble is open bus
+        if address & 1 == 1 {
+            return 0xFF;
+        }
+
+        match self.selected_register {
+            0xA => 0xF1,
+            0xC => 0xF0 | self.registers[0xC],
+            0xD => 0xF0 | self.registers[0xD],
+            _   => 0xFF
+        }
+    }
+
+    pub fn write(&mut self, address: usize, data: u8) {
+        if address & 1 == 1 {
+            self.selected_register = (data & 0xF) as usize;
+            return;
+        }
+
+        let value = data & 0xF;
+        self.registers[self.selected_register] = value;
+
+        // Writing the low address nibble kicks off whatever command was set up
+        if self.selected_register == 0x7 {
+            self.do_command();
+        }
+    }
+
+    pub fn tick(&mut self, cycles: usize) {
+        self.rtc_cycles += cycles;
+
+        while self.rtc_cycles >= CPU_CLOCK {
+            self.rtc_cycles -= CPU_CLOCK;
+            self.do_tick_second();
+        }
+    }
+
+    fn do_command(&mut self) {
+        let command = self.registers[0x6] >> 1;
+        let address = (((self.registers[0x6] & 1) << 4) | self.registers[0x7]) as usize;
+        let data = (self.registers[0x5] << 4) | self.registers[0x4];
+
+        match command {
+            0 => self.ram[address] = data,
+            1 => {
+                let value = self.ram[address];
+                self.registers[0xC] = value & 0xF;
+                self.registers[0xD] = value >> 4;
+            }
+            2 => self.rtc[address & 0xF] = self.registers[0x4],
+            3 => {
+                self.registers[0xC] = self.rtc[address & 0xF];
+                self.registers[0xD] = 0;
+            }
+            _ => log!("Unknown TAMA5 command {}", command)
+        }
+    }
+
+    fn do_tick_second(&mut self) {
+        // Each register is a single BCD digit, carry up through seconds, minutes,
+        // hours and days
+        if !self.do_increment_digits(RTC_SECONDS_LOW, RTC_SECONDS_HIGH, 60) {
+            return;
+        }
+
+        if !self.do_increment_digits(RTC_MINUTES_LOW, RTC_MINUTES_HIGH, 60) {
+            return;
+        }
+
+        if !self.do_increment_digits(RTC_HOURS_LOW, RTC_HOURS_HIGH, 24) {
+            return;
+        }
+
+        self.rtc[RTC_DAY_OF_WEEK] = (self.rtc[RTC_DAY_OF_WEEK] + 1) % 7;
+        self.do_increment_digits(RTC_DAYS_LOW, RTC_DAYS_HIGH, 100);
+    }
+
+    fn do_increment_digits(&mut self, low: usize, high: usize, limit: u8) -> bool {
+        // Returns true if the value wrapped around and the next unit needs to go up
+        let mut value = (self.rtc[high] % 10) * 10 + (self.rtc[low] % 10) + 1;
+        let wrapped = value >= limit;
+        if wrapped {
+            value = 0;
+        }
+
+        self.rtc[low] = value % 10;
+        self.rtc[high] = value / 10;
+        wrapped
+    }
+}
@@ -0,0 +1,175 @@
+use super::emulator::Emulator;
+use super::game;
+use super::pacing::CYCLES_PER_FRAME;
+
+// Headless runner for test ROMs (blargg, mooneye, etc.)
+//
+// Runs a ROM with nothing presented, watching for the ways the common test suites
+// report their result:
+// - blargg: Prints "Passed" or "Failed" over the serial port. Some of the newer
+//   ones instead write DE B0 61 to A001-A003 and the result code to A000 (0x80
+//   while still running, 0 when passed)
+// - mooneye: Loads the fibonacci numbers 3, 5, 8, 13, 21, 34 into B, C, D, E, H, L
+//   when passed, or 0x42 into all of them when failed, then runs LD B, B (which is
+//   otherwise a no-op) as a software breakpoint
+//
+// Some suites (mealybug-tearoom) only run LD B, B to say the screen is ready to be
+// compared against a reference image, run_to_breakpoint is for those.
+
+const MOONEYE_BREAKPOINT: u8 = 0x40;
+const MOONEYE_PASS: [u8; 6] = [3, 5, 8, 13, 21, 34];
+const MOONEYE_FAIL: [u8; 6] = [0x42; 6];
+
+const BLARGG_SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+const BLARGG_RUNNING: u8 = 0x80;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TestRomStatus {
+    // Nothing recognisable was seen before running out of frames
+    Unknown = 0,
+    Passed = 1,
+    Failed = 2,
+}
+
+// serial_output: Everything written to the serial port, as text
+// screen_hash: 64 bit FNV-1a hash of the final screen, in hex. Good for comparing
+//              against a known good run when a test only reports on screen
+// frames_run: How many frames ran before a result was found (or max_frames)
+#[derive(Clone, Debug)]
+pub struct TestRomResult {
+    pub status: TestRomStatus,
+    pub serial_output: String,
+    pub screen_hash: String,
+    pub frames_run: u32,
+}
+
+// Runs a test ROM headless for up to max_frames frames, stopping early once it
+// reports a pass or fail
+pub fn run_test_rom(rom: &[u8], max_frames: u32) -> TestRomResult {
+    let mut emulator = Emulator::new(game::Game::from_bytes(rom));
+    run(&mut emulator, max_frames)
+}
+
+// Runs until LD B, B whatever is in the registers, for up to max_frames frames.
+// Returns whether it got there. The emulator is left as it was at that point, so
+// the frame can be taken with get_frame
+pub fn run_to_breakpoint(emulator: &mut Emulator, max_frames: u32) -> bool {
+    let _log_scope = emulator.log_scope();
+
+    for _ in 0..max_frames {
+        let mut cycles_this_frame = 0;
+        while cycles_this_frame < CYCLES_PER_FRAME {
+            let pc = emulator.cpu.program_counter as usize;
+            let breakpoint = !emulator.cpu.is_halted() && emulator.cpu.mmu.peek_memory(&pc) == MOONEYE_BREAKPOINT;
+            cycles_this_frame += emulator.step();
+
+            if breakpoint {
+                emulator.cpu.sync();
+                return true;
+            }
+        }
+    }
+
+    emulator.cpu.sync();
+    false
+}
+
+fn run(emulator: &mut Emulator, max_frames: u32) -> TestRomResult {
+    let _log_scope = emulator.log_scope();
+    emulator.cpu.mmu.set_serial_capture(true);
+    let mut status = TestRomStatus::Unknown;
+    let mut frames_run = 0;
+
+    while frames_run < max_frames && status == TestRomStatus::Unknown {
+        let mut cycles_this_frame = 0;
+        while cycles_this_frame < CYCLES_PER_FRAME && status == TestRomStatus::Unknown {
+            let pc = emulator.cpu.program_counter as usize;
+            let breakpoint = !emulator.cpu.is_halted() && emulator.cpu.mmu.peek_memory(&pc) == MOONEYE_BREAKPOINT;
+            cycles_this_frame += emulator.step();
+
+            if breakpoint {
+                status = get_mooneye_status(emulator);
+            }
+        }
+        emulator.cpu.sync();
+
+        frames_run += 1;
+        if status == TestRomStatus::Unknown {
+            status = get_status(emulator);
+        }
+    }
+
+    let serial = emulator.cpu.mmu.get_serial_output();
+
+    TestRomResult {
+        status,
+        serial_output: String::from_utf8_lossy(serial).into_owned(),
+        screen_hash: format!("{:016x}", hash(emulator.cpu.ppu.get_screen_data())),
+        frames_run,
+    }
+}
+
+fn get_mooneye_status(emulator: &Emulator) -> TestRomStatus {
+    // Only a breakpoint with one of the register fingerprints is a result, other
+    // ROMs could be running LD B, B for real
+    match get_mooneye_registers(emulator) {
+        MOONEYE_PASS => TestRomStatus::Passed,
+        MOONEYE_FAIL => TestRomStatus::Failed,
+        _            => TestRomStatus::Unknown,
+    }
+}
+
+fn get_status(emulator: &Emulator) -> TestRomStatus {
+    // blargg, serial output
+    let serial = String::from_utf8_lossy(emulator.cpu.mmu.get_serial_output());
+    if serial.contains("Passed") {
+        return TestRomStatus::Passed;
+    }
+
+    if serial.contains("Failed") {
+        return TestRomStatus::Failed;
+    }
+
+    // blargg, result in cartridge RAM
+    let mmu = &emulator.cpu.mmu;
+    let signature = [
+        mmu.peek_memory(&0xA001),
+        mmu.peek_memory(&0xA002),
+        mmu.peek_memory(&0xA003),
+    ];
+
+    if signature == BLARGG_SIGNATURE {
+        match mmu.peek_memory(&0xA000) {
+            BLARGG_RUNNING => {},
+            0              => return TestRomStatus::Passed,
+            _              => return TestRomStatus::Failed,
+        }
+    }
+
+    TestRomStatus::Unknown
+}
+
+fn get_mooneye_registers(emulator: &Emulator) -> [u8; 6] {
+    let cpu = &emulator.cpu;
+
+    [
+        cpu.bc.hi(),
+        cpu.bc.lo(),
+        cpu.de.hi(),
+        cpu.de.lo(),
+        cpu.hl.hi(),
+        cpu.hl.lo(),
+    ]
+}
+
+// 64 bit FNV-1a
+fn hash(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    for byte in data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
@@ -0,0 +1,51 @@
+// Decoded tile cache
+//
+// Tiles are stored 2 bits per pixel, with each row of 8 pixels split over two
+// bytes (the low bit of every pixel's colour number in the first, the high bit in
+// the second). Pulling a colour number out of that takes a handful of shifts, and
+// drawing a line would otherwise do it for every pixel, every line, every frame.
+// Games hardly ever change tile data compared to how often it's drawn, so the
+// colour numbers of all 384 tiles (0x8000 - 0x97FF) are kept decoded here instead,
+// and the row a VRAM write lands in is decoded again as it happens.
+
+pub const TILE_COUNT: usize = 384;
+
+pub struct TileCache {
+    // Colour numbers (0 - 3), 64 per tile row by row
+    pixels: Vec<u8>,
+}
+
+impl TileCache {
+    pub fn new() -> TileCache {
+        TileCache {
+            pixels: vec![0; TILE_COUNT * 64],
+        }
+    }
+
+    pub fn update_row(&mut self, address: &usize, data_1: u8, data_2: u8) {
+        // address is either byte of the row, data_1 and data_2 are the row's low
+        // and high bytes as they now are
+        let offset = (*address - 0x8000) & !1;
+        let row = &mut self.pixels[offset * 4..offset * 4 + 8];
+
+        // Bit 7 is the leftmost pixel
+        for (i, pixel) in row.iter_mut().enumerate() {
+            let bit = 7 - i;
+            *pixel = (((data_2 >> bit) & 1) << 1) | ((data_1 >> bit) & 1);
+        }
+    }
+
+    pub fn rebuild(&mut self, vram: &[u8]) {
+        // Decodes everything again from the tile data, for when VRAM has been
+        // replaced wholesale (loading a savestate)
+        for offset in (0..TILE_COUNT * 16).step_by(2) {
+            self.update_row(&(0x8000 + offset), vram[offset], vram[offset + 1]);
+        }
+    }
+
+    pub fn get_row(&self, tile: usize, row: usize) -> &[u8] {
+        // The 8 colour numbers of one row of a tile, left to right
+        let index = (tile % TILE_COUNT) * 64 + (row % 8) * 8;
+        &self.pixels[index..index + 8]
+    }
+}
@@ -0,0 +1,98 @@
+use serde::Serialize;
+
+// PPU timeline
+//
+// Records what the PPU did on every scanline of a frame so a debug UI can draw a
+// timeline (like BGB's) for tracking down raster effects. Once a frame is finished
+// it becomes the "last frame" that gets handed out, while the next one is recorded.
+
+// 144 visible lines and 10 lines of V-Blank
+pub const SCANLINE_COUNT: usize = 154;
+
+// mode_dots: Dots spent in each mode (0 - 3) on this line
+// sprites: OAM index of every sprite selected for this line
+// window: Whether the window was drawn on this line
+// stat_interrupt: Whether a STAT (LCD) interrupt was requested during this line
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ScanlineTiming {
+    pub mode_dots: [u32; 4],
+    pub sprites: Vec<u8>,
+    pub window: bool,
+    pub stat_interrupt: bool,
+}
+
+pub struct PpuTimeline {
+    enabled: bool,
+    current: Vec<ScanlineTiming>,
+    last_frame: Vec<ScanlineTiming>,
+}
+
+impl PpuTimeline {
+    pub fn new() -> PpuTimeline {
+        PpuTimeline {
+            enabled: false,
+            current: vec![ScanlineTiming::default(); SCANLINE_COUNT],
+            last_frame: Vec::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.current = vec![ScanlineTiming::default(); SCANLINE_COUNT];
+        self.last_frame = Vec::new();
+    }
+
+    pub fn add_mode_dots(&mut self, line: u8, mode: u8, dots: usize) {
+        if let Some(timing) = self.get_line(line) {
+            timing.mode_dots[(mode & 0x3) as usize] += dots as u32;
+        }
+    }
+
+    pub fn add_sprite(&mut self, line: u8, sprite: u8) {
+        if let Some(timing) = self.get_line(line) {
+            timing.sprites.push(sprite);
+        }
+    }
+
+    pub fn set_window(&mut self, line: u8) {
+        if let Some(timing) = self.get_line(line) {
+            timing.window = true;
+        }
+    }
+
+    pub fn set_stat_interrupt(&mut self, line: u8) {
+        if let Some(timing) = self.get_line(line) {
+            timing.stat_interrupt = true;
+        }
+    }
+
+    // Called when the PPU wraps back around to line 0
+    pub fn end_frame(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        let finished = std::mem::replace(&mut self.current, vec![ScanlineTiming::default(); SCANLINE_COUNT]);
+        self.last_frame = finished;
+    }
+
+    pub fn get_last_frame(&self) -> &[ScanlineTiming] {
+        &self.last_frame
+    }
+
+    fn get_line(&mut self, line: u8) -> Option<&mut ScanlineTiming> {
+        if !self.enabled {
+            return None;
+        }
+
+        self.current.get_mut(line as usize)
+    }
+}
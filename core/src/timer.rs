@@ -0,0 +1,208 @@
+// Timer
+//
+// DIV (FF04), TIMA (FF05), TMA (FF06) and TAC (FF07). Internally there is a single
+// 16 bit counter that goes up every T-cycle, and DIV is just its upper 8 bits.
+// TIMA doesn't have a clock of its own, instead TAC picks one of the counter's
+// bits and TIMA goes up whenever that bit falls from 1 to 0 (while the timer is
+// enabled). When TIMA overflows it's reloaded from TMA and the timer interrupt is
+// requested.
+//
+// The reload doesn't happen straight away. For the machine cycle after the
+// overflow TIMA reads as 0, and writing TIMA then cancels both the reload and the
+// interrupt. In the machine cycle the reload happens in, writes to TIMA are lost
+// and writes to TMA go straight through to TIMA as well.
+//
+// As TIMA only sees the edge detector, anything that drops the signal from 1 to 0
+// counts as a falling edge. Resetting the counter through DIV while the selected
+// bit is set, or changing TAC so the signal goes low (disabling the timer or
+// picking a bit that's 0), both give TIMA an extra increment.
+//
+// TAC:
+// Bit 2: Timer enabled
+// Bits 1 - 0: Counter bit that clocks TIMA
+//   00: Bit 9 (4096 Hz)
+//   01: Bit 3 (262144 Hz)
+//   10: Bit 5 (65536 Hz)
+//   11: Bit 7 (16384 Hz)
+
+pub struct Timer {
+    counter: u16,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+
+    // T-cycles left until an overflowed TIMA is reloaded, 0 when there isn't one
+    reload_delay: u8,
+
+    // Set in the T-cycle the reload happened in
+    reloaded: bool,
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer {
+            counter: 0,
+            tima: 0,
+            tma: 0,
+            tac: 0,
+            reload_delay: 0,
+            reloaded: false,
+        }
+    }
+
+    pub fn tick(&mut self, cycles: usize) -> bool {
+        // Moves the counter on, returns true if TIMA overflowed (and the timer
+        // interrupt should be requested)
+        let mut overflowed = false;
+
+        for _ in 0..cycles {
+            self.reloaded = false;
+            if self.reload_delay > 0 {
+                self.reload_delay -= 1;
+                if self.reload_delay == 0 {
+                    self.tima = self.tma;
+                    self.reloaded = true;
+                    overflowed = true;
+                }
+            }
+
+            let signal = self.get_signal();
+            self.counter = self.counter.wrapping_add(1);
+            self.check_falling_edge(signal);
+        }
+
+        overflowed
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.tac & 4 > 0
+    }
+
+    pub fn get_counter(&self) -> u16 {
+        self.counter
+    }
+
+    pub fn set_counter(&mut self, counter: u16) {
+        // Only for loading states, use reset_divider from the game's side
+        self.counter = counter;
+    }
+
+    pub fn reset_divider(&mut self) {
+        // Writing anything to DIV, or entering STOP, resets the whole counter
+        let signal = self.get_signal();
+        self.counter = 0;
+        self.check_falling_edge(signal);
+    }
+
+    pub fn get_reload_delay(&self) -> u8 {
+        self.reload_delay
+    }
+
+    pub fn set_reload_delay(&mut self, reload_delay: u8) {
+        // Only for loading states
+        self.reload_delay = reload_delay;
+        self.reloaded = false;
+    }
+
+    pub fn get_cycles_until_increment(&self) -> usize {
+        // Cycles until TIMA next changes. That's a pending reload, or otherwise
+        // the selected bit next falling. It falls when it carries into the bit
+        // above, so every (bit * 2) cycles
+        if self.reload_delay > 0 {
+            return self.reload_delay as usize;
+        }
+
+        let period = (self.get_selected_bit() as usize) << 1;
+        period - (self.counter as usize & (period - 1))
+    }
+
+    pub fn get_cycles_until_overflow(&self) -> usize {
+        // Cycles until the timer interrupt is next requested, which is when TIMA is
+        // reloaded after overflowing. usize::MAX if it can't happen while stopped
+        if self.reload_delay > 0 {
+            return self.reload_delay as usize;
+        }
+
+        if !self.is_enabled() {
+            return usize::MAX;
+        }
+
+        let period = (self.get_selected_bit() as usize) << 1;
+        self.get_cycles_until_increment() + (0xFF - self.tima as usize) * period + 4
+    }
+
+    pub fn get_cycles_until_div_increment(&self) -> usize {
+        256 - (self.counter as usize & 0xFF)
+    }
+
+    pub fn read_div(&self) -> u8 {
+        (self.counter >> 8) as u8
+    }
+
+    pub fn read_tima(&self) -> u8 {
+        self.tima
+    }
+
+    pub fn write_tima(&mut self, value: u8) {
+        if self.reloaded {
+            return;
+        }
+
+        // Cancels a reload that hasn't happened yet
+        self.reload_delay = 0;
+        self.tima = value;
+    }
+
+    pub fn read_tma(&self) -> u8 {
+        self.tma
+    }
+
+    pub fn write_tma(&mut self, value: u8) {
+        self.tma = value;
+        if self.reloaded {
+            self.tima = value;
+        }
+    }
+
+    pub fn read_tac(&self) -> u8 {
+        // Only the low 3 bits exist, the rest read back as 1
+        self.tac | 0xF8
+    }
+
+    pub fn write_tac(&mut self, value: u8) {
+        let signal = self.get_signal();
+        self.tac = value & 0x7;
+        self.check_falling_edge(signal);
+    }
+
+    fn get_selected_bit(&self) -> u16 {
+        match self.tac & 0x3 {
+            0 => 1 << 9,
+            1 => 1 << 3,
+            2 => 1 << 5,
+            _ => 1 << 7,
+        }
+    }
+
+    fn get_signal(&self) -> bool {
+        // What the falling edge detector sees, the selected bit ANDed with enable
+        self.is_enabled() && self.counter & self.get_selected_bit() > 0
+    }
+
+    fn check_falling_edge(&mut self, signal: bool) {
+        // signal is what the edge detector saw before the change
+        if signal && !self.get_signal() {
+            self.increment_tima();
+        }
+    }
+
+    fn increment_tima(&mut self) {
+        // On overflow TIMA sits at 0 for a machine cycle before the reload
+        if self.tima == 0xFF {
+            self.tima = 0;
+            self.reload_delay = 4;
+        } else {
+            self.tima += 1;
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use super::cpu::Cpu;
+
+// CPU trace
+//
+// Logs the CPU state before every instruction in the Gameboy Doctor format
+// (https://github.com/robert-dh/gameboy-doctor) so a run can be diffed against a
+// known good emulator to find where they first disagree:
+// A:00 F:11 B:22 C:33 D:44 E:55 H:66 L:77 SP:8888 PC:9999 PCMEM:AA,BB,CC,DD
+// Lines are either kept until they're taken, or handed to a callback as they're
+// made. Nothing is logged while halted, or for interrupt dispatch.
+
+// Gets the instance id and each line as it's made, without the newline
+pub type TraceCallback = Box<dyn FnMut(u32, &str)>;
+
+pub struct Tracer {
+    enabled: bool,
+    lines: String,
+    callback: Option<TraceCallback>,
+}
+
+impl Tracer {
+    pub fn new() -> Tracer {
+        Tracer {
+            enabled: false,
+            lines: String::new(),
+            callback: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.lines.clear();
+    }
+
+    pub fn set_callback(&mut self, callback: Option<TraceCallback>) {
+        // With a callback nothing is kept, every line goes straight to it
+        self.callback = callback;
+    }
+
+    pub fn trace(&mut self, cpu: &Cpu, id: u32) {
+        // id is the emulator instance's, only passed on to the callback
+        let line = format_line(cpu);
+
+        match self.callback.as_mut() {
+            Some(callback) => callback(id, &line),
+            None           => {
+                self.lines.push_str(&line);
+                self.lines.push('\n');
+            }
+        }
+    }
+
+    pub fn take(&mut self) -> String {
+        // Everything logged since the last take, one line per instruction. Lines
+        // build up until they're taken, so take them regularly on long runs
+        std::mem::take(&mut self.lines)
+    }
+}
+
+fn format_line(cpu: &Cpu) -> String {
+    let pc = cpu.program_counter as usize;
+    let pcmem: Vec<String> = (0..4)
+        .map(|i| format!("{:02X}", cpu.mmu.peek_memory(&((pc + i) & 0xFFFF))))
+        .collect();
+
+    format!(
+        "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{}",
+        cpu.af.hi(),
+        cpu.af.lo(),
+        cpu.bc.hi(),
+        cpu.bc.lo(),
+        cpu.de.hi(),
+        cpu.de.lo(),
+        cpu.hl.hi(),
+        cpu.hl.lo(),
+        cpu.stack_pointer.value(),
+        cpu.program_counter,
+        pcmem.join(","),
+    )
+}
@@ -0,0 +1,202 @@
+use std::cell::RefCell;
+use std::sync::RwLock;
+
+// A macro to provide `println!(..)`-style syntax for logging. Messages go to
+// whatever handler the frontend installed with set_log_handler (the wasm wrapper
+// sends them to `console.log`), or stderr if none was installed.
+// Logging is only compiled in for debug builds or when the `debug-logging`
+// feature is enabled. Release builds expand this to nothing so hot paths
+// (banking warnings, restricted writes) don't pay for formatting.
+#[cfg(any(debug_assertions, feature = "debug-logging"))]
+#[macro_export]
+macro_rules! log {
+    ( $( $t:tt )* ) => {
+        {
+            $crate::utils::write_log(&format!( $( $t )* ));
+        }
+    }
+}
+
+// Stripped version of the macro - the arguments are still type checked via
+// format_args! but nothing is formatted or logged
+#[cfg(not(any(debug_assertions, feature = "debug-logging")))]
+#[macro_export]
+macro_rules! log {
+    ( $( $t:tt )* ) => {
+        {
+            let _ = format_args!( $( $t )* );
+        }
+    }
+}
+
+// Where log lines go. This is the one piece of global state and is shared by
+// every emulator instance, it's set once by the frontend
+static LOG_HANDLER: RwLock<Option<fn(&str)>> = RwLock::new(None);
+
+thread_local! {
+    // Prefix of the emulator instance that is currently running (see LogScope)
+    static LOG_PREFIX: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+pub fn set_log_handler(handler: fn(&str)) {
+    if let Ok(mut current) = LOG_HANDLER.write() {
+        *current = Some(handler);
+    }
+}
+
+pub fn write_log(message: &str) {
+    let message = LOG_PREFIX.with(|prefix| {
+        let prefix = prefix.borrow();
+        if prefix.is_empty() {
+            String::from(message)
+        } else {
+            format!("[{}] {}", prefix, message)
+        }
+    });
+
+    match LOG_HANDLER.read().ok().and_then(|handler| *handler) {
+        Some(handler) => handler(&message),
+        None          => eprintln!("{}", message),
+    }
+}
+
+// While one of these is alive, log lines are tagged with its prefix so output
+// from several instances can be told apart. The previous prefix comes back when
+// it is dropped
+pub struct LogScope {
+    previous: String,
+}
+
+impl LogScope {
+    pub fn enter(prefix: &str) -> LogScope {
+        let previous = LOG_PREFIX.with(|current| current.replace(String::from(prefix)));
+
+        LogScope {
+            previous,
+        }
+    }
+}
+
+impl Drop for LogScope {
+    fn drop(&mut self) {
+        let previous = std::mem::take(&mut self.previous);
+        LOG_PREFIX.with(|current| current.replace(previous));
+    }
+}
+
+// Flag Bits in Register F
+pub const ZERO_BIT: u8 = 7;
+pub const SUBTRACT_BIT: u8 = 6;
+pub const HALF_CARRY_BIT: u8 = 5;
+pub const CARRY_BIT: u8 = 4;
+
+// Carry detection for arithmetic. carry_in is the carry flag going into ADC/SBC
+// (0 or 1). Half carry is a carry out of (or borrow into) bit 3 for 8 bit
+// operations and bit 11 for 16 bit ones
+pub fn is_half_carry_add(a: u8, b: u8, carry_in: u8) -> bool {
+    (a & 0xF) + (b & 0xF) + carry_in > 0xF
+}
+
+pub fn is_carry_add(a: u8, b: u8, carry_in: u8) -> bool {
+    a as u16 + b as u16 + carry_in as u16 > 0xFF
+}
+
+pub fn is_half_carry_sub(a: u8, b: u8, carry_in: u8) -> bool {
+    (a & 0xF) < (b & 0xF) + carry_in
+}
+
+pub fn is_carry_sub(a: u8, b: u8, carry_in: u8) -> bool {
+    (a as u16) < b as u16 + carry_in as u16
+}
+
+pub fn is_half_carry_add_16(a: u16, b: u16) -> bool {
+    (a & 0xFFF) + (b & 0xFFF) > 0xFFF
+}
+
+pub fn is_carry_add_16(a: u16, b: u16) -> bool {
+    a as u32 + b as u32 > 0xFFFF
+}
+
+// Timer Constants
+pub const DIVIDER_REGISTER_ADDR: usize = 0xFF04; // The address of the divier register
+pub const TIMER_ADDR: usize = 0xFF05; // The timer is located here and counts up a preset interval
+pub const TIMER_MODULATOR_ADDR: usize = 0xFF06; // The timer modulator that timer resets to on overflow is here
+
+// Timer Controller is 3-bit that controls timer and specifies frequency.
+// The 1st 2 bits describe frequency. Here is the mapping:
+// 00: 4096 Hz
+// 01: 262144 Hz
+// 10: 65536 Hz
+// 11: 16384 Hz
+//
+// The third bit specifies if the timer is enabled (1) or disabled (0)
+// This is the memory address that the controller is stored at
+pub const TIMER_CONTROLLER_ADDR: usize = 0xFF07;
+
+// There are 5 types of interrupts that can occur, see interrupts.rs for the bits
+// that are set in the enabled register and request register when they occur
+//
+// Interrupt Register Address Constants
+pub const INTERRUPT_ENABLED_ADDR: usize = 0xFFFF;
+pub const INTERRUPT_REQUEST_ADDR: usize = 0xFF0F;
+
+// The address of the current scanline
+pub const CURRENT_SCANLINE_ADDR: usize = 0xFF44;
+
+// LCD goes through 4 different modes. Bit 1 and 0 (2nd and 1st)
+// reflect the current status of the the LCD:
+// 00: H-Blank
+// 01: V-Blank
+// 10: Searching Sprites Atts
+// 11: Transferring data to LCD driver
+//
+// Bits 3, 4, 5 are interrupt enabled flags
+// Bit 3: Mode 0 interrupt enabled
+// Bit 4: Mode 1 interrupt enabled
+// Bit 5: Mode 2 interrupt enabled
+//
+// Bit 2 is the coincidence flag. It is set to one if
+// register 0xFF44 is the same as value as 0xFF45, 0 otherwise
+// Bit 6 is an interrupt enabled flag which requests interrupt
+// if Bit 2 is 1
+//
+// The address of the current status of the LCD
+pub const LCD_STATUS_ADDR: usize = 0xFF41;
+
+// Bits of the LCD control Register are as follows:
+// Bit 7 - LCD Display Enable (0=Off, 1=On)
+// Bit 6 - Window Tile Map Display Select (0=9800-9BFF, 1=9C00-9FFF)
+// Bit 5 - Window Display Enable (0=Off, 1=On)
+// Bit 4 - BG & Window Tile Data Select (0=8800-97FF, 1=8000-8FFF)
+// Bit 3 - BG Tile Map Display Select (0=9800-9BFF, 1=9C00-9FFF)
+// Bit 2 - OBJ (Sprite) Size (0=8x8, 1=8x16)
+// Bit 1 - OBJ (Sprite) Display Enable (0=Off, 1=On)
+// Bit 0 - BG Display (for CGB see below) (0=Off, 1=On)
+// The address of the LCD control register
+pub const LCD_CONTROL_ADDR: usize = 0xFF40;
+
+// The folllowing are addresses which specify where to draw the background and window
+// ScrollY (0xFF42): The Y Position of the BACKGROUND where to start drawing the viewing area from
+// ScrollX (0xFF43): The X Position of the BACKGROUND to start drawing the viewing area from
+// WindowY (0xFF4A): The Y Position of the VIEWING AREA to start drawing the window from
+// WindowX (0xFF4B): The X Positions -7 of the VIEWING AREA to start drawing the window from
+pub const SCROLL_Y_ADDR: usize = 0xFF42;
+pub const SCROLL_X_ADDR: usize = 0xFF43;
+pub const WINDOW_Y_ADDR: usize = 0xFF4A;
+pub const WINDOW_X_ADDR: usize = 0xFF4B;
+
+// Writing the upper byte of an address here copies 160 bytes from it into OAM
+pub const DMA_ADDR: usize = 0xFF46;
+
+// The address of the color pallette
+pub const COLOR_PALLETTE_ADDR: usize = 0xFF47;
+
+// The addresses of the two sprite color pallettes, attribute bit 4 selects which
+pub const SPRITE_PALLETTE_0_ADDR: usize = 0xFF48;
+pub const SPRITE_PALLETTE_1_ADDR: usize = 0xFF49;
+
+// Writing anything other than 0 here unmaps the boot ROM from 0000 - 00FF
+pub const BOOT_ROM_DISABLE_ADDR: usize = 0xFF50;
+
+// The starting address of sprite attribute region
+pub const SPRITE_ATTRIBUTE_ADDR: usize = 0xFE00;
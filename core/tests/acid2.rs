@@ -0,0 +1,48 @@
+// dmg-acid2, checked against its reference image (https://github.com/mattcurrie/dmg-acid2)
+//
+// Needs the ROM, so only built with the test-roms feature. Point ACID2_DIR at a
+// directory holding dmg-acid2.gb and the reference-dmg.png from the repo's img/:
+//   ACID2_DIR=~/dmg-acid2 cargo test -p gameboy-core --features gameboy-core/test-roms
+#![cfg(feature = "test-roms")]
+
+mod common;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// The face is finished well within this, it's drawn the same every frame after
+const FRAMES: u32 = 60;
+
+fn get_path(name: &str) -> PathBuf {
+    let dir = env::var("ACID2_DIR").expect("ACID2_DIR should point at dmg-acid2.gb and reference-dmg.png");
+    PathBuf::from(dir).join(name)
+}
+
+#[test]
+fn dmg_acid2() {
+    let path = get_path("dmg-acid2.gb");
+    let rom = fs::read(&path).unwrap_or_else(|e| panic!("Couldn't read {}: {}", path.display(), e));
+
+    let mut emulator = gameboy_core::Emulator::new(gameboy_core::Game::from_bytes(&rom));
+    for _ in 0..FRAMES {
+        emulator.update();
+    }
+
+    let frame = common::get_frame(&emulator);
+    let reference = common::read_reference(&get_path("reference-dmg.png"));
+
+    // Where it's wrong is the useful part when it fails
+    let wrong: Vec<(usize, usize)> = (0..160 * 144)
+        .filter(|i| frame[i * 3..i * 3 + 3] != reference[i * 3..i * 3 + 3])
+        .map(|i| (i % 160, i / 160))
+        .collect();
+
+    assert_eq!(
+        common::get_hash(&frame),
+        common::get_hash(&reference),
+        "{} pixels differ, the first at (x, y) {:?}",
+        wrong.len(),
+        wrong.first(),
+    );
+}
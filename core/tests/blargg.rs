@@ -0,0 +1,30 @@
+// blargg's cpu_instrs, run to completion (https://github.com/retrio/gb-test-roms)
+//
+// Needs the ROMs, so only built with the test-roms feature. Point BLARGG_ROMS_DIR
+// at a checkout of gb-test-roms:
+//   BLARGG_ROMS_DIR=~/gb-test-roms cargo test -p gameboy-core --features gameboy-core/test-roms
+#![cfg(feature = "test-roms")]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use gameboy_core::TestRomStatus;
+
+// cpu_instrs takes a little under a minute on hardware
+const MAX_FRAMES: u32 = 60 * 90;
+
+fn get_rom(name: &str) -> Vec<u8> {
+    let dir = env::var("BLARGG_ROMS_DIR").expect("BLARGG_ROMS_DIR should point at gb-test-roms");
+    let path = PathBuf::from(dir).join(name);
+    fs::read(&path).unwrap_or_else(|e| panic!("Couldn't read {}: {}", path.display(), e))
+}
+
+#[test]
+fn cpu_instrs() {
+    let rom = get_rom("cpu_instrs/cpu_instrs.gb");
+    let result = gameboy_core::run_test_rom(&rom, MAX_FRAMES);
+
+    // Everything the ROM printed is the useful part when it fails
+    assert_eq!(result.status, TestRomStatus::Passed, "{}", result.serial_output);
+}
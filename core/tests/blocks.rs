@@ -0,0 +1,52 @@
+// The block cache (see blocks.rs) has to forget what it decoded from a bank once
+// another one is switched in. Each test calls the same address in two banks
+// holding different code, and checks both ran as they are in ROM.
+
+use gameboy_core::{Emulator, Game};
+
+fn store_a(address: u16, value: u8) -> Vec<u8> {
+    // LD A, value / LD (address), A
+    vec![0x3E, value, 0xEA, address as u8, (address >> 8) as u8]
+}
+
+fn put(rom: &mut [u8], address: usize, code: &[u8]) {
+    rom[address..address + code.len()].copy_from_slice(code);
+}
+
+#[test]
+fn tama5_bank_switch() {
+    let mut rom = vec![0; 0x10000];
+    rom[0x147] = 0xFD;
+
+    // A001 selects a register, A000 writes it. Register 0 is the ROM bank
+    let mut code = Vec::new();
+    for bank in [1, 2].iter() {
+        code.extend(store_a(0xA001, 0x0));
+        code.extend(store_a(0xA000, *bank));
+        code.extend([0xCD, 0x00, 0x40]); // CALL 0x4000
+    }
+    code.extend([0x18, 0xFE]); // JR -2
+
+    put(&mut rom, 0x100, &[0xC3, 0x50, 0x01]); // JP 0x150
+    put(&mut rom, 0x150, &code);
+
+    // Different instructions at the same address, so running bank 2 with what
+    // was decoded from bank 1 goes wrong
+    let mut bank_1 = store_a(0xC000, 0x11);
+    bank_1.push(0xC9); // RET
+    put(&mut rom, 0x4000, &bank_1);
+
+    put(&mut rom, 0x8000, &[
+        0x06, 0x22,       // LD B, 0x22
+        0x78,             // LD A, B
+        0xEA, 0x01, 0xC0, // LD (0xC001), A
+        0xC9,             // RET
+    ]);
+
+    let mut emulator = Emulator::new(Game::from_bytes(&rom));
+    emulator.update();
+
+    assert_eq!(emulator.mbc_state().rom_bank, 2);
+    assert_eq!(emulator.peek(0xC000), 0x11);
+    assert_eq!(emulator.peek(0xC001), 0x22);
+}
@@ -0,0 +1,43 @@
+// Callbacks are told which instance they came from, so a page running several
+// emulators can share one callback between them.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gameboy_core::{Emulator, Game};
+
+fn get_rom() -> Vec<u8> {
+    let mut rom = vec![0; 0x8000];
+    rom[0x100..0x102].copy_from_slice(&[0x18, 0xFE]); // JR -2
+    rom
+}
+
+#[test]
+fn vblank_callback_gets_instance_id() {
+    let ids = Rc::new(RefCell::new(Vec::new()));
+    let mut emulators = [Emulator::new(Game::from_bytes(&get_rom())), Emulator::new(Game::from_bytes(&get_rom()))];
+
+    for emulator in emulators.iter_mut() {
+        let ids = ids.clone();
+        emulator.on_vblank(Some(Box::new(move |id, _| ids.borrow_mut().push(id))));
+        emulator.update();
+    }
+
+    let expected: Vec<u32> = emulators.iter().map(|emulator| emulator.id()).collect();
+    let mut seen = ids.borrow().clone();
+    seen.dedup();
+    assert_eq!(seen, expected);
+}
+
+#[test]
+fn trace_callback_gets_instance_id() {
+    let ids = Rc::new(RefCell::new(Vec::new()));
+    let mut emulator = Emulator::new(Game::from_bytes(&get_rom()));
+
+    let seen = ids.clone();
+    emulator.set_trace(true);
+    emulator.set_trace_callback(Some(Box::new(move |id, _| seen.borrow_mut().push(id))));
+    emulator.step_instruction();
+
+    assert_eq!(*ids.borrow(), vec![emulator.id()]);
+}
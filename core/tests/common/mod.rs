@@ -0,0 +1,40 @@
+// Helpers shared by the test ROM harnesses that compare the screen against a
+// reference image (acid2.rs, mealybug.rs).
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+pub fn read_reference(path: &Path) -> Vec<u8> {
+    // A 160x144 reference image as RGB. They're drawn with the same four greys as
+    // the default palette, so they can be compared with the frame as is
+    let file = fs::File::open(path).unwrap_or_else(|e| panic!("Couldn't read {}: {}", path.display(), e));
+
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().unwrap_or_else(|e| panic!("{} should be a PNG: {}", path.display(), e));
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).unwrap_or_else(|e| panic!("{} should decode: {}", path.display(), e));
+    assert_eq!((info.width, info.height), (160, 144));
+
+    let pixels = &buffer[..info.buffer_size()];
+    match info.color_type {
+        png::ColorType::Grayscale      => pixels.iter().flat_map(|g| vec![*g; 3]).collect(),
+        png::ColorType::GrayscaleAlpha => pixels.chunks(2).flat_map(|p| vec![p[0]; 3]).collect(),
+        png::ColorType::Rgb            => pixels.to_vec(),
+        png::ColorType::Rgba           => pixels.chunks(4).flat_map(|p| p[..3].to_vec()).collect(),
+        other                          => panic!("Unexpected colour type {:?}", other),
+    }
+}
+
+pub fn get_frame(emulator: &gameboy_core::Emulator) -> Vec<u8> {
+    // The frame is RGBA, drop the alpha to match the references
+    emulator.get_frame().chunks(4).flat_map(|p| p[..3].to_vec()).collect()
+}
+
+pub fn get_hash(rgb: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    rgb.hash(&mut hasher);
+    hasher.finish()
+}
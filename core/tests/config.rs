@@ -0,0 +1,39 @@
+// Imported configs go through the same limits as the individual setters, checked
+// by importing values out of range and reading back what was kept.
+
+use gameboy_core::{Emulator, Game};
+
+#[test]
+fn imported_values_are_clamped() {
+    let mut emulator = Emulator::new(Game::from_bytes(&vec![0; 0x8000]));
+    emulator
+        .import_config(
+            r#"{
+                "speed": -2.0,
+                "filters": { "lcd_grid": 5.0, "scale": 9 },
+                "display": { "brightness": 3.0, "contrast": -1.0, "gamma": 0.0 }
+            }"#,
+        )
+        .unwrap();
+
+    let config: serde_json::Value = serde_json::from_str(&emulator.export_config()).unwrap();
+    assert_eq!(config["speed"], 0.0);
+    assert_eq!(config["filters"]["lcd_grid"], 1.0);
+    assert_eq!(config["filters"]["scale"], 4);
+    assert_eq!(config["display"]["brightness"], 1.0);
+    assert_eq!(config["display"]["contrast"], 0.0);
+    assert_eq!(config["display"]["gamma"], 1.0);
+}
+
+#[test]
+fn setters_turn_down_nan() {
+    let mut emulator = Emulator::new(Game::from_bytes(&vec![0; 0x8000]));
+    emulator.set_lcd_grid(f32::NAN);
+    emulator.set_display_adjustments(f32::NAN, f32::NAN, f32::NAN);
+
+    let config: serde_json::Value = serde_json::from_str(&emulator.export_config()).unwrap();
+    assert_eq!(config["filters"]["lcd_grid"], 0.0);
+    assert_eq!(config["display"]["brightness"], 0.0);
+    assert_eq!(config["display"]["contrast"], 1.0);
+    assert_eq!(config["display"]["gamma"], 1.0);
+}
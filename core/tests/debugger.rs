@@ -0,0 +1,48 @@
+// The bank switch log, checked by running a few banking writes and reading back
+// what was recorded.
+
+use gameboy_core::{Emulator, Game};
+
+fn store_a(address: u16, value: u8) -> Vec<u8> {
+    // LD A, value / LD (address), A
+    vec![0x3E, value, 0xEA, address as u8, (address >> 8) as u8]
+}
+
+#[test]
+fn tama5_writes_are_logged() {
+    let mut rom = vec![0; 0x10000];
+    rom[0x147] = 0xFD;
+
+    // Select register 0 (the ROM bank) and switch to bank 3
+    let mut code = Vec::new();
+    code.extend(store_a(0xA001, 0x0));
+    code.extend(store_a(0xA000, 0x3));
+    code.extend([0x18, 0xFE]); // JR -2
+
+    rom[0x100..0x103].copy_from_slice(&[0xC3, 0x50, 0x01]); // JP 0x150
+    rom[0x150..0x150 + code.len()].copy_from_slice(&code);
+
+    let mut emulator = Emulator::new(Game::from_bytes(&rom));
+    emulator.enable_bank_log(16);
+    emulator.update();
+
+    let log: Vec<(u16, u8, u8)> = emulator.bank_log().iter().map(|e| (e.address, e.value, e.rom_bank)).collect();
+    assert_eq!(log, vec![(0xA001, 0x0, 0), (0xA000, 0x3, 3)]);
+}
+
+#[test]
+fn huge_capacity_is_not_allocated_up_front() {
+    let mut rom = vec![0; 0x8000];
+    rom[0x147] = 0x01;
+
+    // One MBC1 banking write, then loop
+    let mut code = store_a(0x2000, 0x01);
+    code.extend([0x18, 0xFE]); // JR -2
+    rom[0x100..0x100 + code.len()].copy_from_slice(&code);
+
+    let mut emulator = Emulator::new(Game::from_bytes(&rom));
+    emulator.enable_bank_log(usize::MAX);
+    emulator.update();
+
+    assert_eq!(emulator.bank_log().len(), 1);
+}
@@ -0,0 +1,31 @@
+// OAM DMA (see dma.rs) copies 160 bytes into OAM, reading its source the same way
+// the CPU would, so a switchable ROM bank comes from whichever bank is selected.
+
+use gameboy_core::{Emulator, Game};
+
+#[test]
+fn copies_from_selected_rom_bank() {
+    let mut rom = vec![0; 0x10000];
+    rom[0x147] = 0x01; // MBC1
+
+    rom[0x100..0x10B].copy_from_slice(&[
+        0x3E, 0x02,       // LD A, 2
+        0xEA, 0x00, 0x20, // LD (0x2000), A - ROM bank 2
+        0x3E, 0x40,       // LD A, 0x40
+        0xE0, 0x46,       // LDH (0x46), A - DMA from 0x4000
+        0x18, 0xFE,       // JR -2
+    ]);
+
+    // Bank 1 would be the one at 4000 without the switch
+    for i in 0..0xA0 {
+        rom[0x4000 + i] = 0xFF;
+        rom[0x8000 + i] = i as u8;
+    }
+
+    let mut emulator = Emulator::new(Game::from_bytes(&rom));
+    emulator.update();
+
+    let oam = emulator.peek_range(0xFE00, 0xA0);
+    let expected: Vec<u8> = (0..0xA0).collect();
+    assert_eq!(oam, expected);
+}
@@ -0,0 +1,44 @@
+// Idle loop skipping (see idle.rs), checked by counting the instructions the CPU
+// trace logs over a frame with skipping off and on. A loop polling LY is waiting on
+// the PPU and should be skipped through, a loop counting a register down is doing
+// work and has to run every time around.
+
+fn get_rom(code: &[u8]) -> Vec<u8> {
+    // code goes at the entry point, 0x100
+    let mut rom = vec![0; 0x8000];
+    rom[0x100..0x100 + code.len()].copy_from_slice(code);
+    rom
+}
+
+fn count_instructions(rom: &[u8], idle_skipping: bool) -> usize {
+    let mut emulator = gameboy_core::Emulator::new(gameboy_core::Game::from_bytes(rom));
+    emulator.set_idle_skipping(idle_skipping);
+    emulator.set_trace(true);
+    emulator.update();
+    emulator.take_trace().lines().count()
+}
+
+#[test]
+fn ly_poll_loop_is_skipped() {
+    let rom = get_rom(&[
+        0xF0, 0x44, // LDH A, (0x44)
+        0xFE, 0x99, // CP 0x99
+        0x20, 0xFA, // JR NZ, -6
+        0x18, 0xF8, // JR -8
+    ]);
+
+    let normal = count_instructions(&rom, false);
+    let skipping = count_instructions(&rom, true);
+    assert!(skipping < normal, "{} instructions with skipping, {} without", skipping, normal);
+}
+
+#[test]
+fn countdown_loop_is_not_skipped() {
+    let rom = get_rom(&[
+        0x05,       // DEC B
+        0x20, 0xFD, // JR NZ, -3
+        0x18, 0xFB, // JR -5
+    ]);
+
+    assert_eq!(count_instructions(&rom, true), count_instructions(&rom, false));
+}
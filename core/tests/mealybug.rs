@@ -0,0 +1,78 @@
+// mealybug-tearoom-tests PPU tests, checked against their reference images
+// (https://github.com/mattcurrie/mealybug-tearoom-tests)
+//
+// These change PPU registers partway through lines, so they run with the pixel
+// FIFO. Needs the ROMs, so only built with the test-roms feature. Point
+// MEALYBUG_DIR at a checkout of the repo with the ROMs built (build/ppu/) next to
+// the DMG reference images (expected/DMG-blob/):
+//   MEALYBUG_DIR=~/mealybug-tearoom-tests cargo test -p gameboy-core --features gameboy-core/test-roms
+#![cfg(feature = "test-roms")]
+
+mod common;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// They run LD B, B once the frame is ready, well within this
+const MAX_FRAMES: u32 = 60 * 2;
+
+const PPU: [&str; 24] = [
+    "m2_win_en_toggle",
+    "m3_bgp_change",
+    "m3_bgp_change_sprites",
+    "m3_lcdc_bg_en_change",
+    "m3_lcdc_bg_map_change",
+    "m3_lcdc_obj_en_change",
+    "m3_lcdc_obj_en_change_variant",
+    "m3_lcdc_obj_size_change",
+    "m3_lcdc_obj_size_change_scx",
+    "m3_lcdc_tile_sel_change",
+    "m3_lcdc_tile_sel_win_change",
+    "m3_lcdc_win_en_change_multiple",
+    "m3_lcdc_win_en_change_multiple_wx",
+    "m3_lcdc_win_map_change",
+    "m3_obp0_change",
+    "m3_scx_high_5_bits",
+    "m3_scx_low_3_bits",
+    "m3_scy_change",
+    "m3_window_timing",
+    "m3_window_timing_wx_0",
+    "m3_wx_4_change",
+    "m3_wx_4_change_sprites",
+    "m3_wx_5_change",
+    "m3_wx_6_change",
+];
+
+fn get_dir() -> PathBuf {
+    PathBuf::from(env::var("MEALYBUG_DIR").expect("MEALYBUG_DIR should point at the mealybug-tearoom-tests repo"))
+}
+
+fn run_rom(name: &str) -> Option<String> {
+    // A description of what went wrong, if anything did
+    let path = get_dir().join("build/ppu").join(format!("{}.gb", name));
+    let rom = fs::read(&path).unwrap_or_else(|e| panic!("Couldn't read {}: {}", path.display(), e));
+
+    let mut emulator = gameboy_core::Emulator::new(gameboy_core::Game::from_bytes(&rom));
+    emulator.set_pixel_fifo(true);
+    if !gameboy_core::run_to_breakpoint(&mut emulator, MAX_FRAMES) {
+        return Some(format!("{}: never finished", name));
+    }
+
+    let frame = common::get_frame(&emulator);
+    let reference = common::read_reference(&get_dir().join("expected/DMG-blob").join(format!("{}.png", name)));
+    if common::get_hash(&frame) == common::get_hash(&reference) {
+        return None;
+    }
+
+    let wrong = (0..160 * 144)
+        .filter(|i| frame[i * 3..i * 3 + 3] != reference[i * 3..i * 3 + 3])
+        .count();
+    Some(format!("{}: {} pixels differ", name, wrong))
+}
+
+#[test]
+fn ppu() {
+    let failures: Vec<String> = PPU.iter().filter_map(|name| run_rom(name)).collect();
+    assert!(failures.is_empty(), "Failed: {:?}", failures);
+}
@@ -0,0 +1,83 @@
+// mooneye-gb acceptance tests (https://github.com/Gekkio/mooneye-test-suite)
+//
+// Needs the ROMs, so only built with the test-roms feature. Point MOONEYE_ROMS_DIR
+// at the build directory of the test suite:
+//   MOONEYE_ROMS_DIR=~/mooneye-test-suite/build cargo test -p gameboy-core --features gameboy-core/test-roms
+#![cfg(feature = "test-roms")]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use gameboy_core::TestRomStatus;
+
+// Every mooneye test finishes within a few seconds
+const MAX_FRAMES: u32 = 60 * 10;
+
+const TIMER: [&str; 13] = [
+    "acceptance/timer/div_write.gb",
+    "acceptance/timer/rapid_toggle.gb",
+    "acceptance/timer/tim00.gb",
+    "acceptance/timer/tim00_div_trigger.gb",
+    "acceptance/timer/tim01.gb",
+    "acceptance/timer/tim01_div_trigger.gb",
+    "acceptance/timer/tim10.gb",
+    "acceptance/timer/tim10_div_trigger.gb",
+    "acceptance/timer/tim11.gb",
+    "acceptance/timer/tim11_div_trigger.gb",
+    "acceptance/timer/tima_reload.gb",
+    "acceptance/timer/tima_write_reloading.gb",
+    "acceptance/timer/tma_write_reloading.gb",
+];
+
+const INTERRUPTS: [&str; 10] = [
+    "acceptance/interrupts/ie_push.gb",
+    "acceptance/di_timing-GS.gb",
+    "acceptance/ei_sequence.gb",
+    "acceptance/ei_timing.gb",
+    "acceptance/halt_ime0_ei.gb",
+    "acceptance/halt_ime0_nointr_timing.gb",
+    "acceptance/halt_ime1_timing.gb",
+    "acceptance/if_ie_registers.gb",
+    "acceptance/intr_timing.gb",
+    "acceptance/rapid_di_ei.gb",
+];
+
+const OAM_DMA: [&str; 6] = [
+    "acceptance/oam_dma/basic.gb",
+    "acceptance/oam_dma/reg_read.gb",
+    "acceptance/oam_dma_restart.gb",
+    "acceptance/oam_dma_start.gb",
+    "acceptance/oam_dma_timing.gb",
+    "acceptance/oam_dma/sources-GS.gb",
+];
+
+fn run_roms(roms: &[&str]) {
+    let dir = PathBuf::from(env::var("MOONEYE_ROMS_DIR").expect("MOONEYE_ROMS_DIR should point at the test suite build"));
+
+    let failures: Vec<_> = roms
+        .iter()
+        .filter(|name| {
+            let path = dir.join(name);
+            let rom = fs::read(&path).unwrap_or_else(|e| panic!("Couldn't read {}: {}", path.display(), e));
+            gameboy_core::run_test_rom(&rom, MAX_FRAMES).status != TestRomStatus::Passed
+        })
+        .collect();
+
+    assert!(failures.is_empty(), "Failed: {:?}", failures);
+}
+
+#[test]
+fn timer() {
+    run_roms(&TIMER);
+}
+
+#[test]
+fn interrupts() {
+    run_roms(&INTERRUPTS);
+}
+
+#[test]
+fn oam_dma() {
+    run_roms(&OAM_DMA);
+}
@@ -0,0 +1,297 @@
+// Single instructions, run through the SM83 test vector runner (see sm83.rs) so
+// the CPU is on flat memory with nothing else involved. Each check gives the state
+// before and after one instruction, and how many machine cycles it should take.
+// Flags are F's upper nibble: Z 0x80, N 0x40, H 0x20, C 0x10.
+
+use serde_json::json;
+
+// Registers and RAM for one side of a check. The instruction's bytes go in ram
+// at pc, and anything left unset is 0
+#[derive(Clone, Default)]
+struct State {
+    a: u8,
+    f: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+    e: u8,
+    h: u8,
+    l: u8,
+    sp: u16,
+    pc: u16,
+    ram: Vec<(u16, u8)>,
+}
+
+impl State {
+    fn new(code: &[u8]) -> State {
+        // Code at 0x100 with the stack well out of its way
+        let ram = code.iter().enumerate().map(|(i, byte)| (0x100 + i as u16, *byte)).collect();
+        State {
+            sp: 0xDFF0,
+            pc: 0x100,
+            ram,
+            ..State::default()
+        }
+    }
+
+    fn with_ram(mut self, address: u16, value: u8) -> State {
+        self.ram.retain(|(a, _)| *a != address);
+        self.ram.push((address, value));
+        self
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "pc": self.pc, "sp": self.sp,
+            "a": self.a, "f": self.f, "b": self.b, "c": self.c,
+            "d": self.d, "e": self.e, "h": self.h, "l": self.l,
+            "ime": 0, "ram": self.ram,
+        })
+    }
+}
+
+fn check(name: &str, initial: &State, expected: &State, machine_cycles: usize) {
+    let vector = json!([{
+        "name": name,
+        "initial": initial.to_json(),
+        "final": expected.to_json(),
+        "cycles": vec![serde_json::Value::Null; machine_cycles],
+    }]);
+
+    let failures = gameboy_core::run_sm83_tests(&vector.to_string()).unwrap();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// DAA
+
+fn check_daa(name: &str, a: u8, f: u8, expected_a: u8, expected_f: u8) {
+    // a and f as an ADD or SUB left them
+    let initial = State { a, f, ..State::new(&[0x27]) };
+    let expected = State { a: expected_a, f: expected_f, pc: 0x101, ..initial.clone() };
+    check(name, &initial, &expected, 1);
+}
+
+#[test]
+fn daa_after_add() {
+    check_daa("0x15 + 0x27", 0x3C, 0x00, 0x42, 0x00);
+    check_daa("0x09 + 0x08, half carry", 0x11, 0x20, 0x17, 0x00);
+    check_daa("0x90 + 0x90, carry", 0x20, 0x10, 0x80, 0x10);
+    check_daa("0x99 + 0x01, both digits over 9", 0x9A, 0x00, 0x00, 0x90);
+    check_daa("0x99 + 0x99, half carry and carry", 0x32, 0x30, 0x98, 0x10);
+}
+
+#[test]
+fn daa_after_sub() {
+    check_daa("0x42 - 0x15, half carry", 0x2D, 0x60, 0x27, 0x40);
+    check_daa("0x10 - 0x20, carry", 0xF0, 0x50, 0x90, 0x50);
+    check_daa("0x00 - 0x01, half carry and carry", 0xFF, 0x70, 0x99, 0x50);
+    check_daa("0x05 - 0x05, zero", 0x00, 0xC0, 0x00, 0xC0);
+}
+
+// Arithmetic and logic
+
+#[test]
+fn add_sets_half_carry_and_carry() {
+    // ADD A, B
+    let initial = State { a: 0x3A, b: 0xC6, ..State::new(&[0x80]) };
+    let expected = State { a: 0x00, f: 0xB0, pc: 0x101, ..initial.clone() };
+    check("ADD A, B", &initial, &expected, 1);
+}
+
+#[test]
+fn sub_sets_zero_and_negative() {
+    // SUB B
+    let initial = State { a: 0x3E, b: 0x3E, ..State::new(&[0x90]) };
+    let expected = State { a: 0x00, f: 0xC0, pc: 0x101, ..initial.clone() };
+    check("SUB B", &initial, &expected, 1);
+}
+
+#[test]
+fn cp_leaves_a_alone() {
+    // CP 0x40
+    let initial = State { a: 0x3C, ..State::new(&[0xFE, 0x40]) };
+    let expected = State { f: 0x50, pc: 0x102, ..initial.clone() };
+    check("CP d8", &initial, &expected, 2);
+}
+
+#[test]
+fn and_sets_half_carry() {
+    // AND 0x0F
+    let initial = State { a: 0xF0, f: 0x10, ..State::new(&[0xE6, 0x0F]) };
+    let expected = State { a: 0x00, f: 0xA0, pc: 0x102, ..initial.clone() };
+    check("AND d8", &initial, &expected, 2);
+}
+
+#[test]
+fn inc_keeps_carry() {
+    // INC B
+    let initial = State { b: 0x0F, f: 0x10, ..State::new(&[0x04]) };
+    let expected = State { b: 0x10, f: 0x30, pc: 0x101, ..initial.clone() };
+    check("INC B", &initial, &expected, 1);
+}
+
+#[test]
+fn dec_hl_indirect() {
+    // DEC (HL)
+    let initial = State { h: 0xC0, ..State::new(&[0x35]) }.with_ram(0xC000, 0x01);
+    let expected = State { f: 0xC0, pc: 0x101, ..initial.clone() }.with_ram(0xC000, 0x00);
+    check("DEC (HL)", &initial, &expected, 3);
+}
+
+// Jumps, calls and returns. A condition that holds costs the extra cycles of
+// actually going somewhere
+
+#[test]
+fn jp_nz() {
+    // JP NZ, 0x1234
+    let initial = State::new(&[0xC2, 0x34, 0x12]);
+    let expected = State { pc: 0x1234, ..initial.clone() };
+    check("JP NZ taken", &initial, &expected, 4);
+
+    let initial = State { f: 0x80, ..initial };
+    let expected = State { pc: 0x103, ..initial.clone() };
+    check("JP NZ not taken", &initial, &expected, 3);
+}
+
+#[test]
+fn jr_z_backwards() {
+    // JR Z, -5
+    let initial = State { f: 0x80, ..State::new(&[0x28, 0xFB]) };
+    let expected = State { pc: 0xFD, ..initial.clone() };
+    check("JR Z taken", &initial, &expected, 3);
+
+    let initial = State { f: 0x00, ..initial };
+    let expected = State { pc: 0x102, ..initial.clone() };
+    check("JR Z not taken", &initial, &expected, 2);
+}
+
+#[test]
+fn call_nz() {
+    // CALL NZ, 0x1234
+    let initial = State::new(&[0xC4, 0x34, 0x12]);
+    let expected = State { pc: 0x1234, sp: 0xDFEE, ..initial.clone() }
+        .with_ram(0xDFEE, 0x03)
+        .with_ram(0xDFEF, 0x01);
+    check("CALL NZ taken", &initial, &expected, 6);
+
+    let initial = State { f: 0x80, ..initial };
+    let expected = State { pc: 0x103, ..initial.clone() };
+    check("CALL NZ not taken", &initial, &expected, 3);
+}
+
+#[test]
+fn ret_c() {
+    // RET C, with 0x1234 on the stack
+    let initial = State { f: 0x10, ..State::new(&[0xD8]) }
+        .with_ram(0xDFF0, 0x34)
+        .with_ram(0xDFF1, 0x12);
+    let expected = State { pc: 0x1234, sp: 0xDFF2, ..initial.clone() };
+    check("RET C taken", &initial, &expected, 5);
+
+    let initial = State { f: 0x00, ..initial };
+    let expected = State { pc: 0x101, ..initial.clone() };
+    check("RET C not taken", &initial, &expected, 2);
+}
+
+#[test]
+fn ret() {
+    // RET, with 0x1234 on the stack
+    let initial = State::new(&[0xC9]).with_ram(0xDFF0, 0x34).with_ram(0xDFF1, 0x12);
+    let expected = State { pc: 0x1234, sp: 0xDFF2, ..initial.clone() };
+    check("RET", &initial, &expected, 4);
+}
+
+// Stack and 16 bit
+
+#[test]
+fn pop_af_masks_low_flag_bits() {
+    // POP AF, with 0x12FF on the stack
+    let initial = State::new(&[0xF1]).with_ram(0xDFF0, 0xFF).with_ram(0xDFF1, 0x12);
+    let expected = State { a: 0x12, f: 0xF0, sp: 0xDFF2, pc: 0x101, ..initial.clone() };
+    check("POP AF", &initial, &expected, 3);
+}
+
+#[test]
+fn add_sp_carries_from_low_byte() {
+    // ADD SP, 1 carries out of both nibbles of the low byte, and clears Z and N
+    let initial = State { sp: 0x00FF, f: 0xC0, ..State::new(&[0xE8, 0x01]) };
+    let expected = State { sp: 0x0100, f: 0x30, pc: 0x102, ..initial.clone() };
+    check("ADD SP, 1", &initial, &expected, 4);
+
+    // ADD SP, -1 from 0 carries out of nothing
+    let initial = State { sp: 0x0000, ..State::new(&[0xE8, 0xFF]) };
+    let expected = State { sp: 0xFFFF, f: 0x00, pc: 0x102, ..initial.clone() };
+    check("ADD SP, -1", &initial, &expected, 4);
+}
+
+#[test]
+fn ld_hl_sp_offset() {
+    // LD HL, SP + 2
+    let initial = State { sp: 0xDFFE, ..State::new(&[0xF8, 0x02]) };
+    let expected = State { h: 0xE0, l: 0x00, f: 0x30, pc: 0x102, ..initial.clone() };
+    check("LD HL, SP + 2", &initial, &expected, 3);
+}
+
+// CB prefixed. The prefix is fetched like any other instruction, so registers take
+// 2 cycles, and (HL) 4 where it's written back or 3 for BIT, which only reads
+
+#[test]
+fn rlc_b() {
+    // RLC B
+    let initial = State { b: 0x85, ..State::new(&[0xCB, 0x00]) };
+    let expected = State { b: 0x0B, f: 0x10, pc: 0x102, ..initial.clone() };
+    check("RLC B", &initial, &expected, 2);
+}
+
+#[test]
+fn rl_c_through_carry() {
+    // RL C
+    let initial = State { c: 0x80, ..State::new(&[0xCB, 0x11]) };
+    let expected = State { c: 0x00, f: 0x90, pc: 0x102, ..initial.clone() };
+    check("RL C", &initial, &expected, 2);
+}
+
+#[test]
+fn srl_a() {
+    // SRL A
+    let initial = State { a: 0x01, ..State::new(&[0xCB, 0x3F]) };
+    let expected = State { a: 0x00, f: 0x90, pc: 0x102, ..initial.clone() };
+    check("SRL A", &initial, &expected, 2);
+}
+
+#[test]
+fn bit_keeps_carry() {
+    // BIT 7, H
+    let initial = State { h: 0x7F, f: 0x50, ..State::new(&[0xCB, 0x7C]) };
+    let expected = State { f: 0xB0, pc: 0x102, ..initial.clone() };
+    check("BIT 7, H", &initial, &expected, 2);
+}
+
+#[test]
+fn bit_hl_indirect() {
+    // BIT 0, (HL)
+    let initial = State { h: 0xC0, ..State::new(&[0xCB, 0x46]) }.with_ram(0xC000, 0x01);
+    let expected = State { f: 0x20, pc: 0x102, ..initial.clone() };
+    check("BIT 0, (HL)", &initial, &expected, 3);
+}
+
+#[test]
+fn res_and_set_hl_indirect() {
+    // RES 0, (HL)
+    let initial = State { h: 0xC0, f: 0xF0, ..State::new(&[0xCB, 0x86]) }.with_ram(0xC000, 0xFF);
+    let expected = State { pc: 0x102, ..initial.clone() }.with_ram(0xC000, 0xFE);
+    check("RES 0, (HL)", &initial, &expected, 4);
+
+    // SET 7, (HL)
+    let initial = State { h: 0xC0, ..State::new(&[0xCB, 0xFE]) }.with_ram(0xC000, 0x00);
+    let expected = State { pc: 0x102, ..initial.clone() }.with_ram(0xC000, 0x80);
+    check("SET 7, (HL)", &initial, &expected, 4);
+}
+
+#[test]
+fn swap_hl_indirect() {
+    // SWAP (HL)
+    let initial = State { h: 0xC0, f: 0x70, ..State::new(&[0xCB, 0x36]) }.with_ram(0xC000, 0xF0);
+    let expected = State { f: 0x00, pc: 0x102, ..initial.clone() }.with_ram(0xC000, 0x0F);
+    check("SWAP (HL)", &initial, &expected, 4);
+}
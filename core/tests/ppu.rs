@@ -0,0 +1,139 @@
+// PPU mode timing, checked by stepping a program of NOPs an instruction (4 dots) at
+// a time and watching STAT, LY and IF as the hardware moves on. With no sprites,
+// no fine scroll and no window, a visible line is 80 dots of OAM scan (mode 2),
+// 172 of drawing (mode 3) and the rest of the 456 in H-Blank (mode 0).
+
+use gameboy_core::{Emulator, Game};
+
+const STAT_ADDR: u16 = 0xFF41;
+const LY_ADDR: u16 = 0xFF44;
+const IF_ADDR: u16 = 0xFF0F;
+
+// Instructions run setting up before the NOPs
+const SETUP_STEPS: usize = 4;
+
+// The state after an instruction, and the cycle it finished on
+struct Sample {
+    cycle: u32,
+    mode: u8,
+    line: u8,
+    stat_requested: bool,
+}
+
+fn run(stat: u8, steps: usize) -> Vec<Sample> {
+    // Turns on the STAT interrupt sources in stat, then runs NOPs. IME stays off
+    // so a requested interrupt just sits in IF. Turning a source on while its mode
+    // is already going requests one straight away, so IF is cleared after
+    let mut rom = vec![0; 0x8000];
+    rom[0x100..0x107].copy_from_slice(&[
+        0x3E, stat, // LD A, stat
+        0xE0, 0x41, // LDH (0x41), A
+        0xAF,       // XOR A
+        0xE0, 0x0F, // LDH (0x0F), A
+    ]);
+
+    let mut emulator = Emulator::new(Game::from_bytes(&rom));
+    let mut cycle = 0;
+    (0..steps)
+        .map(|_| {
+            cycle += emulator.step_instruction().cycles;
+            Sample {
+                cycle,
+                mode: emulator.peek(STAT_ADDR) & 3,
+                line: emulator.peek(LY_ADDR),
+                stat_requested: emulator.peek(IF_ADDR) & 2 > 0,
+            }
+        })
+        .skip(SETUP_STEPS)
+        .collect()
+}
+
+fn get_mode_changes(samples: &[Sample]) -> Vec<(u32, u8, u8)> {
+    // The cycle, mode and line of every sample where the mode differs from the
+    // one before
+    samples
+        .windows(2)
+        .filter(|pair| pair[0].mode != pair[1].mode)
+        .map(|pair| (pair[1].cycle, pair[1].mode, pair[1].line))
+        .collect()
+}
+
+#[test]
+fn visible_line_mode_lengths() {
+    // A couple of frames, plenty to find whole lines in
+    let samples = run(0, 40000);
+    let changes = get_mode_changes(&samples);
+
+    // Start from an OAM scan well into the frame, so the line is a whole one
+    let start = changes
+        .iter()
+        .position(|(_, mode, line)| *mode == 2 && (10..140).contains(line))
+        .expect("should find a visible line");
+
+    let lengths: Vec<(u8, u32)> = changes[start..start + 4]
+        .windows(2)
+        .map(|pair| (pair[0].1, pair[1].0 - pair[0].0))
+        .collect();
+    assert_eq!(lengths, vec![(2, 80), (3, 172), (0, 204)]);
+}
+
+#[test]
+fn mode_0_interrupt_starts_with_h_blank() {
+    // STAT bit 3 requests the interrupt as mode 0 starts
+    let samples = run(8, 4000);
+    let requested = samples.iter().position(|s| s.stat_requested).expect("should request a STAT interrupt");
+
+    assert_eq!(samples[requested].mode, 0);
+    assert_eq!(samples[requested - 1].mode, 3);
+}
+
+#[test]
+fn mode_2_interrupt_starts_with_oam_scan() {
+    // STAT bit 5 requests the interrupt as mode 2 starts, 80 dots before mode 3
+    let samples = run(32, 4000);
+    let requested = samples.iter().position(|s| s.stat_requested).expect("should request a STAT interrupt");
+    assert_eq!(samples[requested].mode, 2);
+    assert_eq!(samples[requested - 1].mode, 0);
+
+    let mode_3 = samples[requested..].iter().find(|s| s.mode == 3).unwrap();
+    assert_eq!(mode_3.cycle - samples[requested].cycle, 80);
+}
+
+#[test]
+fn sprite_size_change_after_oam_scan() {
+    // The OAM scan picks a Y flipped 8x16 sprite for line 40, on its row 12, then
+    // sprites go to 8x8 before the line is drawn. Row 12 doesn't exist any more,
+    // drawing the line anyway shouldn't panic
+    let mut code = vec![
+        0xAF,             // XOR A
+        0xE0, 0x40,       // LDH (0x40), A - LCD off, so OAM can be written
+        0x21, 0x00, 0xFE, // LD HL, 0xFE00
+    ];
+    for byte in [40 - 12 + 16, 0x50, 0x00, 0x40].iter() {
+        code.extend([0x3E, *byte, 0x22]); // LD A, byte / LD (HL+), A
+    }
+
+    // Every frame, as the first one after turning the LCD on isn't drawn
+    code.extend([
+        0x3E, 0x97,       // LD A, 0x97 - LCD on, 8x16 sprites
+        0xE0, 0x40,       // LDH (0x40), A
+        0xF0, 0x44,       // LDH A, (0x44)
+        0xFE, 0x28,       // CP 40
+        0x20, 0xFA,       // JR NZ, -6
+        0x3E, 0x93,       // LD A, 0x93 - 8x8 sprites, still in mode 2
+        0xE0, 0x40,       // LDH (0x40), A
+        0xF0, 0x44,       // LDH A, (0x44)
+        0xFE, 0x29,       // CP 41
+        0x20, 0xFA,       // JR NZ, -6
+        0x18, 0xEA,       // JR -22
+    ]);
+
+    let mut rom = vec![0; 0x8000];
+    rom[0x100..0x103].copy_from_slice(&[0xC3, 0x50, 0x01]); // JP 0x150
+    rom[0x150..0x150 + code.len()].copy_from_slice(&code);
+
+    let mut emulator = Emulator::new(Game::from_bytes(&rom));
+    for _ in 0..3 {
+        emulator.update();
+    }
+}
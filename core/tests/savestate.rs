@@ -0,0 +1,86 @@
+// Savestates of carts whose mappers keep registers of their own. Each test runs a
+// little program that sets the mapper up, saves, and loads the state into a fresh
+// emulator, which should then see memory exactly as the first one did.
+
+use gameboy_core::{Emulator, Game};
+
+fn run_and_reload(rom: &[u8]) -> (Emulator, Emulator) {
+    let mut emulator = Emulator::new(Game::from_bytes(rom));
+    emulator.update();
+
+    let mut restored = Emulator::new(Game::from_bytes(rom));
+    restored.load_state(&emulator.save_state()).unwrap();
+    (emulator, restored)
+}
+
+fn store_a(address: u16, value: u8) -> Vec<u8> {
+    // LD A, value / LD (address), A
+    vec![0x3E, value, 0xEA, address as u8, (address >> 8) as u8]
+}
+
+#[test]
+fn mmm01_round_trip() {
+    // 8 banks, with the menu in the last two. The menu picks bank 2 as the game's
+    // bank 0, locks the mapping in and is never seen again
+    let mut rom = vec![0; 0x20000];
+    rom[0x147] = 0x01;
+    rom[0x18147] = 0x0B;
+    rom[0x8000] = 0xAB;
+    rom[0x18000] = 0xCD;
+
+    let mut code = Vec::new();
+    code.extend(store_a(0x6000, 0x3C)); // Mask every bank bit the game could change
+    code.extend(store_a(0x2000, 0x02)); // ROM bank 2
+    code.extend(store_a(0x0000, 0x40)); // Map and lock
+    rom[0x18100..0x18100 + code.len()].copy_from_slice(&code);
+
+    // Carries on in the game's bank once mapped
+    let end = 0x100 + code.len();
+    rom[0x8000 + end..0x8000 + end + 2].copy_from_slice(&[0x18, 0xFE]); // JR -2
+
+    let (emulator, restored) = run_and_reload(&rom);
+    assert_eq!(emulator.peek(0x0000), 0xAB);
+    assert_eq!(restored.peek(0x0000), 0xAB);
+    assert!(restored.mbc_state().rtc.is_empty());
+    assert_eq!(restored.dump_memory(), emulator.dump_memory());
+}
+
+#[test]
+fn tama5_round_trip() {
+    let mut rom = vec![0; 0x10000];
+    rom[0x147] = 0xFD;
+
+    // A001 selects a register, A000 writes it
+    let mut code = Vec::new();
+    let mut write_register = |register: u8, value: u8| {
+        code.extend(store_a(0xA001, register));
+        code.extend(store_a(0xA000, value));
+    };
+
+    // ROM bank 2
+    write_register(0x0, 0x2);
+
+    // 0x5A into EEPROM address 5 (command 0)
+    write_register(0x4, 0xA);
+    write_register(0x5, 0x5);
+    write_register(0x6, 0x0);
+    write_register(0x7, 0x5);
+
+    // 7 into RTC register 3 (command 2)
+    write_register(0x4, 0x7);
+    write_register(0x6, 0x2 << 1);
+    write_register(0x7, 0x3);
+
+    code.extend([0x18, 0xFE]); // JR -2
+
+    // Too long to fit before the header, so it goes after
+    rom[0x100..0x103].copy_from_slice(&[0xC3, 0x50, 0x01]); // JP 0x150
+    rom[0x150..0x150 + code.len()].copy_from_slice(&code);
+
+    let (emulator, restored) = run_and_reload(&rom);
+    assert_eq!(restored.mbc_state().rom_bank, 2);
+    assert_eq!(restored.rtc_registers()[3], 7);
+    assert_eq!(restored.mbc_state().rtc, restored.rtc_registers());
+    assert_eq!(restored.rtc_registers(), emulator.rtc_registers());
+    assert_eq!(restored.dump_memory(), emulator.dump_memory());
+}
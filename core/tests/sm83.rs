@@ -0,0 +1,45 @@
+// Single instruction test vectors for the CPU (https://github.com/SingleStepTests/sm83)
+//
+// The vectors are far too big to keep in the repo, so point SM83_TESTS_DIR at the
+// v1 directory of a checkout to run them. Without it this does nothing.
+
+use std::env;
+use std::fs;
+
+// Failures shown per file, the rest are only counted
+const MAX_SHOWN: usize = 5;
+
+#[test]
+fn sm83_test_vectors() {
+    let dir = match env::var("SM83_TESTS_DIR") {
+        Ok(dir) => dir,
+        Err(_)  => {
+            eprintln!("SM83_TESTS_DIR isn't set, skipping");
+            return;
+        }
+    };
+
+    let mut paths: Vec<_> = fs::read_dir(&dir)
+        .expect("SM83_TESTS_DIR should be a directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .collect();
+    paths.sort();
+
+    let mut failed_files = 0;
+    for path in paths.iter() {
+        let json = fs::read_to_string(path).unwrap();
+        let failures = gameboy_core::run_sm83_tests(&json).unwrap();
+        if failures.is_empty() {
+            continue;
+        }
+
+        failed_files += 1;
+        eprintln!("{}: {} failed", path.display(), failures.len());
+        for failure in failures.iter().take(MAX_SHOWN) {
+            eprintln!("  {}", failure);
+        }
+    }
+
+    assert_eq!(failed_files, 0, "{} of {} files had failures", failed_files, paths.len());
+}
@@ -0,0 +1,24 @@
+// The headless test ROM runner (see testrom.rs), checked with a little program
+// that reports its result the way blargg's do.
+
+use gameboy_core::{run_test_rom, TestRomStatus};
+
+#[test]
+fn serial_pass_is_captured() {
+    // Sends "Passed" a byte at a time: SB, then start the transfer in SC
+    let mut code = Vec::new();
+    for byte in b"Passed".iter() {
+        code.extend([0x3E, *byte, 0xE0, 0x01]); // LD A, byte / LDH (0x01), A
+        code.extend([0x3E, 0x81, 0xE0, 0x02]); // LD A, 0x81 / LDH (0x02), A
+    }
+    code.extend([0x18, 0xFE]); // JR -2
+
+    let mut rom = vec![0; 0x8000];
+    rom[0x100..0x103].copy_from_slice(&[0xC3, 0x50, 0x01]); // JP 0x150
+    rom[0x150..0x150 + code.len()].copy_from_slice(&code);
+
+    let result = run_test_rom(&rom, 10);
+    assert_eq!(result.status, TestRomStatus::Passed);
+    assert_eq!(result.serial_output, "Passed");
+    assert_eq!(result.frames_run, 1);
+}
@@ -0,0 +1,35 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+// Canvas output
+//
+// For frontends that would rather not write any rendering code themselves. Once a
+// canvas is attached the emulator draws every finished frame into it straight from
+// Rust, so all the page has to do is keep calling update.
+
+pub struct Canvas {
+    context: CanvasRenderingContext2d,
+}
+
+impl Canvas {
+    pub fn new(canvas: &HtmlCanvasElement) -> Result<Canvas, JsValue> {
+        // The canvas is sized to the LCD, use CSS to show it any bigger
+        canvas.set_width(160);
+        canvas.set_height(144);
+
+        let context = canvas
+            .get_context("2d")?
+            .ok_or_else(|| JsValue::from_str("Canvas has no 2d context"))?
+            .dyn_into::<CanvasRenderingContext2d>()?;
+        context.set_image_smoothing_enabled(false);
+
+        Ok(Canvas { context })
+    }
+
+    pub fn draw(&self, frame: &[u8]) -> Result<(), JsValue> {
+        // frame is 160x144 RGBA, the same as get_frame hands out
+        let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(frame), 160, 144)?;
+        self.context.put_image_data(&image, 0.0, 0.0)
+    }
+}
@@ -1,31 +1,49 @@
-use super::utils;
-
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
 pub struct Game {
-    memory_bank_one:   [u8; 0x8000],
-    memory_bank_two:   [u8; 0x8000],
-    memory_bank_three: [u8; 0x8000],
-    memory_bank_four:  [u8; 0x8000],
+    game: gameboy_core::Game,
 }
 
 #[wasm_bindgen]
 impl Game {
     pub fn new() -> Game {
         Game {
-            memory_bank_one:   [0; 0x8000],
-            memory_bank_two:   [0; 0x8000],
-            memory_bank_three: [0; 0x8000],
-            memory_bank_four:  [0; 0x8000],
+            game: gameboy_core::Game::new(),
+        }
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Game {
+        Game {
+            game: gameboy_core::Game::from_bytes(data),
         }
     }
 
     pub fn load_game_memory() {
-        log!("Loading game");
+        gameboy_core::Game::load_game_memory();
     }
 
     pub fn read_catridge_data(&self, address: usize) -> u8 {
-        return 0;
+        self.game.read_catridge_data(address)
+    }
+
+    pub fn size(&self) -> usize {
+        self.game.size()
+    }
+
+    pub fn rom_bank_count(&self) -> usize {
+        self.game.rom_bank_count()
+    }
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
+}
+
+impl Game {
+    pub fn into_inner(self) -> gameboy_core::Game {
+        self.game
     }
 }
@@ -1,17 +1,20 @@
-#[macro_use]
-mod utils;
+// wasm bindings
+//
+// A thin layer over gameboy-core that exposes the emulator to JS. Anything that
+// isn't about crossing the JS boundary belongs in the core.
 
-mod cpu;
+mod canvas;
 mod game;
-mod mmu;
-mod ops;
+mod types;
+mod utils;
 
 extern crate js_sys;
 extern crate web_sys;
 
 use wasm_bindgen::prelude::*;
 
-use std::collections::HashMap;
+pub use game::Game;
+pub use types::*;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -21,36 +24,378 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 #[wasm_bindgen]
 pub struct Emulator {
-    cpu: cpu::Cpu,
+    emulator: gameboy_core::Emulator,
+
+    // Where finished frames get drawn, if a canvas has been attached
+    canvas: Option<canvas::Canvas>,
 }
 
 #[wasm_bindgen]
 impl Emulator {
-    pub fn new(game: game::Game) -> Emulator {
+    pub fn new(game: Game) -> Emulator {
+        utils::init();
+
         Emulator {
-            cpu: cpu::Cpu::new(game),
+            emulator: gameboy_core::Emulator::new(game.into_inner()),
+            canvas: None,
         }
     }
 
+    pub fn load_boot_rom(&mut self, boot_rom: &[u8]) -> Result<(), JsValue> {
+        self.emulator.load_boot_rom(boot_rom).map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn set_model(&mut self, model: Model) {
+        self.emulator.set_model(model.into());
+    }
+
     pub fn update(&mut self) {
-        // Gameboy can execute 4194304 cycles per second and
-        // we will be emulating at 60 fps. In other words, this
-        // function should be called 60 times per second as it represents
-        // a single frame update
-
-        // 4194304/60 = 66905
-        let max_cycles_per_frame = 69905;
-        let mut cycles_this_update = 0;
-
-        while cycles_this_update < max_cycles_per_frame {
-            let cycles = self.cpu.execute_op();
-            cycles_this_update += cycles;
-
-            self.cpu.update_timers(&cycles);
-            self.cpu.update_graphics(&cycles);
-            self.cpu.do_interrupts();
-        }
+        self.emulator.update();
+        self.draw_to_canvas();
+    }
+
+    pub fn update_audio_paced(&mut self, samples_consumed: u32, buffer_fill: u32) {
+        self.emulator.update_audio_paced(samples_consumed, buffer_fill);
+        self.draw_to_canvas();
+    }
+
+    pub fn attach_canvas(&mut self, canvas: web_sys::HtmlCanvasElement) -> Result<(), JsValue> {
+        // From now on every update draws the frame (as get_frame gives it) into
+        // the canvas, which is resized to 160x144
+        self.canvas = Some(canvas::Canvas::new(&canvas)?);
+        Ok(())
+    }
+
+    pub fn detach_canvas(&mut self) {
+        self.canvas = None;
+    }
+
+    pub fn set_audio_pacing(&mut self, sample_rate: u32, target_buffer: u32) {
+        self.emulator.set_audio_pacing(sample_rate, target_buffer);
+    }
+
+    pub fn id(&self) -> u32 {
+        self.emulator.id()
+    }
+
+    pub fn set_log_prefix(&mut self, prefix: &str) {
+        self.emulator.set_log_prefix(prefix);
+    }
+
+    pub fn set_idle_skipping(&mut self, enabled: bool) {
+        self.emulator.set_idle_skipping(enabled);
+    }
+
+    pub fn set_pixel_fifo(&mut self, enabled: bool) {
+        self.emulator.set_pixel_fifo(enabled);
+    }
+
+    pub fn set_frame_skip(&mut self, frames: u8) {
+        self.emulator.set_frame_skip(frames);
+    }
+
+    pub fn set_oam_bug(&mut self, enabled: bool) {
+        self.emulator.set_oam_bug(enabled);
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        self.emulator.set_speed(speed);
+    }
+
+    pub fn set_palette(&mut self, palette: GraphicsPalette, colors: &[u8]) {
+        self.emulator.set_palette(palette.into(), colors);
+    }
+
+    pub fn set_palette_color(&mut self, palette: GraphicsPalette, shade: u8, red: u8, green: u8, blue: u8) {
+        self.emulator.set_palette_color(palette.into(), shade, red, green, blue);
+    }
+
+    pub fn set_palette_preset(&mut self, preset: PalettePreset) {
+        self.emulator.set_palette_preset(preset.into());
+    }
+
+    pub fn set_color_correction(&mut self, correction: ColorCorrection) {
+        self.emulator.set_color_correction(correction.into());
+    }
+
+    pub fn convert_cgb_color(&self, color: u16) -> Vec<u8> {
+        self.emulator.convert_cgb_color(color)
+    }
+
+    pub fn set_display_adjustments(&mut self, brightness: f32, contrast: f32, gamma: f32) {
+        self.emulator.set_display_adjustments(brightness, contrast, gamma);
+    }
+
+    pub fn get_frame(&self) -> Vec<u8> {
+        self.emulator.get_frame()
+    }
+
+    pub fn screenshot_png(&self) -> Result<Vec<u8>, JsValue> {
+        self.emulator.screenshot_png().map_err(|e| JsValue::from_str(&e))
+    }
+
+    // With the module's memory, new Uint8ClampedArray(memory.buffer, screen_ptr(),
+    // screen_len()) views the frame without copying it, ready for an ImageData
+    pub fn screen_ptr(&self) -> *const u8 {
+        self.emulator.screen_ptr()
+    }
+
+    pub fn screen_len(&self) -> usize {
+        self.emulator.screen_len()
+    }
+
+    pub fn osd_message(&mut self, text: &str, frames: u32) {
+        self.emulator.osd_message(text, frames);
+    }
+
+    pub fn clear_osd(&mut self) {
+        self.emulator.clear_osd();
+    }
+
+    pub fn set_frame_blending(&mut self, enabled: bool) {
+        self.emulator.set_frame_blending(enabled);
+    }
+
+    pub fn set_layer_enabled(&mut self, layer: Layer, enabled: bool) {
+        self.emulator.set_layer_enabled(layer.into(), enabled);
+    }
+
+    pub fn set_lcd_grid(&mut self, strength: f32) {
+        self.emulator.set_lcd_grid(strength);
+    }
+
+    pub fn get_scaled_frame(&self, scale: usize) -> Vec<u8> {
+        self.emulator.get_scaled_frame(scale)
+    }
+
+    pub fn set_scale(&mut self, scale: usize) {
+        self.emulator.set_scale(scale);
+    }
+
+    pub fn get_scale(&self) -> usize {
+        self.emulator.get_scale()
+    }
+
+    // Like screen_ptr, but for the upscaled frame set_scale keeps. It's
+    // (160 * scale) x (144 * scale), look the pointer up again after changing scale
+    pub fn scaled_frame_ptr(&self) -> *const u8 {
+        self.emulator.scaled_frame_ptr()
+    }
+
+    pub fn scaled_frame_len(&self) -> usize {
+        self.emulator.scaled_frame_len()
+    }
+
+    pub fn export_config(&self) -> String {
+        self.emulator.export_config()
+    }
+
+    pub fn import_config(&mut self, json: &str) -> Result<(), JsValue> {
+        self.emulator.import_config(json).map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn set_joypad(&mut self, player: u8, state: u8) {
+        self.emulator.set_joypad(player, state);
+    }
+
+    pub fn set_sgb_multiplayer(&mut self, enabled: bool) {
+        self.emulator.set_sgb_multiplayer(enabled);
+    }
+
+    pub fn add_breakpoint(&mut self, pc: u16) {
+        self.emulator.add_breakpoint(pc);
+    }
+
+    pub fn remove_breakpoint(&mut self, pc: u16) {
+        self.emulator.remove_breakpoint(pc);
+    }
+
+    pub fn break_on_interrupt(&mut self, bit: u8) {
+        self.emulator.break_on_interrupt(bit);
+    }
+
+    pub fn break_on_mode(&mut self, mode: u8) {
+        self.emulator.break_on_mode(mode);
+    }
+
+    pub fn break_on_bank_switch(&mut self) {
+        self.emulator.break_on_bank_switch();
+    }
+
+    pub fn clear_event_breakpoints(&mut self) {
+        self.emulator.clear_event_breakpoints();
+    }
+
+    pub fn break_info(&self) -> Option<BreakInfo> {
+        self.emulator.break_info().map(BreakInfo::from)
+    }
+
+    pub fn resume(&mut self) {
+        self.emulator.resume();
+    }
+
+    pub fn step_instruction(&mut self) -> StepInfo {
+        self.emulator.step_instruction().into()
+    }
+
+    pub fn mbc_state(&self) -> MbcState {
+        self.emulator.mbc_state().into()
+    }
+
+    pub fn enable_bank_log(&mut self, capacity: usize) {
+        self.emulator.enable_bank_log(capacity);
+    }
+
+    pub fn disable_bank_log(&mut self) {
+        self.emulator.disable_bank_log();
+    }
+
+    pub fn clear_bank_log(&mut self) {
+        self.emulator.clear_bank_log();
+    }
+
+    pub fn bank_log(&self) -> Vec<BankSwitchEntry> {
+        self.emulator.bank_log().into_iter().map(BankSwitchEntry::from).collect()
+    }
+
+    pub fn get_registers(&self) -> Registers {
+        self.emulator.get_registers().into()
+    }
+
+    pub fn set_register(&mut self, name: &str, value: u16) -> Result<(), JsValue> {
+        self.emulator.set_register(name, value).map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn timer_state(&self) -> TimerState {
+        self.emulator.timer_state().into()
+    }
+
+    pub fn lock_info(&self) -> Option<LockInfo> {
+        self.emulator.lock_info().map(LockInfo::from)
+    }
+
+    pub fn set_ppu_timeline(&mut self, enabled: bool) {
+        self.emulator.set_ppu_timeline(enabled);
+    }
+
+    pub fn ppu_timeline(&self) -> Result<String, JsValue> {
+        self.emulator.ppu_timeline().map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn ppu_stats(&self) -> Result<String, JsValue> {
+        self.emulator.ppu_stats().map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn set_trace(&mut self, enabled: bool) {
+        self.emulator.set_trace(enabled);
+    }
+
+    pub fn set_trace_callback(&mut self, callback: Option<js_sys::Function>) {
+        // The callback gets the instance id and each trace line as a string, pass
+        // nothing to go back to collecting them for take_trace
+        let callback = callback.map(|callback| {
+            Box::new(move |id: u32, line: &str| {
+                let _ = callback.call2(&JsValue::NULL, &JsValue::from(id), &JsValue::from_str(line));
+            }) as gameboy_core::TraceCallback
+        });
+
+        self.emulator.set_trace_callback(callback);
+    }
+
+    pub fn on_vblank(&mut self, callback: Option<js_sys::Function>) {
+        // The callback gets the instance id and the frame number as each frame
+        // finishes, during update. Pass nothing to stop it
+        let callback = callback.map(|callback| {
+            Box::new(move |id: u32, frame_number: u32| {
+                let _ = callback.call2(&JsValue::NULL, &JsValue::from(id), &JsValue::from(frame_number));
+            }) as gameboy_core::VBlankCallback
+        });
 
-        // Frame Update
+        self.emulator.on_vblank(callback);
     }
+
+    pub fn take_trace(&mut self) -> String {
+        self.emulator.take_trace()
+    }
+
+    pub fn rtc_registers(&self) -> Vec<u8> {
+        self.emulator.rtc_registers()
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        self.emulator.save_state()
+    }
+
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), JsValue> {
+        self.emulator.load_state(state).map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn diff_states(&self, before: &[u8], after: &[u8]) -> Result<String, JsValue> {
+        self.emulator.diff_states(before, after).map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn diff_with_current(&self, state: &[u8]) -> Result<String, JsValue> {
+        self.emulator.diff_with_current(state).map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn export_tile(&self, tile: usize, palette: GraphicsPalette) -> Vec<u8> {
+        self.emulator.export_tile(tile, palette.into())
+    }
+
+    pub fn export_sprite_sheet(&self, palette: GraphicsPalette) -> Vec<u8> {
+        self.emulator.export_sprite_sheet(palette.into())
+    }
+
+    pub fn debug_tiles(&self) -> Vec<u8> {
+        self.emulator.debug_tiles()
+    }
+
+    pub fn debug_palette(&self, palette: GraphicsPalette) -> Vec<u8> {
+        self.emulator.debug_palette(palette.into())
+    }
+
+    pub fn debug_oam(&self) -> Result<String, JsValue> {
+        self.emulator.debug_oam().map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn debug_oam_thumbnails(&self) -> Vec<u8> {
+        self.emulator.debug_oam_thumbnails()
+    }
+
+    pub fn export_tilemap(&self, layer: TilemapLayer) -> Vec<u8> {
+        self.emulator.export_tilemap(layer.into())
+    }
+
+    pub fn debug_tilemap(&self, map: TileMap, data: TileData, show_viewport: bool) -> Vec<u8> {
+        self.emulator.debug_tilemap(map.into(), data.into(), show_viewport)
+    }
+
+    pub fn dump_memory(&self) -> Vec<u8> {
+        self.emulator.dump_memory()
+    }
+
+    pub fn peek(&self, address: u16) -> u8 {
+        self.emulator.peek(address)
+    }
+
+    pub fn peek_range(&self, address: u16, length: usize) -> Vec<u8> {
+        self.emulator.peek_range(address, length)
+    }
+}
+
+impl Emulator {
+    fn draw_to_canvas(&self) {
+        // A failed draw only loses a frame, the next update tries again
+        if let Some(canvas) = &self.canvas {
+            let _ = canvas.draw(&self.emulator.get_frame());
+        }
+    }
+}
+
+// Runs a test ROM headless for up to max_frames frames, stopping early once it
+// reports a pass or fail
+#[wasm_bindgen]
+pub fn run_test_rom(rom: &[u8], max_frames: u32) -> TestRomResult {
+    utils::init();
+    gameboy_core::run_test_rom(rom, max_frames).into()
 }
@@ -0,0 +1,383 @@
+use wasm_bindgen::prelude::*;
+
+// JS facing copies of the core's enums and debug structs. The core knows nothing
+// about wasm_bindgen, so every type handed across the boundary gets a twin here
+// with a conversion to/from the core version. See the core for what the fields mean.
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorCorrection {
+    None = 0,
+    Cgb = 1,
+    Agb = 2,
+}
+
+impl From<ColorCorrection> for gameboy_core::ColorCorrection {
+    fn from(correction: ColorCorrection) -> gameboy_core::ColorCorrection {
+        match correction {
+            ColorCorrection::None => gameboy_core::ColorCorrection::None,
+            ColorCorrection::Cgb  => gameboy_core::ColorCorrection::Cgb,
+            ColorCorrection::Agb  => gameboy_core::ColorCorrection::Agb,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PalettePreset {
+    Gray = 0,
+    Green = 1,
+    Pocket = 2,
+}
+
+impl From<PalettePreset> for gameboy_core::PalettePreset {
+    fn from(preset: PalettePreset) -> gameboy_core::PalettePreset {
+        match preset {
+            PalettePreset::Gray   => gameboy_core::PalettePreset::Gray,
+            PalettePreset::Green  => gameboy_core::PalettePreset::Green,
+            PalettePreset::Pocket => gameboy_core::PalettePreset::Pocket,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Layer {
+    Background = 0,
+    Window = 1,
+    Sprites = 2,
+}
+
+impl From<Layer> for gameboy_core::Layer {
+    fn from(layer: Layer) -> gameboy_core::Layer {
+        match layer {
+            Layer::Background => gameboy_core::Layer::Background,
+            Layer::Window     => gameboy_core::Layer::Window,
+            Layer::Sprites    => gameboy_core::Layer::Sprites,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Model {
+    Dmg = 0,
+    Mgb = 1,
+}
+
+impl From<Model> for gameboy_core::Model {
+    fn from(model: Model) -> gameboy_core::Model {
+        match model {
+            Model::Dmg => gameboy_core::Model::Dmg,
+            Model::Mgb => gameboy_core::Model::Mgb,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GraphicsPalette {
+    Bgp = 0,
+    Obp0 = 1,
+    Obp1 = 2,
+}
+
+impl From<GraphicsPalette> for gameboy_core::GraphicsPalette {
+    fn from(palette: GraphicsPalette) -> gameboy_core::GraphicsPalette {
+        match palette {
+            GraphicsPalette::Bgp  => gameboy_core::GraphicsPalette::Bgp,
+            GraphicsPalette::Obp0 => gameboy_core::GraphicsPalette::Obp0,
+            GraphicsPalette::Obp1 => gameboy_core::GraphicsPalette::Obp1,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TilemapLayer {
+    Background = 0,
+    Window = 1,
+}
+
+impl From<TilemapLayer> for gameboy_core::TilemapLayer {
+    fn from(layer: TilemapLayer) -> gameboy_core::TilemapLayer {
+        match layer {
+            TilemapLayer::Background => gameboy_core::TilemapLayer::Background,
+            TilemapLayer::Window     => gameboy_core::TilemapLayer::Window,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TileMap {
+    Map9800 = 0,
+    Map9C00 = 1,
+}
+
+impl From<TileMap> for gameboy_core::TileMap {
+    fn from(map: TileMap) -> gameboy_core::TileMap {
+        match map {
+            TileMap::Map9800 => gameboy_core::TileMap::Map9800,
+            TileMap::Map9C00 => gameboy_core::TileMap::Map9C00,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TileData {
+    Data8000 = 0,
+    Data8800 = 1,
+}
+
+impl From<TileData> for gameboy_core::TileData {
+    fn from(data: TileData) -> gameboy_core::TileData {
+        match data {
+            TileData::Data8000 => gameboy_core::TileData::Data8000,
+            TileData::Data8800 => gameboy_core::TileData::Data8800,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BreakKind {
+    Breakpoint = 0,
+    Interrupt = 1,
+    ModeChange = 2,
+    RomBankSwitch = 3,
+    RamBankSwitch = 4,
+}
+
+impl From<gameboy_core::BreakKind> for BreakKind {
+    fn from(kind: gameboy_core::BreakKind) -> BreakKind {
+        match kind {
+            gameboy_core::BreakKind::Breakpoint    => BreakKind::Breakpoint,
+            gameboy_core::BreakKind::Interrupt     => BreakKind::Interrupt,
+            gameboy_core::BreakKind::ModeChange    => BreakKind::ModeChange,
+            gameboy_core::BreakKind::RomBankSwitch => BreakKind::RomBankSwitch,
+            gameboy_core::BreakKind::RamBankSwitch => BreakKind::RamBankSwitch,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug)]
+pub struct BreakInfo {
+    pub instance: u32,
+    pub kind: BreakKind,
+    pub value: u16,
+    pub pc: u16,
+    pub scanline: u8,
+}
+
+impl From<gameboy_core::BreakInfo> for BreakInfo {
+    fn from(info: gameboy_core::BreakInfo) -> BreakInfo {
+        BreakInfo {
+            instance: info.instance,
+            kind: info.kind.into(),
+            value: info.value,
+            pc: info.pc,
+            scanline: info.scanline,
+        }
+    }
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct StepInfo {
+    pub bytes: Vec<u8>,
+    pub mnemonic: String,
+    pub cycles: u32,
+    pub pc: u16,
+}
+
+impl From<gameboy_core::StepInfo> for StepInfo {
+    fn from(info: gameboy_core::StepInfo) -> StepInfo {
+        StepInfo {
+            bytes: info.bytes,
+            mnemonic: info.mnemonic,
+            cycles: info.cycles,
+            pc: info.pc,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug)]
+pub struct BankSwitchEntry {
+    pub address: u16,
+    pub value: u8,
+    pub rom_bank: u8,
+    pub ram_bank: u8,
+    pub pc: u16,
+}
+
+impl From<gameboy_core::BankSwitchEntry> for BankSwitchEntry {
+    fn from(entry: gameboy_core::BankSwitchEntry) -> BankSwitchEntry {
+        BankSwitchEntry {
+            address: entry.address,
+            value: entry.value,
+            rom_bank: entry.rom_bank,
+            ram_bank: entry.ram_bank,
+            pc: entry.pc,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MapperType {
+    None = 0,
+    Mbc1 = 1,
+    Mbc2 = 2,
+    Mmm01 = 3,
+    WisdomTree = 4,
+    Tama5 = 5,
+}
+
+impl From<gameboy_core::MapperType> for MapperType {
+    fn from(mapper: gameboy_core::MapperType) -> MapperType {
+        match mapper {
+            gameboy_core::MapperType::None       => MapperType::None,
+            gameboy_core::MapperType::Mbc1       => MapperType::Mbc1,
+            gameboy_core::MapperType::Mbc2       => MapperType::Mbc2,
+            gameboy_core::MapperType::Mmm01      => MapperType::Mmm01,
+            gameboy_core::MapperType::WisdomTree => MapperType::WisdomTree,
+            gameboy_core::MapperType::Tama5      => MapperType::Tama5,
+        }
+    }
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct MbcState {
+    pub mapper: MapperType,
+    pub rom_bank: u8,
+    pub ram_bank: u8,
+    pub ram_enabled: bool,
+    pub rom_banking_mode: bool,
+    pub rtc: Vec<u8>,
+}
+
+impl From<gameboy_core::MbcState> for MbcState {
+    fn from(state: gameboy_core::MbcState) -> MbcState {
+        MbcState {
+            mapper: state.mapper.into(),
+            rom_bank: state.rom_bank,
+            ram_bank: state.ram_bank,
+            ram_enabled: state.ram_enabled,
+            rom_banking_mode: state.rom_banking_mode,
+            rtc: state.rtc,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug)]
+pub struct TimerState {
+    pub div: u8,
+    pub internal_counter: u16,
+    pub tima: u8,
+    pub tma: u8,
+    pub tac: u8,
+    pub timer_enabled: bool,
+    pub cycles_until_increment: u32,
+}
+
+impl From<gameboy_core::TimerState> for TimerState {
+    fn from(state: gameboy_core::TimerState) -> TimerState {
+        TimerState {
+            div: state.div,
+            internal_counter: state.internal_counter,
+            tima: state.tima,
+            tma: state.tma,
+            tac: state.tac,
+            timer_enabled: state.timer_enabled,
+            cycles_until_increment: state.cycles_until_increment,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug)]
+pub struct Registers {
+    pub af: u16,
+    pub bc: u16,
+    pub de: u16,
+    pub hl: u16,
+    pub sp: u16,
+    pub pc: u16,
+    pub ime: bool,
+    pub halted: bool,
+}
+
+impl From<gameboy_core::Registers> for Registers {
+    fn from(registers: gameboy_core::Registers) -> Registers {
+        Registers {
+            af: registers.af,
+            bc: registers.bc,
+            de: registers.de,
+            hl: registers.hl,
+            sp: registers.sp,
+            pc: registers.pc,
+            ime: registers.ime,
+            halted: registers.halted,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug)]
+pub struct LockInfo {
+    pub pc: u16,
+    pub opcode: u8,
+}
+
+impl From<gameboy_core::LockInfo> for LockInfo {
+    fn from(info: gameboy_core::LockInfo) -> LockInfo {
+        LockInfo {
+            pc: info.pc,
+            opcode: info.opcode,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TestRomStatus {
+    Unknown = 0,
+    Passed = 1,
+    Failed = 2,
+}
+
+impl From<gameboy_core::TestRomStatus> for TestRomStatus {
+    fn from(status: gameboy_core::TestRomStatus) -> TestRomStatus {
+        match status {
+            gameboy_core::TestRomStatus::Unknown => TestRomStatus::Unknown,
+            gameboy_core::TestRomStatus::Passed  => TestRomStatus::Passed,
+            gameboy_core::TestRomStatus::Failed  => TestRomStatus::Failed,
+        }
+    }
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct TestRomResult {
+    pub status: TestRomStatus,
+    pub serial_output: String,
+    pub screen_hash: String,
+    pub frames_run: u32,
+}
+
+impl From<gameboy_core::TestRomResult> for TestRomResult {
+    fn from(result: gameboy_core::TestRomResult) -> TestRomResult {
+        TestRomResult {
+            status: result.status.into(),
+            serial_output: result.serial_output,
+            screen_hash: result.screen_hash,
+            frames_run: result.frames_run,
+        }
+    }
+}